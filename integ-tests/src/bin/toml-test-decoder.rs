@@ -0,0 +1,37 @@
+//! A [toml-test](https://github.com/toml-lang/toml-test) decoder, which reads a
+//! toml document on stdin and writes it as tagged JSON on stdout, or exits
+//! with an error if the document is invalid
+//!
+//! The document is parsed as TOML 1.1 unless `--toml 1.0` is passed.
+
+use std::{io::Read, process::ExitCode};
+use toml_span::TomlVersion;
+
+fn main() -> ExitCode {
+    let args: Vec<_> = std::env::args().skip(1).collect();
+    let version = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] | ["--toml", "1.1" | "1.1.0"] => TomlVersion::V1_1,
+        ["--toml", "1.0" | "1.0.0"] => TomlVersion::V1_0,
+        _ => {
+            eprintln!("usage: toml-test-decoder [--toml <1.0|1.1>]");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut toml = Vec::new();
+    if let Err(err) = std::io::stdin().read_to_end(&mut toml) {
+        eprintln!("failed to read stdin: {err}");
+        return ExitCode::FAILURE;
+    }
+
+    match integ_tests::toml_test::decode(&toml, version) {
+        Ok(json) => {
+            println!("{json}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,5 +1,18 @@
 #![allow(missing_docs)]
 
+pub mod toml_test;
+
+/// Asserts a json snapshot of the value with the keys of every table sorted,
+/// so that the snapshot is the same whether `preserve_order` is enabled or not
+#[macro_export]
+macro_rules! assert_sorted_json_snapshot {
+    ($value:expr) => {
+        insta::with_settings!({ sort_maps => true }, {
+            insta::assert_json_snapshot!($value);
+        });
+    };
+}
+
 /// Loads a valid toml file and does a snapshot assertion against `toml`
 #[macro_export]
 macro_rules! valid {
@@ -9,7 +22,7 @@ macro_rules! valid {
             let toml_str = std::fs::read_to_string(concat!("data/", stringify!($name), ".toml"))
                 .expect(concat!("failed to load ", stringify!($name), ".toml"));
             let valid_toml = toml_span::parse(&toml_str).expect("failed to parse toml");
-            insta::assert_json_snapshot!(valid_toml);
+            $crate::assert_sorted_json_snapshot!(valid_toml);
 
             $crate::emit_spans!($name, valid_toml, &toml_str);
         }
@@ -18,7 +31,7 @@ macro_rules! valid {
         #[test]
         fn $name() {
             let valid_toml = toml_span::parse($toml).expect("failed to parse toml");
-            insta::assert_json_snapshot!(valid_toml);
+            $crate::assert_sorted_json_snapshot!(valid_toml);
 
             $crate::emit_spans!($name, valid_toml, $toml);
         }
@@ -160,6 +173,7 @@ macro_rules! error_snapshot {
 }
 
 use codespan_reporting::diagnostic::Diagnostic;
+use toml_span::value::{Key, Table, Value};
 
 pub fn collect_spans(
     key: &str,
@@ -174,6 +188,7 @@ pub fn collect_spans(
         ValueInner::Integer(_s) => "integer",
         ValueInner::Float(_s) => "float",
         ValueInner::Boolean(_s) => "bool",
+        ValueInner::Datetime(_s) => "datetime",
         ValueInner::Array(arr) => {
             for (i, v) in arr.iter().enumerate() {
                 collect_spans(&format!("{key}_{i}"), v, diags);
@@ -182,7 +197,7 @@ pub fn collect_spans(
             "array"
         }
         ValueInner::Table(tab) => {
-            for (k, v) in tab {
+            for (k, v) in sorted(tab) {
                 collect_spans(&format!("{key}_{}", k.name), v, diags);
             }
 
@@ -233,3 +248,36 @@ macro_rules! invalid {
         }
     };
 }
+
+/// Parses toml with error recovery and does a snapshot assertion on all of the
+/// errors, as well as the value that could be recovered
+#[macro_export]
+macro_rules! recovering {
+    ($name:ident, $toml:expr) => {
+        #[test]
+        fn $name() {
+            let (value, errors) = toml_span::parse_recovering($toml);
+
+            // The first error is always the same one that is returned by a
+            // regular parse
+            let error = toml_span::parse($toml).unwrap_err();
+            assert_eq!(errors[0].to_string(), error.to_string());
+            assert_eq!(errors[0].span, error.span);
+
+            $crate::error_snapshot!(
+                $name,
+                errors.into_iter().map(|err| err.to_diagnostic(())),
+                $toml
+            );
+            $crate::assert_sorted_json_snapshot!(value);
+        }
+    };
+}
+
+/// Returns the entries of the table sorted by key, regardless of whether
+/// `preserve_order` is enabled
+pub fn sorted<'t, 'de>(table: &'t Table<'de>) -> Vec<(&'t Key<'de>, &'t Value<'de>)> {
+    let mut entries: Vec<_> = table.iter().collect();
+    entries.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+    entries
+}
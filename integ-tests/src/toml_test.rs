@@ -0,0 +1,104 @@
+//! Support for running the [toml-test](https://github.com/toml-lang/toml-test)
+//! suite, whose decoders write documents out in a tagged JSON format
+
+use serde_json::{Map, Value as Json, json};
+use toml_span::{
+    Datetime, ParseOptions, TomlVersion,
+    value::{Value, ValueInner},
+};
+
+/// Converts a parsed document into the tagged JSON format, where every
+/// scalar is an object with its `type` and its `value` as a string
+pub fn to_json(value: &Value<'_>) -> Json {
+    let tagged = |ty: &str, value: String| json!({ "type": ty, "value": value });
+
+    match value.as_ref() {
+        ValueInner::String(s) => tagged("string", s.to_string()),
+        ValueInner::Integer(i) => tagged("integer", i.to_string()),
+        ValueInner::Float(f) => tagged("float", float(*f)),
+        ValueInner::Boolean(b) => tagged("bool", b.to_string()),
+        ValueInner::Datetime(dt) => {
+            let ty = match (dt.date.is_some(), dt.time.is_some(), dt.offset.is_some()) {
+                (true, true, true) => "datetime",
+                (true, true, false) => "datetime-local",
+                (true, false, _) => "date-local",
+                (false, ..) => "time-local",
+            };
+            tagged(ty, dt.to_string())
+        }
+        ValueInner::Array(arr) => Json::Array(arr.iter().map(to_json).collect()),
+        ValueInner::Table(table) => Json::Object(
+            table
+                .iter()
+                .map(|(k, v)| (k.name.to_string(), to_json(v)))
+                .collect::<Map<_, _>>(),
+        ),
+    }
+}
+
+fn float(f: f64) -> String {
+    if f.is_nan() {
+        "nan".into()
+    } else if f.is_infinite() {
+        if f < 0.0 { "-inf" } else { "inf" }.into()
+    } else {
+        f.to_string()
+    }
+}
+
+/// Decodes a document as a toml-test decoder does, returning the error
+/// message if it is invalid
+pub fn decode(toml: &[u8], version: TomlVersion) -> Result<Json, String> {
+    let toml = std::str::from_utf8(toml).map_err(|err| err.to_string())?;
+    let options = ParseOptions {
+        version,
+        ..Default::default()
+    };
+    let doc = toml_span::parse_with(toml, &options).map_err(|err| err.to_string())?;
+    Ok(to_json(&doc.root))
+}
+
+/// Compares decoded JSON with the expected JSON of a valid test, comparing
+/// floats and datetimes by value rather than by how they are written
+pub fn matches(expected: &Json, actual: &Json) -> bool {
+    match (expected, actual) {
+        (Json::Object(expected), Json::Object(actual)) => {
+            if let (Some(Json::String(ty)), Some(Json::String(value))) =
+                (expected.get("type"), expected.get("value"))
+            {
+                let (Some(Json::String(aty)), Some(Json::String(avalue))) =
+                    (actual.get("type"), actual.get("value"))
+                else {
+                    return false;
+                };
+                return ty == aty && scalar_matches(ty, value, avalue);
+            }
+
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .all(|(k, v)| actual.get(k).is_some_and(|a| matches(v, a)))
+        }
+        (Json::Array(expected), Json::Array(actual)) => {
+            expected.len() == actual.len()
+                && expected.iter().zip(actual).all(|(e, a)| matches(e, a))
+        }
+        _ => false,
+    }
+}
+
+fn scalar_matches(ty: &str, expected: &str, actual: &str) -> bool {
+    match ty {
+        "float" => match (expected.parse::<f64>(), actual.parse::<f64>()) {
+            (Ok(e), Ok(a)) => e == a || (e.is_nan() && a.is_nan()),
+            _ => false,
+        },
+        "datetime" | "datetime-local" | "date-local" | "time-local" => {
+            match (expected.parse::<Datetime>(), actual.parse::<Datetime>()) {
+                (Ok(e), Ok(a)) => e == a,
+                _ => false,
+            }
+        }
+        _ => expected == actual,
+    }
+}
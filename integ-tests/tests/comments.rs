@@ -0,0 +1,120 @@
+use std::fmt::Write;
+use toml_span::{
+    ParseOptions,
+    comments::{Comments, ItemComments},
+    value::{Value, ValueInner},
+};
+
+fn write(out: &mut String, kind: &str, pointer: &str, item: Option<&ItemComments<'_>>) {
+    let Some(item) = item else {
+        return;
+    };
+
+    writeln!(out, "{kind} '{pointer}'").unwrap();
+    for comment in &item.leading {
+        writeln!(out, "  leading {:?} {:?}", comment.text(), comment.span).unwrap();
+    }
+    if let Some(comment) = &item.trailing {
+        writeln!(out, "  trailing {:?} {:?}", comment.text(), comment.span).unwrap();
+    }
+}
+
+/// Walks the document, writing out the comments attached to every key and
+/// value along with its pointer
+fn collect(comments: &Comments<'_>, value: &Value<'_>, pointer: &str, out: &mut String) {
+    write(out, "value", pointer, comments.value(value));
+
+    match value.as_ref() {
+        ValueInner::Table(tab) => {
+            for (k, v) in integ_tests::sorted(tab) {
+                let pointer = format!("{pointer}/{}", k.name);
+                write(out, "key", &pointer, comments.key(k));
+                collect(comments, v, &pointer, out);
+            }
+        }
+        ValueInner::Array(arr) => {
+            for (i, v) in arr.iter().enumerate() {
+                collect(comments, v, &format!("{pointer}/{i}"), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+macro_rules! comments {
+    ($name:ident, $toml:expr) => {
+        #[test]
+        fn $name() {
+            let doc = toml_span::parse_with(
+                $toml,
+                &ParseOptions {
+                    comments: true,
+                    ..Default::default()
+                },
+            )
+            .expect("failed to parse toml");
+
+            let mut out = String::new();
+            collect(&doc.comments, &doc.root, "", &mut out);
+            insta::assert_snapshot!(out);
+        }
+    };
+}
+
+comments!(
+    deny,
+    r##"# The crates we don't want
+# to be used
+
+# Banned crates
+[bans] # the bans table
+# How to treat multiple versions
+multiple-versions = "warn" # for now
+deny = [
+    # Use rustls instead
+    { name = "openssl" },
+    { name = "git2", wrappers = ["crate-a", "crate-b"] }, # deny:allow
+    "libssh2-sys" # no comma
+] # end of deny
+skip = [1, 2, # only the last
+    3]
+
+  # indented
+[[bans.features]]
+name = "reqwest" # the name
+
+hashes = 1 # a comment with # in it is fine
+str = "# not a comment"
+
+# about `a.b`
+a.b = 1 # dotted
+
+inline = {
+    # leading
+    key = 1, # trailing
+    other = 2
+}
+
+[[bans.features]] # second
+# not a doc comment for anything
+
+"##
+);
+
+#[test]
+fn disabled() {
+    let toml = "# comment\na = 1 # comment";
+    let doc = toml_span::parse_with(toml, &ParseOptions::default()).unwrap();
+    assert!(doc.comments.is_empty());
+
+    let doc = toml_span::parse_with(
+        toml,
+        &ParseOptions {
+            comments: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let raw: Vec<_> = doc.comments.all().iter().map(|c| c.raw).collect();
+    assert_eq!(raw, ["# comment", "# comment"]);
+}
@@ -0,0 +1,223 @@
+use toml_span::{
+    cst::{self, ArrayPart, InlineTablePart, Item, KeyPart, KeyValue, Trivia, ValueNode},
+    value::{Value, ValueInner},
+};
+
+/// Writes each node of the tree on its own line, indented by its nesting
+fn write_kv(kv: &KeyValue<'_>, indent: usize, out: &mut String) {
+    use std::fmt::Write;
+
+    let pad = "  ".repeat(indent);
+    for part in &kv.key.parts {
+        match part {
+            KeyPart::Trivia(trivia) => writeln!(out, "{pad}key trivia {trivia:?}"),
+            KeyPart::Segment(segment) => writeln!(
+                out,
+                "{pad}key {:?} {:?} {:?}",
+                segment.raw.raw, segment.key.name, segment.key.span
+            ),
+            KeyPart::Dot(dot) => writeln!(out, "{pad}dot {:?}", dot.span),
+        }
+        .unwrap();
+    }
+    writeln!(out, "{pad}eq {:?}", kv.eq.span).unwrap();
+    for trivia in &kv.trivia {
+        writeln!(out, "{pad}trivia {trivia:?}").unwrap();
+    }
+    write_value(&kv.value, indent, out);
+}
+
+fn write_value(value: &ValueNode<'_>, indent: usize, out: &mut String) {
+    use std::fmt::Write;
+
+    let pad = "  ".repeat(indent);
+    match value {
+        ValueNode::Scalar(scalar) => {
+            writeln!(out, "{pad}scalar {:?} {:?}", scalar.raw, scalar.value).unwrap();
+        }
+        ValueNode::Array(array) => {
+            writeln!(out, "{pad}array {:?}", array.open.span).unwrap();
+            for part in &array.parts {
+                match part {
+                    ArrayPart::Trivia(trivia) => {
+                        writeln!(out, "{pad}  trivia {trivia:?}").unwrap();
+                    }
+                    ArrayPart::Value(value) => write_value(value, indent + 1, out),
+                    ArrayPart::Comma(comma) => {
+                        writeln!(out, "{pad}  comma {:?}", comma.span).unwrap();
+                    }
+                }
+            }
+            writeln!(out, "{pad}end {:?}", array.close.span).unwrap();
+        }
+        ValueNode::InlineTable(table) => {
+            writeln!(out, "{pad}table {:?}", table.open.span).unwrap();
+            for part in &table.parts {
+                match part {
+                    InlineTablePart::Trivia(trivia) => {
+                        writeln!(out, "{pad}  trivia {trivia:?}").unwrap();
+                    }
+                    InlineTablePart::KeyValue(kv) => write_kv(kv, indent + 1, out),
+                    InlineTablePart::Comma(comma) => {
+                        writeln!(out, "{pad}  comma {:?}", comma.span).unwrap();
+                    }
+                }
+            }
+            writeln!(out, "{pad}end {:?}", table.close.span).unwrap();
+        }
+    }
+}
+
+#[test]
+fn tree() {
+    use std::fmt::Write;
+
+    let toml = "\u{feff}# A comment\r\nname = \"toml-span\" # trailing\nversion . major= 0\n\n[[ bin . 'quoted' ]] # header\nnested = [ 1, [], { a = 1.5,\n  b.c = true, }, # last\n]\n";
+    let doc = cst::parse(toml).unwrap();
+
+    let mut out = String::new();
+    writeln!(out, "bom {:?}", doc.bom).unwrap();
+    for item in &doc.items {
+        match item {
+            Item::Trivia(trivia) => writeln!(out, "trivia {trivia:?}").unwrap(),
+            Item::TableHeader(header) => {
+                writeln!(out, "header {:?} array={}", header.open.raw, header.array).unwrap();
+                for part in &header.key.parts {
+                    writeln!(out, "  {part:?}").unwrap();
+                }
+                writeln!(out, "header end {:?}", header.close.raw).unwrap();
+            }
+            Item::KeyValue(kv) => write_kv(kv, 0, &mut out),
+        }
+    }
+
+    insta::assert_snapshot!(out);
+}
+
+/// Writes the span of every value in the tree along with its pointer
+fn spans(value: &Value<'_>, pointer: &str, out: &mut Vec<String>) {
+    out.push(format!("{pointer} {:?}", value.span));
+    match value.as_ref() {
+        ValueInner::Table(table) => {
+            for (k, v) in table {
+                let pointer = format!("{pointer}/{}", k.name);
+                out.push(format!("{pointer} key {:?}", k.span));
+                spans(v, &pointer, out);
+            }
+        }
+        ValueInner::Array(arr) => {
+            for (i, v) in arr.iter().enumerate() {
+                spans(v, &format!("{pointer}/{i}"), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The tree prints back to the exact input, and converts to the same value,
+/// or fails with the same error, as [`toml_span::parse`]
+#[test]
+fn matches_parse() {
+    let mut docs: Vec<_> = std::fs::read_dir("data")
+        .unwrap()
+        .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect();
+    let crlf: Vec<_> = docs.iter().map(|doc| doc.replace('\n', "\r\n")).collect();
+    docs.extend(crlf);
+    docs.extend(
+        [
+            "\u{feff}a = 1",
+            "\u{feff}",
+            "",
+            "# only a comment",
+            "a = [1, 2,]\nb = { c = [ {}, { d = 'e' } ] }",
+            "a = {\n  b = 1,\n  # comment\n  c = [\n    2,\n  ],\n}",
+            "[a] # no newline",
+            "[a]\n\n  \t\n[[b]]\n[[b]]\nc = 1 # comment\r\n",
+            "a = 1 b = 2",
+            "a = [1 2]",
+            "a = { b = 1 c = 2 }",
+            "a = { b = 1,, }",
+            "a = [1,,]",
+            "[a\nb = 1",
+            "[a] b = 1",
+            "a.b = 1\na.b = 2",
+            "a = { b = 1, b = 2 }",
+            "a = { b.c = 1, b = 2 }",
+            "[a]\n[a]",
+            "a = 1\n[a]",
+            "[[a]]\n[a]",
+            "a = \"unterminated",
+            "a = 1979-13-27",
+            "a = +",
+            "a = [",
+            "a = {",
+            "a = 1 # comment \u{7f} with delete",
+        ]
+        .map(String::from),
+    );
+
+    for doc in &docs {
+        let tree = cst::parse(doc);
+        if let Ok(tree) = &tree {
+            assert_eq!(&tree.to_string(), doc);
+        }
+
+        match toml_span::parse(doc) {
+            Ok(expected) => {
+                let value = tree
+                    .and_then(cst::Document::into_value)
+                    .unwrap_or_else(|err| panic!("{err}\n{doc}"));
+
+                let (mut actual_spans, mut expected_spans) = (Vec::new(), Vec::new());
+                spans(&value, "", &mut actual_spans);
+                spans(&expected, "", &mut expected_spans);
+                assert_eq!(actual_spans, expected_spans, "{doc}");
+                assert_eq!(format!("{value:?}"), format!("{expected:?}"), "{doc}");
+            }
+            Err(err) => {
+                let tree_err = tree.and_then(cst::Document::into_value).expect_err(doc);
+                assert_eq!(
+                    (tree_err.to_string(), tree_err.span),
+                    (err.to_string(), err.span),
+                    "{doc}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn options() {
+    use toml_span::{ParseOptions, TomlVersion};
+
+    let toml = "a = {\n  b = 1 }";
+    assert!(cst::parse(toml).is_ok());
+
+    let err = cst::parse_with(
+        toml,
+        &ParseOptions {
+            version: TomlVersion::V1_0,
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "newlines in inline tables are not supported in TOML 1.0"
+    );
+
+    // Trivia is kept the same whether comments are collected or not
+    let doc = cst::parse_with(
+        "a = 1 # comment",
+        &ParseOptions {
+            comments: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert!(matches!(
+        doc.items.last(),
+        Some(Item::Trivia(Trivia::Comment(leaf))) if leaf.raw == "# comment"
+    ));
+}
@@ -51,15 +51,13 @@ impl<'de> Deserialize<'de> for Package {
             }
         }
 
-        match value.take() {
-            ValueInner::String(s) => {
-                let (name, version) = from_str(s);
+        match string_or_table(value)? {
+            StringOrTable::String(s) => {
+                let (name, version) = from_str(s.value);
 
                 Ok(Self { name, version })
             }
-            ValueInner::Table(tab) => {
-                let mut th = TableHelper::from((tab, value.span));
-
+            StringOrTable::Table(mut th) => {
                 if let Some(mut val) = th.table.remove("crate") {
                     let (name, version) = match val.take() {
                         ValueInner::String(s) => from_str(s),
@@ -86,7 +84,6 @@ impl<'de> Deserialize<'de> for Package {
                     })
                 }
             }
-            other => Err(expected("a string or table", other, value.span).into()),
         }
     }
 }
@@ -225,3 +222,522 @@ invalid_de!(
     Ohno,
     "year = 40_000\nthis-is-deprecated = 'this should not be here'"
 );
+
+#[derive(Debug)]
+struct Datetimes {
+    datetime: toml_span::Datetime,
+    chrono_date: chrono::NaiveDate,
+    chrono_datetime: chrono::DateTime<chrono::FixedOffset>,
+    time_time: time::Time,
+    time_datetime: time::OffsetDateTime,
+    jiff_datetime: jiff::civil::DateTime,
+    jiff_timestamp: Option<jiff::Timestamp>,
+}
+
+impl<'de> Deserialize<'de> for Datetimes {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+        let datetime = th.required("datetime").ok();
+        let chrono_date = th.required("chrono_date").ok();
+        let chrono_datetime = th.required("chrono_datetime").ok();
+        let time_time = th.required("time_time").ok();
+        let time_datetime = th.required("time_datetime").ok();
+        let jiff_datetime = th.required("jiff_datetime").ok();
+        let jiff_timestamp = th.optional("jiff_timestamp");
+
+        th.finalize(None)?;
+        Ok(Self {
+            datetime: datetime.unwrap(),
+            chrono_date: chrono_date.unwrap(),
+            chrono_datetime: chrono_datetime.unwrap(),
+            time_time: time_time.unwrap(),
+            time_datetime: time_datetime.unwrap(),
+            jiff_datetime: jiff_datetime.unwrap(),
+            jiff_timestamp,
+        })
+    }
+}
+
+valid_de!(
+    datetimes,
+    Datetimes,
+    r#"
+datetime = 1979-05-27T07:32:00.5-08:00
+chrono_date = 1979-05-27
+chrono_datetime = 1979-05-27 07:32:00+01:30
+time_time = 07:32:00.999999999
+time_datetime = 1979-05-27T07:32:00Z
+jiff_datetime = 1979-05-27T07:32
+jiff_timestamp = 1979-05-27T07:32:00.123-00:00
+"#
+);
+invalid_de!(
+    datetimes_wrong_form,
+    Datetimes,
+    r#"
+datetime = '1979-05-27'
+chrono_date = 1979-05-27T07:32:00
+chrono_datetime = 1979-05-27T07:32:00
+time_time = 1979-05-27
+time_datetime = 07:32:00
+jiff_datetime = 1979-05-27T07:32:00Z
+jiff_timestamp = 1979-05-27
+"#
+);
+
+#[derive(Debug)]
+struct Renamed {
+    name: String,
+    features: Option<Vec<String>>,
+}
+
+impl<'de> Deserialize<'de> for Renamed {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+
+        let name = th.required_or_deprecated("name", "crate-name");
+        let features = th.optional_aliased(&["features", "feature"]);
+
+        th.report_warnings(value);
+        th.finalize(None)?;
+
+        Ok(Self {
+            name: name.unwrap(),
+            features,
+        })
+    }
+}
+
+invalid_de!(
+    deprecated_and_new,
+    Renamed,
+    "name = 'a'\ncrate-name = 'b'\nfeature = ['c']\nfeatures = []"
+);
+
+#[derive(Debug)]
+struct Aliased {
+    features: Option<Vec<String>>,
+}
+
+impl<'de> Deserialize<'de> for Aliased {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+        let features = th.optional_aliased(&["features", "feature", "feats"]);
+        th.finalize(None)?;
+        Ok(Self { features })
+    }
+}
+
+// Each extra alias is reported once as a duplicate, not also as an unexpected key
+invalid_de!(
+    multiple_aliases,
+    Aliased,
+    "feats = ['a']\nfeature = ['b']\nfeatures = ['c']"
+);
+
+/// Using a deprecated key succeeds with a warning
+#[test]
+fn deprecated_warning() {
+    let toml = "crate-name = 'a'\nfeature = ['b']";
+    let mut value = toml_span::parse(toml).unwrap();
+    let renamed = Renamed::deserialize(&mut value).unwrap();
+    let warnings = value.take_warnings();
+
+    assert_eq!(renamed.name, "a");
+    assert_eq!(renamed.features.unwrap(), ["b"]);
+    integ_tests::error_snapshot!(
+        deprecated_warning,
+        warnings.into_iter().map(|d| d.to_diagnostic(())),
+        toml
+    );
+}
+
+invalid_de!(
+    unknown_field_suggestions,
+    Boop,
+    "s = 'val'\nOS = 20\nunrelated = 1\nss = 'typo'"
+);
+
+#[derive(Debug)]
+enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl<'de> Deserialize<'de> for Level {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        Ok(match unit_variant(value, &["allow", "warn", "deny"])? {
+            "allow" => Self::Allow,
+            "warn" => Self::Warn,
+            _ => Self::Deny,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Levels {
+    levels: Vec<Level>,
+}
+
+impl<'de> Deserialize<'de> for Levels {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+        let levels = th.required("levels");
+        th.finalize(None)?;
+        Ok(Self {
+            levels: levels.unwrap(),
+        })
+    }
+}
+
+invalid_de!(
+    unexpected_value_suggestion,
+    Levels,
+    "levels = ['allow', 'dney', 'forbid']"
+);
+
+/// An internally tagged enum
+#[derive(Debug)]
+enum Source {
+    Git { url: String, rev: Option<String> },
+    Path { path: String },
+}
+
+impl<'de> Deserialize<'de> for Source {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+
+        let source = match th.tag("type", &["git", "path"]) {
+            Ok("git") => {
+                let url = th.required("url");
+                let rev = th.optional("rev");
+                url.ok().map(|url| Self::Git { url, rev })
+            }
+            Ok(_) => th.required("path").ok().map(|path| Self::Path { path }),
+            Err(_) => None,
+        };
+
+        th.finalize(None)?;
+        Ok(source.unwrap())
+    }
+}
+
+/// An externally tagged enum
+#[derive(Debug)]
+enum Dependency {
+    Version(String),
+    Git(Source),
+}
+
+impl<'de> Deserialize<'de> for Dependency {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let variant = external_variant(value, &["version", "git"])?;
+        Ok(match variant.name {
+            "version" => Self::Version(variant.deserialize()?),
+            _ => Self::Git(variant.deserialize()?),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Enums {
+    levels: Vec<Level>,
+    sources: Vec<Source>,
+    dependencies: Vec<Dependency>,
+}
+
+impl<'de> Deserialize<'de> for Enums {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+        let levels = th.required("levels");
+        let sources = th.required("sources");
+        let dependencies = th.required("dependencies");
+        th.finalize(None)?;
+        Ok(Self {
+            levels: levels.unwrap(),
+            sources: sources.unwrap(),
+            dependencies: dependencies.unwrap(),
+        })
+    }
+}
+
+valid_de!(
+    enums,
+    Enums,
+    r#"
+levels = ["allow", "warn", "deny"]
+sources = [
+    { type = "git", url = "https://github.com/EmbarkStudios/toml-span", rev = "abc" },
+    { type = "path", path = "../toml-span" },
+]
+dependencies = [
+    { version = "0.7" },
+    { git = { type = "path", path = "../toml-span" } },
+]
+"#
+);
+invalid_de!(
+    enums_invalid,
+    Enums,
+    r#"
+levels = ["alow", 1]
+sources = [
+    { type = "gti", url = "https://github.com/EmbarkStudios/toml-span" },
+    { type = "path" },
+    { url = "https://github.com/EmbarkStudios/toml-span" },
+]
+dependencies = [
+    { version = "0.7", git = { type = "git" } },
+    { branch = "main" },
+    { git = { type = "git", url = 1 } },
+    "0.7",
+]
+"#
+);
+
+#[derive(Debug)]
+struct StdTypes {
+    path: std::path::PathBuf,
+    ip: std::net::IpAddr,
+    socket: std::net::SocketAddr,
+    c: char,
+    non_zero: std::num::NonZeroU32,
+    timeout: HumanDuration,
+    max_size: ByteSize,
+    chunk_size: ByteSize,
+}
+
+impl<'de> Deserialize<'de> for StdTypes {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+        let path = th.required("path");
+        let ip = th.required("ip");
+        let socket = th.required("socket");
+        let c = th.required("c");
+        let non_zero = th.required("non-zero");
+        let timeout = th.required("timeout");
+        let max_size = th.required("max-size");
+        let chunk_size = th.required("chunk-size");
+        th.finalize(None)?;
+        Ok(Self {
+            path: path.unwrap(),
+            ip: ip.unwrap(),
+            socket: socket.unwrap(),
+            c: c.unwrap(),
+            non_zero: non_zero.unwrap(),
+            timeout: timeout.unwrap(),
+            max_size: max_size.unwrap(),
+            chunk_size: chunk_size.unwrap(),
+        })
+    }
+}
+
+valid_de!(
+    std_types,
+    StdTypes,
+    r#"
+path = "/usr/bin"
+ip = "::1"
+socket = "127.0.0.1:8080"
+c = "é"
+non-zero = 1
+timeout = "1h 30m 1.5s"
+max-size = "1.5 GiB"
+chunk-size = 4096
+"#
+);
+invalid_de!(
+    std_types_invalid,
+    StdTypes,
+    r#"
+path = 1
+ip = "127.0.0.256"
+socket = "localhost"
+c = "ab"
+non-zero = 0
+timeout = "1h 30x"
+max-size = "10 MiBs"
+chunk-size = -1
+"#
+);
+
+#[test]
+fn human_readable() {
+    let duration = |s: &str| {
+        let toml = format!("d = '{s}'");
+        let mut value = toml_span::parse(&toml).unwrap();
+        let mut th = TableHelper::new(&mut value).unwrap();
+        th.required::<HumanDuration>("d")
+            .map(|d| d.0)
+            .map_err(|err| (err.to_string(), err.span))
+    };
+
+    use std::time::Duration;
+    assert_eq!(duration("30s"), Ok(Duration::from_secs(30)));
+    assert_eq!(duration("5m"), Ok(Duration::from_secs(300)));
+    assert_eq!(duration("1d 2h"), Ok(Duration::from_secs(26 * 3600)));
+    assert_eq!(duration("1.5ms"), Ok(Duration::from_micros(1500)));
+    assert_eq!(duration(" 10µs 5ns "), Ok(Duration::from_nanos(10_005)));
+    assert_eq!(
+        duration("").unwrap_err().0,
+        "d: invalid string, expected a duration: the duration is empty"
+    );
+    assert_eq!(
+        duration("5m 30").unwrap_err(),
+        (
+            "d: invalid string, expected a duration: the number is missing a unit, eg. `s`".into(),
+            toml_span::Span::new(8, 10)
+        )
+    );
+    assert_eq!(duration("s").unwrap_err().1, toml_span::Span::new(5, 6));
+    assert_eq!(
+        duration("99999999999999999999999d").unwrap_err().0,
+        "d: invalid string, expected a duration: the duration is too large"
+    );
+
+    let size = |s: &str| {
+        let toml = format!("s = '{s}'");
+        let mut value = toml_span::parse(&toml).unwrap();
+        let mut th = TableHelper::new(&mut value).unwrap();
+        th.required::<ByteSize>("s")
+            .map(|s| s.0)
+            .map_err(|err| err.span)
+    };
+
+    assert_eq!(size("10"), Ok(10));
+    assert_eq!(size("10B"), Ok(10));
+    assert_eq!(size("10kb"), Ok(10_000));
+    assert_eq!(size("10MiB"), Ok(10 << 20));
+    assert_eq!(size("0.5 KiB"), Ok(512));
+    assert_eq!(size("20 PiB"), Ok(20 << 50));
+    assert_eq!(size("20000 PiB"), Err(toml_span::Span::new(5, 14)));
+    assert_eq!(size("10 MiB 5"), Err(toml_span::Span::new(12, 13)));
+    assert_eq!(size("MiB"), Err(toml_span::Span::new(5, 6)));
+}
+
+#[derive(Debug)]
+struct Ecosystem {
+    version: Option<semver::Version>,
+    req: Option<semver::VersionReq>,
+    url: Option<url::Url>,
+    regex: Option<regex::Regex>,
+    glob: Option<globset::Glob>,
+}
+
+impl<'de> Deserialize<'de> for Ecosystem {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+        let version = th.optional("version");
+        let req = th.optional("req");
+        let url = th.optional("url");
+        let regex = th.optional("regex");
+        let glob = th.optional("glob");
+        th.finalize(None)?;
+        Ok(Self {
+            version,
+            req,
+            url,
+            regex,
+            glob,
+        })
+    }
+}
+
+#[test]
+fn ecosystem() {
+    let toml = r#"
+version = "1.2.3-beta.1"
+req = ">=1.2, <2"
+url = "https://github.com/EmbarkStudios/toml-span"
+regex = '^toml-\w+$'
+glob = "**/*.{rs,toml}"
+"#;
+    let mut value = toml_span::parse(toml).unwrap();
+    let eco = Ecosystem::deserialize(&mut value).unwrap();
+
+    assert!(eco.req.unwrap().matches(&semver::Version::new(1, 5, 0)));
+    assert!(!eco.version.unwrap().pre.is_empty());
+    assert_eq!(eco.url.unwrap().host_str(), Some("github.com"));
+    assert!(eco.regex.unwrap().is_match("toml-span"));
+    assert!(eco.glob.unwrap().compile_matcher().is_match("src/lib.rs"));
+}
+
+invalid_de!(
+    ecosystem_invalid,
+    Ecosystem,
+    r#"
+version = "1.2"
+req = "~>1"
+url = "not a url"
+regex = 'toml-(span|edit'
+glob = "src/[z-a].rs"
+"#
+);
+invalid_de!(
+    ecosystem_invalid_positions,
+    Ecosystem,
+    r#"
+regex = 'a{2,1}b'
+glob = "src/{a,b"
+"#
+);
+invalid_de!(
+    ecosystem_invalid_escaped_positions,
+    Ecosystem,
+    r#"
+regex = "\\d\t\\w{2,1}"
+glob = "src/\u007Ba,b"
+"#
+);
+
+#[derive(Debug)]
+struct Numbers {
+    timeout: Lenient<f64>,
+    ratio: Lenient<f32>,
+    retries: Lenient<u8>,
+    offset: Lenient<i64>,
+    scale: f32,
+}
+
+impl<'de> Deserialize<'de> for Numbers {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+        let timeout = th.required("timeout");
+        let ratio = th.required("ratio");
+        let retries = th.required("retries");
+        let offset = th.required("offset");
+        let scale = th.required("scale");
+        th.finalize(None)?;
+        Ok(Self {
+            timeout: timeout.unwrap(),
+            ratio: ratio.unwrap(),
+            retries: retries.unwrap(),
+            offset: offset.unwrap(),
+            scale: scale.unwrap(),
+        })
+    }
+}
+
+valid_de!(
+    lenient_numbers,
+    Numbers,
+    r#"
+timeout = 5
+ratio = 0.5
+retries = 3.0
+offset = -9_007_199_254_740_992
+scale = 3.4e38
+"#
+);
+invalid_de!(
+    lenient_numbers_invalid,
+    Numbers,
+    r#"
+timeout = 9_007_199_254_740_993
+ratio = 16_777_217
+retries = 2.5
+offset = 1e19
+scale = 3.5e38
+"#
+);
@@ -0,0 +1,238 @@
+#![allow(dead_code)]
+
+use integ_tests::error_snapshot;
+use serde::Deserialize;
+use std::{borrow::Cow, collections::BTreeMap};
+use toml_span::{Spanned, impl_serde::Native};
+
+/// Deserializes the toml to the specified type via [`serde::Deserialize`]
+fn de<'de, T: Deserialize<'de>>(toml: &'de str) -> Result<T, toml_span::DeserError> {
+    let value = toml_span::parse(toml).expect("failed to parse toml");
+    T::deserialize(value)
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+enum Kind {
+    Unit,
+    Newtype(u32),
+    Tuple(u8, String),
+    Struct { name: String },
+}
+
+#[derive(Deserialize, Debug)]
+struct Point(i32, i32);
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct Everything<'a> {
+    string: String,
+    #[serde(borrow)]
+    borrowed: &'a str,
+    small: u8,
+    signed: i16,
+    float: f32,
+    boolean: bool,
+    datetime: toml_span::Datetime,
+    datetime_str: String,
+    optional: Option<u64>,
+    missing: Option<u64>,
+    #[serde(default)]
+    defaulted: Vec<String>,
+    point: Point,
+    kinds: Vec<Kind>,
+    map: BTreeMap<String, Vec<i64>>,
+}
+
+#[test]
+fn everything() {
+    let everything: Everything<'_> = de(r#"
+string = "a \"string\""
+borrowed = "borrowed"
+small = 255
+signed = -32768
+float = 1.5
+boolean = true
+datetime = 1979-05-27T07:32:00Z
+datetime-str = 1979-05-27
+optional = 1
+point = [1, -1]
+kinds = [
+    "unit",
+    { newtype = 2 },
+    { tuple = [3, "three"] },
+    { struct = { name = "four" } },
+    { unit = {} },
+]
+
+[map]
+a = [1, 2]
+b = []
+"#)
+    .unwrap();
+
+    insta::assert_debug_snapshot!(everything);
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Strict {
+    number: u8,
+    kind: Option<Kind>,
+}
+
+macro_rules! invalid_serde {
+    ($name:ident, $kind:ty, $toml:literal) => {
+        #[test]
+        fn $name() {
+            match de::<$kind>($toml) {
+                Ok(de) => panic!("expected an error but deserialized '{de:#?}' successfully"),
+                Err(err) => {
+                    error_snapshot!(
+                        $name,
+                        err.errors.iter().map(|err| err.to_diagnostic(())),
+                        $toml
+                    );
+                }
+            }
+        }
+    };
+}
+
+invalid_serde!(wrong_type, Strict, "number = 'one'");
+invalid_serde!(out_of_range, Strict, "number = 256");
+invalid_serde!(missing_field, Strict, "kind = 'unit'");
+invalid_serde!(unknown_field, Strict, "number = 1\nunknown = 2");
+invalid_serde!(unknown_variant, Strict, "number = 1\nkind = 'nope'");
+invalid_serde!(
+    unknown_table_variant,
+    Strict,
+    "number = 1\nkind = { nope = 1 }"
+);
+invalid_serde!(
+    too_many_variants,
+    Strict,
+    "number = 1\nkind = { unit = {}, newtype = 1 }"
+);
+invalid_serde!(
+    nested,
+    BTreeMap<String, Strict>,
+    "[a]\nnumber = 1\n\n[b]\nnumber = 2\nkind = { tuple = [1, 2] }"
+);
+
+/// A type that only implements this crate's `Deserialize`
+#[derive(Debug)]
+struct Version {
+    major: u32,
+    minor: u32,
+}
+
+impl<'de> toml_span::Deserialize<'de> for Version {
+    fn deserialize(value: &mut toml_span::Value<'de>) -> Result<Self, toml_span::DeserError> {
+        let s = value.take_string(Some("a version"))?;
+        let (major, minor) = s
+            .split_once('.')
+            .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)))
+            .ok_or_else(|| {
+                toml_span::Error::from((
+                    toml_span::ErrorKind::Custom("invalid version".into()),
+                    value.span,
+                ))
+            })?;
+        Ok(Self { major, minor })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Mixed {
+    name: String,
+    version: Native<Spanned<Version>>,
+    #[serde(deserialize_with = "toml_span::impl_serde::native")]
+    license: Spanned<String>,
+    optional: Option<Native<Spanned<u32>>>,
+    list: Vec<Native<Spanned<String>>>,
+}
+
+#[test]
+fn native() {
+    let toml = r#"
+name = "toml-span"
+version = "0.7"
+license = "MIT"
+list = ["a", "b"]
+"#;
+    let mixed: Mixed = de(toml).unwrap();
+
+    insta::assert_debug_snapshot!(mixed);
+
+    let spanned = [
+        mixed.version.0.span,
+        mixed.license.span,
+        mixed.list[0].0.span,
+        mixed.list[1].0.span,
+    ];
+    assert_eq!(
+        spanned.map(|span| &toml[span.start..span.end]),
+        ["0.7", "MIT", "a", "b"]
+    );
+}
+
+invalid_serde!(
+    native_error,
+    Mixed,
+    "name = 'toml-span'\nversion = 'one.two'\nlicense = 'MIT'\nlist = []"
+);
+
+#[derive(Deserialize, Debug)]
+struct NativeList {
+    list: Native<Vec<Spanned<u8>>>,
+}
+
+// Every error of the native type is returned, not just the first
+invalid_serde!(native_errors, NativeList, "list = [1, 'two', 3, 256]");
+
+#[derive(Deserialize, Debug)]
+struct Borrowed<'a> {
+    #[serde(borrow)]
+    name: Native<Spanned<Cow<'a, str>>>,
+    #[serde(borrow, deserialize_with = "toml_span::impl_serde::native")]
+    aliases: Vec<Cow<'a, str>>,
+}
+
+/// Native types can have the lifetime of the document
+#[test]
+fn native_borrowed() {
+    let toml = "name = 'toml-span'\naliases = ['toml_span']";
+    let borrowed: Borrowed<'_> = de(toml).unwrap();
+
+    let span = borrowed.name.0.span;
+    assert_eq!(&toml[span.start..span.end], "toml-span");
+    assert_eq!(borrowed.name.0.value, "toml-span");
+    assert_eq!(borrowed.aliases, ["toml_span"]);
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Untagged {
+    Native(Native<Spanned<String>>),
+}
+
+#[test]
+fn native_requires_value() {
+    let value = toml_span::parse("a = 'b'").unwrap();
+    let err = BTreeMap::<String, Untagged>::deserialize(value).unwrap_err();
+    assert_eq!(
+        err.errors[0].to_string(),
+        "data did not match any variant of untagged enum Untagged"
+    );
+
+    // Deserializers other than `Value` can't deserialize native types
+    let err = Native::<u32>::deserialize(serde::de::value::U32Deserializer::<
+        serde::de::value::Error,
+    >::new(1))
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: integer `1`, expected a toml_span::value::Value"
+    );
+}
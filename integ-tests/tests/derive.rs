@@ -0,0 +1,319 @@
+#![allow(dead_code)]
+
+use integ_tests::{invalid_de, valid_de};
+use std::borrow::Cow;
+use toml_span::{Deserialize, span::Spanned};
+
+#[derive(Debug, Deserialize)]
+#[toml_span(rename_all = "kebab-case", deny_unknown_fields)]
+struct CrateBan {
+    name: Spanned<String>,
+    version: Option<String>,
+    deny_multiple_versions: Option<Spanned<bool>>,
+    #[toml_span(rename = "wrapped-by")]
+    wrappers: Option<Vec<Spanned<String>>>,
+    #[toml_span(default)]
+    reasons: Vec<String>,
+    #[toml_span(default = "default_level")]
+    level: u8,
+}
+
+fn default_level() -> u8 {
+    3
+}
+
+valid_de!(
+    derive_basic,
+    CrateBan,
+    "name = 'openssl'\ndeny-multiple-versions = true\nwrapped-by = ['native-tls']"
+);
+valid_de!(
+    derive_all_fields,
+    CrateBan,
+    "name = 'openssl'\nversion = '<1.0'\nreasons = ['one', 'two']\nlevel = 1"
+);
+invalid_de!(
+    derive_errors,
+    CrateBan,
+    "version = 2\nreasons = ['one', 2]\nnot-a-field = 'nope'"
+);
+
+#[derive(Debug, Deserialize)]
+struct DefaultOptions {
+    #[toml_span(default)]
+    count: Option<u8>,
+    #[toml_span(default = "default_name")]
+    name: Option<String>,
+}
+
+fn default_name() -> Option<String> {
+    Some("default".into())
+}
+
+valid_de!(derive_default_options, DefaultOptions, "count = 1");
+valid_de!(derive_default_options_set, DefaultOptions, "name = 'set'");
+
+#[derive(Debug, Deserialize)]
+struct Borrowed<'a> {
+    s: Cow<'a, str>,
+}
+
+valid_de!(derive_borrowed, Borrowed<'_>, "s = 'borrowed'");
+
+#[derive(Debug, Deserialize)]
+struct Extended {
+    name: String,
+    #[toml_span(flatten)]
+    inner: Inner,
+}
+
+#[derive(Debug, Deserialize)]
+#[toml_span(deny_unknown_fields)]
+struct Inner {
+    reason: String,
+}
+
+valid_de!(
+    derive_flatten,
+    Extended,
+    "name = 'flat'\nreason = 'because'"
+);
+invalid_de!(
+    derive_flatten_errors,
+    Extended,
+    "name = 'flat'\nunknown = 'because'"
+);
+
+#[derive(Debug, Deserialize)]
+#[toml_span(deny_unknown_fields)]
+struct Generic<T> {
+    items: Vec<T>,
+    #[toml_span(flatten)]
+    rest: Rest,
+}
+
+/// Leaves any unconsumed keys in the table for the parent to report
+#[derive(Debug, Deserialize)]
+struct Rest {
+    extra: Option<u32>,
+}
+
+valid_de!(derive_generic, Generic<u32>, "items = [1, 2, 3]\nextra = 4");
+invalid_de!(
+    derive_generic_unknown,
+    Generic<u32>,
+    "items = [1, 2, 3]\nextra = 4\nunknown = 5"
+);
+
+#[derive(Debug, Deserialize)]
+struct Newtype(Vec<String>);
+
+#[derive(Debug, Deserialize)]
+struct Outer {
+    newtype: Newtype,
+}
+
+valid_de!(derive_newtype, Outer, "newtype = ['a', 'b']");
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    package: ManifestPackage,
+}
+
+#[derive(Debug, Deserialize)]
+#[toml_span(deny_unknown_fields)]
+struct ManifestPackage {
+    metadata: Metadata,
+}
+
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    deny: Vec<CrateBan>,
+    #[toml_span(rename = "dotted.key")]
+    dotted: Option<u8>,
+}
+
+/// Errors carry the path to the value they occurred in
+#[test]
+fn key_paths() {
+    use toml_span::PathSegment;
+
+    let toml = r#"[package]
+unknown = 1
+[package.metadata]
+deny = [{ name = 'a' }, { name = 1 }, { version = 'x' }]
+"dotted.key" = 256
+"#;
+    let mut value = toml_span::parse(toml).unwrap();
+    let err = Manifest::deserialize(&mut value).unwrap_err();
+
+    assert_eq!(
+        err.errors[0].path.segments(),
+        [
+            PathSegment::Key("package".into()),
+            PathSegment::Key("metadata".into()),
+            PathSegment::Key("deny".into()),
+            PathSegment::Index(1),
+            PathSegment::Key("name".into()),
+        ]
+    );
+    insta::assert_snapshot!(err.to_string());
+}
+
+#[derive(Debug, Deserialize)]
+struct Lints {
+    lints: Vec<Lint>,
+}
+
+#[derive(Debug, Deserialize)]
+#[toml_span(warn_unknown_fields)]
+struct Lint {
+    name: String,
+}
+
+/// Unknown keys are reported as warnings with their path, without failing
+#[test]
+fn warnings() {
+    use toml_span::Severity;
+
+    let toml = "lints = [{ name = 'a' }, { name = 'b', level = 'deny' }]";
+    let mut value = toml_span::parse(toml).unwrap();
+    let lints = Lints::deserialize(&mut value).unwrap();
+    let warnings = value.take_warnings();
+
+    assert_eq!(lints.lints[1].name, "b");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, Severity::Warning);
+    assert_eq!(warnings[0].path.to_string(), "lints[1]");
+    insta::assert_snapshot!(warnings[0].to_string());
+
+    // The warnings are taken only once
+    assert!(value.take_warnings().is_empty());
+
+    // The warnings follow the value rather than the thread that deserialized it
+    let mut value = toml_span::parse(toml).unwrap();
+    let mut value = std::thread::spawn(move || {
+        assert!(Lints::deserialize(&mut value).is_ok());
+        value
+    })
+    .join()
+    .unwrap();
+    assert_eq!(value.take_warnings().len(), 1);
+}
+
+#[derive(Debug)]
+enum Plugin {
+    Lint(Lint),
+}
+
+impl<'de> Deserialize<'de> for Plugin {
+    fn deserialize(value: &mut toml_span::Value<'de>) -> Result<Self, toml_span::DeserError> {
+        let variant = toml_span::de_helpers::external_variant(value, &["Lint"])?;
+        Ok(Self::Lint(variant.deserialize()?))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Plugins {
+    plugins: Vec<Plugin>,
+}
+
+/// Warnings of nested values, including enum variants, are reported by the
+/// outermost value with their full path
+#[test]
+fn nested_warnings() {
+    let toml = "plugins = [{ Lint = { name = 'a', level = 'deny' } }]";
+    let mut value = toml_span::parse(toml).unwrap();
+    Plugins::deserialize(&mut value).unwrap();
+
+    let warnings = value.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].path.to_string(), "plugins[0].Lint");
+}
+
+#[derive(Debug, Deserialize)]
+struct Collections {
+    map: std::collections::BTreeMap<Spanned<String>, u32>,
+    set: std::collections::BTreeSet<String>,
+    boxed: Box<u8>,
+    shared: std::sync::Arc<String>,
+    pair: (String, i64),
+    array: [u8; 3],
+    options: Vec<Option<bool>>,
+}
+
+valid_de!(
+    collections,
+    Collections,
+    r#"
+map = { b = 2, a = 1 }
+set = ["x", "y"]
+boxed = 8
+shared = "shared"
+pair = ["one", 1]
+array = [1, 2, 3]
+options = [true]
+"#
+);
+invalid_de!(
+    collections_invalid,
+    Collections,
+    r#"
+map = { a = 1, b = "two" }
+set = ["x", "y", "x", "x"]
+boxed = -1
+shared = "shared"
+pair = ["one", "two"]
+array = [1, 2]
+options = ["no"]
+"#
+);
+
+#[test]
+fn hash_collections() {
+    use std::collections::{HashMap, HashSet};
+
+    let mut value = toml_span::parse("a = 1\nb = 2").unwrap();
+    let map = HashMap::<Cow<'_, str>, u8>::deserialize(&mut value).unwrap();
+    assert_eq!((map["a"], map["b"]), (1, 2));
+
+    let mut value = toml_span::parse("a = [3, 1, 2]").unwrap();
+    let map = HashMap::<String, HashSet<u8>>::deserialize(&mut value).unwrap();
+    assert_eq!(map["a"], HashSet::from([1, 2, 3]));
+
+    let mut value = toml_span::parse("a = [3, 1, 3]").unwrap();
+    let err = HashMap::<String, HashSet<u8>>::deserialize(&mut value).unwrap_err();
+    assert_eq!(err.errors[0].to_string(), "a[2]: duplicate value in set");
+}
+
+/// A key that ignores case, so that different keys in a table can be the same
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct CaseInsensitive(String);
+
+impl<'de> toml_span::DeserializeKey<'de> for CaseInsensitive {
+    fn deserialize_key(key: toml_span::value::Key<'de>) -> Result<Self, toml_span::DeserError> {
+        Ok(Self(key.name.to_lowercase()))
+    }
+}
+
+#[test]
+fn duplicate_map_keys() {
+    use std::collections::{BTreeMap, HashMap};
+
+    // The first key is the same with or without preserve_order
+    let toml = "A = 1\na = 2\nb = 3";
+    let mut value = toml_span::parse(toml).unwrap();
+    let err = BTreeMap::<CaseInsensitive, u8>::deserialize(&mut value).unwrap_err();
+    let mut value = toml_span::parse(toml).unwrap();
+    let hash_err = HashMap::<CaseInsensitive, u8>::deserialize(&mut value).unwrap_err();
+
+    for err in [err, hash_err] {
+        assert_eq!(err.errors.len(), 1);
+        let err = &err.errors[0];
+        assert_eq!(err.to_string(), "a: duplicate key: `a`");
+        let toml_span::ErrorKind::DuplicateKey { first, .. } = &err.kind else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!((first.start, err.span.start), (0, 6));
+    }
+}
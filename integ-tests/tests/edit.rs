@@ -0,0 +1,273 @@
+use toml_span::{
+    edit::{EditError, Editor},
+    value::{Value, ValueInner},
+};
+
+/// Applies the edits to the toml and asserts the edited document matches a
+/// snapshot and is still valid toml
+macro_rules! edit {
+    ($name:ident, $toml:expr, |$editor:ident| $edits:block) => {
+        #[test]
+        fn $name() {
+            let toml_str = $toml;
+            let value = toml_span::parse(toml_str).expect("failed to parse toml");
+            let mut $editor = Editor::new(toml_str, &value);
+            $edits
+            let edited = $editor.finish().expect("failed to apply edits");
+
+            if let Err(err) = toml_span::parse(&edited) {
+                panic!("edited document is invalid: {err}\n{edited}");
+            }
+
+            insta::assert_snapshot!(edited);
+        }
+    };
+}
+
+fn string(s: &str) -> Value<'_> {
+    Value::new(ValueInner::String(s.into()))
+}
+
+fn int(i: i64) -> Value<'static> {
+    Value::new(ValueInner::Integer(i))
+}
+
+const DENY: &str = r#"# The crates we don't want
+[bans]
+multiple-versions = "warn" # for now
+deny = [
+    # Use rustls instead
+    { name = "openssl" },
+    { name = "git2", wrappers = ["crate-a", "crate-b"] }, # c
+    { name = "libssh2-sys" },
+]
+skip = []
+
+[[bans.features]]
+name = "reqwest"
+deny = ["default-tls"]
+
+[bans.features.exact]
+allow = []
+
+[[bans.features]]
+name = "tokio"
+
+# Sources
+[sources]
+unknown-registry = 'deny'
+"#;
+
+edit!(remove_array_element, DENY, |editor| {
+    editor.remove("/bans/deny/1").unwrap();
+});
+edit!(remove_last_array_elements, DENY, |editor| {
+    editor.remove("/bans/deny/1").unwrap();
+    editor.remove("/bans/deny/2").unwrap();
+});
+edit!(remove_all_array_elements, DENY, |editor| {
+    for i in 0..3 {
+        editor.remove(&format!("/bans/deny/{i}")).unwrap();
+    }
+});
+edit!(
+    remove_last_elements_keeps_comments,
+    r#"a = [
+    'x', # keep me
+    'y',
+]
+b = [
+    'x', # keep me
+    'y'
+]
+c = ['x', 'y', 'z']
+d = [
+    'x', # keep me
+    'y', # y
+    'z', # z
+]
+e = { x = 1, y = 2 }
+"#,
+    |editor| {
+        editor.remove("/a/1").unwrap();
+        editor.remove("/b/1").unwrap();
+        editor.remove("/c/2").unwrap();
+        editor.remove("/d/1").unwrap();
+        editor.remove("/d/2").unwrap();
+        editor.remove("/e/y").unwrap();
+    }
+);
+edit!(remove_key, DENY, |editor| {
+    editor.remove("/bans/multiple-versions").unwrap();
+    editor.remove("/bans/deny/1/wrappers").unwrap();
+    editor.remove("/bans/deny/2/name").unwrap();
+});
+edit!(remove_table, DENY, |editor| {
+    editor.remove("/bans").unwrap();
+});
+edit!(remove_array_of_tables_element, DENY, |editor| {
+    editor.remove("/bans/features/0").unwrap();
+});
+edit!(set_values, DENY, |editor| {
+    editor
+        .set("/bans/multiple-versions", &string("deny"))
+        .unwrap();
+    editor
+        .set("/bans/deny/0/name", &string("native-tls"))
+        .unwrap();
+    editor.set("/sources/unknown-registry", &int(1)).unwrap();
+    editor.set_raw("/bans/skip", "[ 'a',  'b' ]").unwrap();
+});
+edit!(insert_keys, DENY, |editor| {
+    editor.insert("", "version", &int(2)).unwrap();
+    editor
+        .insert("/bans", "wildcards", &string("deny"))
+        .unwrap();
+    editor.insert("/bans", "highlight", &string("all")).unwrap();
+    editor
+        .insert("/bans/deny/2", "version", &string("<1.0"))
+        .unwrap();
+    editor
+        .insert("/bans/features/0/exact", "deny", &string("x"))
+        .unwrap();
+    editor
+        .insert("/sources", "allow-git", &string("url"))
+        .unwrap();
+});
+edit!(push_values, DENY, |editor| {
+    editor.push("/bans/skip", &string("a")).unwrap();
+    editor.push("/bans/skip", &string("b")).unwrap();
+    editor
+        .push("/bans/deny/1/wrappers", &string("crate-c"))
+        .unwrap();
+
+    let mut table = toml_span::value::Table::new();
+    table.insert(
+        toml_span::value::Key {
+            name: "name".into(),
+            span: Default::default(),
+        },
+        string("serde"),
+    );
+    let table = Value::new(ValueInner::Table(table));
+    editor.push("/bans/deny", &table).unwrap();
+    editor.push("/bans/features", &table).unwrap();
+});
+
+const LAYOUTS: &str = r#"key = "value"
+dotted.a = 1
+dotted.b.c = 2
+
+[implicit.child]
+x = 1
+
+[[arr]]
+[arr.implicit.child]
+y = 2
+
+[[arr]]
+z = 3
+"#;
+
+edit!(insert_dotted, LAYOUTS, |editor| {
+    editor.insert("/dotted", "d", &int(3)).unwrap();
+    editor.insert("/dotted/b", "e", &int(4)).unwrap();
+});
+edit!(insert_implicit, LAYOUTS, |editor| {
+    editor.insert("/implicit", "new", &int(1)).unwrap();
+    editor.insert("/arr/0/implicit", "new", &int(2)).unwrap();
+});
+edit!(remove_dotted, LAYOUTS, |editor| {
+    editor.remove("/dotted").unwrap();
+    editor.remove("/implicit").unwrap();
+});
+edit!(remove_implicit_in_array, LAYOUTS, |editor| {
+    editor.remove("/arr/0/implicit").unwrap();
+});
+
+edit!(
+    empty_containers,
+    "a = {}\nb = [ ]\nc = 'no newline'",
+    |editor| {
+        editor.insert("/a", "one", &int(1)).unwrap();
+        editor.insert("/a", "two", &int(2)).unwrap();
+        editor.push("/b", &int(1)).unwrap();
+        editor.push("/b", &int(2)).unwrap();
+        editor.insert("", "d", &string("")).unwrap();
+    }
+);
+edit!(
+    remove_then_append,
+    "a = [1]\nb = [1, 2,]\nc = { x = 1 }\nd = { x = 1, y = 2 }\ne = [\n    1,\n    2, # two\n]\n",
+    |editor| {
+        editor.remove("/a/0").unwrap();
+        editor.push("/a", &string("y")).unwrap();
+        editor.remove("/b/1").unwrap();
+        editor.push("/b", &string("y")).unwrap();
+        editor.remove("/c/x").unwrap();
+        editor.insert("/c", "k", &int(1)).unwrap();
+        editor.remove("/d/y").unwrap();
+        editor.insert("/d", "k", &int(1)).unwrap();
+        editor.remove("/e/1").unwrap();
+        editor.push("/e", &int(3)).unwrap();
+    }
+);
+edit!(
+    strings,
+    "basic = \"basic\"\nliteral = 'literal'\nempty = ''\nml = \"\"\"\nmulti\nline\"\"\"\nmle = ''''''\n'quoted key' = 1\n",
+    |editor| {
+        editor.set("/basic", &int(1)).unwrap();
+        editor.set("/literal", &int(2)).unwrap();
+        editor.set("/empty", &int(3)).unwrap();
+        editor.set("/ml", &int(4)).unwrap();
+        editor.set("/mle", &int(5)).unwrap();
+        editor.remove("/quoted key").unwrap();
+    }
+);
+
+edit!(
+    set_raw_values,
+    "a = 1\nb = 2\nc = 3\nd = 4\ne = 5\n",
+    |editor| {
+        editor.set_raw("/a", "''").unwrap();
+        editor.set_raw("/b", "\"two\"").unwrap();
+        editor.set_raw("/c", "'''\nthree'''").unwrap();
+        editor.set_raw("/d", "{ x = [1, 2] }").unwrap();
+        editor.set_raw("/e", "1979-05-27").unwrap();
+    }
+);
+
+#[test]
+fn errors() {
+    let toml = "a = 1\nb = { c.d = 1 }\n[t]\n";
+    let value = toml_span::parse(toml).unwrap();
+    let mut editor = Editor::new(toml, &value);
+
+    let errors = [
+        editor.set("/nope", &int(1)).unwrap_err(),
+        editor.insert("/a", "key", &int(1)).unwrap_err(),
+        editor.insert("", "a", &int(1)).unwrap_err(),
+        editor.push("/a", &int(1)).unwrap_err(),
+        editor.set("/t", &int(1)).unwrap_err(),
+        editor.remove("").unwrap_err(),
+        editor.remove("/b/c/d").unwrap_err(),
+        editor.set_raw("/a", "not valid").unwrap_err(),
+        editor.set_raw("/a", "3\nc = 4").unwrap_err(),
+        editor.set_raw("/a", "3 # comment").unwrap_err(),
+    ];
+
+    insta::assert_snapshot!(
+        errors
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+
+    editor.set("/a", &int(2)).unwrap();
+    editor.remove("/a").unwrap();
+    assert!(matches!(
+        editor.finish(),
+        Err(EditError::Overlapping { .. })
+    ));
+}
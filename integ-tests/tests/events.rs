@@ -0,0 +1,199 @@
+use toml_span::{
+    ErrorKind,
+    events::{Event, EventReader},
+    value::{Value, ValueInner},
+};
+
+fn src(toml: &str, span: toml_span::Span) -> &str {
+    &toml[span.start..span.end]
+}
+
+/// Writes each event on its own line, indented by its nesting
+fn events(toml: &str) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::new();
+    let mut indent = 0;
+    for event in EventReader::new(toml) {
+        let event = event.expect("failed to read event");
+        if matches!(event, Event::ArrayEnd { .. } | Event::InlineTableEnd { .. }) {
+            indent -= 1;
+        }
+
+        let pad = "  ".repeat(indent);
+        match &event {
+            Event::TableHeader { path, array, span } => {
+                let path: Vec<_> = path.iter().map(|k| k.name.as_ref()).collect();
+                writeln!(
+                    out,
+                    "{pad}header {path:?} array={array} {:?}",
+                    src(toml, *span)
+                )
+            }
+            Event::Key { path, span } => {
+                let path: Vec<_> = path.iter().map(|k| k.name.as_ref()).collect();
+                writeln!(out, "{pad}key {path:?} {:?}", src(toml, *span))
+            }
+            Event::Value(value) => {
+                writeln!(out, "{pad}value {:?} {:?}", value, src(toml, value.span))
+            }
+            Event::ArrayStart { .. } => writeln!(out, "{pad}array"),
+            Event::InlineTableStart { .. } => writeln!(out, "{pad}table"),
+            Event::ArrayEnd { span } | Event::InlineTableEnd { span } => {
+                writeln!(out, "{pad}end {:?}", src(toml, *span))
+            }
+        }
+        .unwrap();
+
+        if matches!(
+            event,
+            Event::ArrayStart { .. } | Event::InlineTableStart { .. }
+        ) {
+            indent += 1;
+        }
+    }
+    out
+}
+
+#[test]
+fn stream() {
+    let toml = r#"# A comment
+name = "toml-span"
+version.major = 0 # trailing
+nested = [[1, 2], [], { a = 1.5, b.c = true },]
+
+[[bin]]
+path = 'main.rs'
+
+[ target . "cfg(unix)" ]
+when = 1979-05-27T07:32:00Z
+empty = {}
+"#;
+
+    insta::assert_snapshot!(events(toml));
+}
+
+/// Collects the spans of every scalar value in the tree
+fn scalar_spans(value: &Value<'_>, spans: &mut Vec<toml_span::Span>) {
+    match value.as_ref() {
+        ValueInner::Table(table) => table.values().for_each(|v| scalar_spans(v, spans)),
+        ValueInner::Array(arr) => arr.iter().for_each(|v| scalar_spans(v, spans)),
+        _ => spans.push(value.span),
+    }
+}
+
+/// The events contain every scalar of a valid document, and fail with the
+/// same error as [`toml_span::parse`] for syntax errors
+#[test]
+fn matches_parse() {
+    let mut docs: Vec<_> = std::fs::read_dir("data")
+        .unwrap()
+        .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect();
+    docs.extend(
+        [
+            "a = [1, 2,]\nb = { c = [ {}, { d = 'e' } ] }",
+            "a = {\n  b = 1,\n  # comment\n  c = [\n    2,\n  ],\n}",
+            "a = 1 b = 2",
+            "a = [1 2]",
+            "a = { b = 1 c = 2 }",
+            "a = { b = 1,, }",
+            "a = [1,,]",
+            "[a\nb = 1",
+            "[a] b = 1",
+            "a.b = 1\na.b = 2",
+            "[a]\n[a]",
+            "a = \"unterminated",
+            "a = 1979-13-27",
+            "a = +",
+            "a = [",
+            "a = {",
+        ]
+        .map(String::from),
+    );
+
+    for doc in &docs {
+        let events: Result<Vec<_>, _> = EventReader::new(doc).collect();
+
+        match toml_span::parse(doc) {
+            Ok(value) => {
+                let events = events.unwrap_or_else(|err| panic!("{err}\n{doc}"));
+
+                let mut expected = Vec::new();
+                scalar_spans(&value, &mut expected);
+                expected.sort_by_key(|s| s.start);
+
+                let actual: Vec<_> = events
+                    .iter()
+                    .filter_map(|e| match e {
+                        Event::Value(v) => Some(v.span),
+                        _ => None,
+                    })
+                    .collect();
+                assert_eq!(actual, expected, "{doc}");
+            }
+            Err(err)
+                if matches!(
+                    err.kind,
+                    ErrorKind::DuplicateKey { .. }
+                        | ErrorKind::DuplicateTable { .. }
+                        | ErrorKind::RedefineAsArray
+                        | ErrorKind::DottedKeyInvalidType { .. }
+                ) =>
+            {
+                assert!(events.is_ok(), "{doc}");
+            }
+            Err(err) => {
+                let event_err = events.expect_err(doc);
+                assert_eq!(
+                    (event_err.to_string(), event_err.span),
+                    (err.to_string(), err.span),
+                    "{doc}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn stops_after_error() {
+    let mut reader = EventReader::new("a = 1\nb = = 2\nc = 3");
+    assert!(matches!(reader.next(), Some(Ok(Event::Key { .. }))));
+    assert!(matches!(reader.next(), Some(Ok(Event::Value(_)))));
+    assert!(matches!(reader.next(), Some(Err(_))));
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn options() {
+    use toml_span::{Limits, ParseOptions, TomlVersion};
+
+    let toml = "a = { b = [[[1]]], }";
+    let read = |options: ParseOptions| {
+        EventReader::with_options(toml, &options)
+            .unwrap()
+            .find_map(Result::err)
+            .map(|err| err.to_string())
+    };
+
+    assert_eq!(read(ParseOptions::default()), None);
+    assert_eq!(
+        read(ParseOptions {
+            version: TomlVersion::V1_0,
+            ..Default::default()
+        })
+        .as_deref(),
+        Some("trailing commas in inline tables are not supported in TOML 1.0")
+    );
+    assert_eq!(
+        read(ParseOptions {
+            limits: Limits {
+                max_depth: 4,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .as_deref(),
+        Some("exceeded the maximum depth of 4")
+    );
+}
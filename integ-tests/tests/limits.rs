@@ -0,0 +1,151 @@
+use integ_tests::error_snapshot;
+use toml_span::{ErrorKind, Limits, ParseOptions};
+
+/// Parses the toml with the limits and snapshots the error
+macro_rules! limit {
+    ($name:ident, $limits:expr, $toml:expr) => {
+        #[test]
+        fn $name() {
+            let toml_str = $toml;
+            let options = ParseOptions {
+                limits: $limits,
+                ..Default::default()
+            };
+            let err = match toml_span::parse_with(toml_str, &options) {
+                Ok(_) => panic!("expected the limit to be exceeded"),
+                Err(err) => err,
+            };
+            error_snapshot!($name, Some(err.to_diagnostic(())), toml_str);
+        }
+    };
+}
+
+const DOC: &str = r#"name = "toml-span"
+deps = ["a", "b", "c"]
+
+[a.b]
+c.d = { e = [[1]] }
+
+[x]
+"#;
+
+limit!(
+    input_length,
+    Limits {
+        max_input_len: 20,
+        ..Default::default()
+    },
+    DOC
+);
+limit!(
+    array_length,
+    Limits {
+        max_array_len: 2,
+        ..Default::default()
+    },
+    DOC
+);
+limit!(
+    string_length,
+    Limits {
+        max_string_len: 5,
+        ..Default::default()
+    },
+    DOC
+);
+limit!(
+    key_length,
+    Limits {
+        max_string_len: 5,
+        ..Default::default()
+    },
+    "a = 1\n[table.'long key']\n"
+);
+limit!(
+    table_count,
+    Limits {
+        max_tables: 1,
+        ..Default::default()
+    },
+    DOC
+);
+limit!(
+    array_depth,
+    Limits {
+        max_depth: 6,
+        ..Default::default()
+    },
+    DOC
+);
+limit!(
+    dotted_key_depth,
+    Limits {
+        max_depth: 3,
+        ..Default::default()
+    },
+    DOC
+);
+limit!(
+    header_depth,
+    Limits {
+        max_depth: 2,
+        ..Default::default()
+    },
+    "[a.b.c]\n"
+);
+limit!(
+    inline_key_depth,
+    Limits {
+        max_depth: 3,
+        ..Default::default()
+    },
+    "a = { b = 1, c.d.e = 2 }"
+);
+
+#[test]
+fn within_limits() {
+    let options = ParseOptions {
+        limits: Limits {
+            max_depth: 8,
+            max_input_len: DOC.len(),
+            max_array_len: 3,
+            max_string_len: 9,
+            max_tables: 2,
+        },
+        ..Default::default()
+    };
+    toml_span::parse_with(DOC, &options).unwrap();
+}
+
+/// Deeply nested documents fail with the default limits instead of
+/// overflowing the stack
+#[test]
+fn deep_nesting() {
+    let depth = 100_000;
+    let docs = [
+        format!("a = {}", "[".repeat(depth)),
+        format!("a = {}", "{ a = ".repeat(depth)),
+        format!("{} = 1", vec!["a"; depth].join(".")),
+        format!("[{}]", vec!["a"; depth].join(".")),
+        format!("[a]\n{} = 1", vec!["a"; 128].join(".")),
+    ];
+
+    for doc in &docs {
+        let err = toml_span::parse(doc).unwrap_err();
+        assert!(
+            matches!(
+                err.kind,
+                ErrorKind::LimitExceeded {
+                    limit: "depth",
+                    max: 128
+                }
+            ),
+            "{err:?}"
+        );
+
+        let (_, errors) = toml_span::parse_recovering(doc);
+        assert!(matches!(errors[0].kind, ErrorKind::LimitExceeded { .. }));
+    }
+
+    toml_span::parse(&format!("a = {}{}", "[".repeat(127), "]".repeat(127))).unwrap();
+}
@@ -0,0 +1,98 @@
+use toml_span::{
+    Deserialize, Span,
+    de_helpers::TableHelper,
+    span::{ColumnUnit, LineIndex, Position},
+};
+
+const TOML: &str = "a = 'é'\r\nb = '😀x'\n\nc = 1";
+
+fn pos(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+#[test]
+fn positions() {
+    let index = LineIndex::new(TOML);
+    assert_eq!(index.line_count(), 4);
+    assert_eq!(index.line_span(1), Some(Span::new(10, 22)));
+    assert_eq!(index.line_span(3), Some(Span::new(23, 28)));
+    assert_eq!(index.line_span(4), None);
+
+    let x = TOML.find('x').unwrap();
+    let cases = [
+        // The `\r` is part of the line
+        (TOML.find('\n').unwrap(), [pos(0, 9), pos(0, 8), pos(0, 8)]),
+        (x, [pos(1, 9), pos(1, 7), pos(1, 6)]),
+        (TOML.find('c').unwrap(), [pos(3, 0); 3]),
+        (TOML.len(), [pos(3, 5); 3]),
+    ];
+
+    for (offset, expected) in cases {
+        for (unit, expected) in [ColumnUnit::Utf8, ColumnUnit::Utf16, ColumnUnit::Char]
+            .into_iter()
+            .zip(expected)
+        {
+            assert_eq!(index.position(offset, unit), expected, "{offset} {unit:?}");
+            assert_eq!(
+                index.offset(expected, unit),
+                Some(offset),
+                "{offset} {unit:?}"
+            );
+        }
+    }
+
+    // Offsets past the end are clamped
+    assert_eq!(index.position(1000, ColumnUnit::Utf8), pos(3, 5));
+    assert_eq!(index.offset(pos(3, 6), ColumnUnit::Utf8), None);
+    assert_eq!(index.offset(pos(4, 0), ColumnUnit::Utf8), None);
+
+    // Columns in the middle of a character have no offset
+    assert_eq!(index.offset(pos(1, 6), ColumnUnit::Utf16), None);
+    assert_eq!(index.offset(pos(1, 7), ColumnUnit::Utf8), None);
+    assert_eq!(index.offset(pos(0, 6), ColumnUnit::Utf8), None);
+    assert_eq!(index.offset(pos(1, 7), ColumnUnit::Utf16), Some(x));
+
+    assert_eq!(
+        index.span(Span::new(x, x + 1), ColumnUnit::Utf16),
+        (pos(1, 7), pos(1, 8))
+    );
+}
+
+#[test]
+fn parser_errors_match() {
+    let toml = "a = 'é'\nb = 'é' c = 1";
+    let err = toml_span::parse(toml).unwrap_err();
+    let (line, column) = err.line_info.unwrap();
+
+    let index = LineIndex::new(toml);
+    let position = index.position(err.span.start, ColumnUnit::Utf8);
+    assert_eq!((position.line, position.column), (line, column));
+    assert_eq!((line, column), (1, 9));
+}
+
+#[derive(Debug)]
+struct Package {
+    _name: String,
+}
+
+impl<'de> Deserialize<'de> for Package {
+    fn deserialize(value: &mut toml_span::Value<'de>) -> Result<Self, toml_span::DeserError> {
+        let mut th = TableHelper::new(value)?;
+        let name = th.required("name")?;
+        th.finalize(None)?;
+        Ok(Self { _name: name })
+    }
+}
+
+#[test]
+fn fill_line_info() {
+    let toml = "[package]\nname = 1\nunknown = 'é'";
+    let mut value = toml_span::parse(toml).unwrap();
+    let mut err = Package::deserialize(value.pointer_mut("/package").unwrap()).unwrap_err();
+
+    assert!(err.errors.iter().all(|err| err.line_info.is_none()));
+    err.fill_line_info(&LineIndex::new(toml));
+
+    let line_info: Vec<_> = err.errors.iter().map(|err| err.line_info).collect();
+    assert_eq!(line_info, [Some((1, 7))]);
+}
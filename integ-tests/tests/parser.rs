@@ -134,6 +134,15 @@ invalid!(integer_range_positive, "a = 9223372036854775808");
 invalid!(integer_range_negative, "a = -9223372036854775809");
 invalid!(bare_number, "4");
 
+mod bad_leading_plus {
+    use super::invalid;
+
+    // Only decimal numbers can have a leading plus
+    invalid!(hex, "a = +0xff");
+    invalid!(octal, "a = +0o755");
+    invalid!(binary, "a = +0b1");
+}
+
 valid!(inline_tables);
 invalid!(eof, "key =");
 
@@ -267,15 +276,15 @@ b = { foo = "bar" }
 mod datetimes {
     use super::invalid;
 
-    invalid!(utc, "utc = 2016-09-09T09:09:09Z");
-    invalid!(utc_punkt, "utc = 2016-09-09T09:09:09.1Z");
-    invalid!(tz, "tz = 2016-09-09T09:09:09.2+10:00");
-    invalid!(tz_neg, "tz = 2016-09-09T09:09:09.123456789-02:00");
     invalid!(utc_trailing_dot, "utc = 2016-09-09T09:09:09.Z");
     invalid!(utc_invalid, "utc = 2016-9-09T09:09:09Z");
     invalid!(tz2, "tz = 2016-09-09T09:09:09+2:00");
     invalid!(tz_neg2, "tz = 2016-09-09T09:09:09-2:00");
     invalid!(tz_neg3, "tz = 2016-09-09T09:09:09Z-2:00");
+    invalid!(month, "date = 2016-13-09");
+    invalid!(not_leap_year, "date = 2100-02-29");
+    invalid!(hour, "time = 24:00:00");
+    invalid!(offset_on_time, "time = 09:09:09Z");
 }
 
 mod require_newlines {
@@ -361,3 +370,158 @@ tbl = {
 "#
     );
 }
+
+/// TOML 1.1 features are rejected when parsing as TOML 1.0
+mod toml_1_0 {
+    use integ_tests::error_snapshot;
+    use toml_span::{ParseOptions, TomlVersion};
+
+    fn options() -> ParseOptions {
+        ParseOptions {
+            version: TomlVersion::V1_0,
+            ..Default::default()
+        }
+    }
+
+    macro_rules! unsupported {
+        ($name:ident, $toml:expr) => {
+            #[test]
+            fn $name() {
+                let Err(error) = toml_span::parse_with($toml, &options()) else {
+                    panic!("expected TOML 1.1 syntax to be rejected");
+                };
+                error_snapshot!($name, Some(error.to_diagnostic(())), $toml);
+            }
+        };
+    }
+
+    unsupported!(inline_table_newlines, "tbl = {\n    key = 'value' }");
+    unsupported!(
+        inline_table_comments,
+        "tbl = { # comment\n    key = 'value' }"
+    );
+    unsupported!(inline_table_trailing_comma, "a = { a = 1, }");
+    unsupported!(escape_e, r#"csi = "\e[""#);
+    unsupported!(escape_x, r#"letter = "a\x61""#);
+    unsupported!(local_time_without_seconds, "t = 07:32");
+    unsupported!(datetime_without_seconds, "dt = 1979-05-27 07:32Z");
+
+    #[test]
+    fn supported() {
+        let toml = r#"
+tbl = { key = "value", nested = { inner = 42 } }
+empty = {}
+escapes = "\b\t\n\f\r\"\\\u0041\U00000042"
+odt = 1979-05-27T07:32:00.999999-07:00
+ldt = 1979-05-27 07:32:00
+ld = 1979-05-27
+lt = 07:32:00
+arr = [
+    1, # comment
+    2,
+]
+"#;
+        if let Err(err) = toml_span::parse_with(toml, &options()) {
+            panic!("failed to parse TOML 1.0: {err}");
+        }
+    }
+}
+
+/// Ensures that parsing can continue after errors so that all of them are
+/// reported, and that as much of the document as possible is recovered
+mod recovery {
+    use integ_tests::recovering;
+
+    recovering!(
+        multiple_lines,
+        r#"
+a = 1
+b = = 2
+c = "three"
+d = 4 5
+e = nope
+f = 6
+"#
+    );
+    recovering!(
+        unterminated_string,
+        r#"
+a = "open
+b = 'also open
+c = 3
+"#
+    );
+    recovering!(
+        multiline_values,
+        r#"
+arr = [
+    1,
+    2 3,
+    4,
+]
+tbl = {
+    a = 1
+    b = 2
+}
+after = true
+"#
+    );
+    recovering!(
+        unclosed_array,
+        r#"
+arr = [
+    1,
+    2,
+
+[table]
+key = "value"
+"#
+    );
+    recovering!(
+        bad_headers,
+        r#"
+[good]
+a = 1
+
+[bad.]
+b = 2
+
+[[also bad]]
+c = 3
+
+[good.child]
+d = 4
+"#
+    );
+    recovering!(
+        redefinitions,
+        r#"
+a = 1
+a = 2
+dotted.x = 1
+
+[dotted]
+y = 2
+
+[table]
+x = 1
+
+[table]
+x = 2
+
+[b.c]
+[[b]]
+inline = { x = 1, x = 2, y.z = 1, y = 2 }
+"#
+    );
+    recovering!(
+        mixed,
+        r#"
+a = 1
+a = 2
+b = ]
+c = 1979-13-27
+d = 1.
+"#
+    );
+}
@@ -0,0 +1,139 @@
+use toml_span::value::{Table, Value, ValueInner};
+
+/// Collects the paths of every key in the order the tables iterate them
+#[cfg(feature = "preserve_order")]
+fn keys(table: &Table<'_>, path: &str, out: &mut Vec<String>) {
+    for (key, value) in table {
+        let path = format!("{path}/{}", key.name);
+        out.push(path.clone());
+        collect(value, &path, out);
+    }
+}
+
+#[cfg(feature = "preserve_order")]
+fn collect(value: &Value<'_>, path: &str, out: &mut Vec<String>) {
+    match value.as_ref() {
+        ValueInner::Table(table) => keys(table, path, out),
+        ValueInner::Array(arr) => {
+            for (i, v) in arr.iter().enumerate() {
+                collect(v, &format!("{path}/{i}"), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(feature = "preserve_order")]
+#[test]
+fn document_order() {
+    let toml = r#"
+zebra = 1
+dotted.z = 2
+apple = { z = 1, y = 2, x = 3 }
+dotted.a = 3
+
+[[tables]]
+b = 1
+[tables.sub]
+z = 1
+a = 2
+
+[[tables]]
+a = 1
+
+[implicit.child]
+x = 1
+
+[between]
+z = 1
+a.z = 2
+a.a = 3
+"#;
+
+    let value = toml_span::parse(toml).unwrap();
+    let mut out = Vec::new();
+    collect(&value, "", &mut out);
+
+    assert_eq!(
+        out,
+        [
+            "/zebra",
+            "/dotted",
+            "/dotted/z",
+            "/dotted/a",
+            "/apple",
+            "/apple/z",
+            "/apple/y",
+            "/apple/x",
+            "/tables",
+            "/tables/0/b",
+            "/tables/0/sub",
+            "/tables/0/sub/z",
+            "/tables/0/sub/a",
+            "/tables/1/a",
+            "/implicit",
+            "/implicit/child",
+            "/implicit/child/x",
+            "/between",
+            "/between/z",
+            "/between/a",
+            "/between/a/z",
+            "/between/a/a",
+        ]
+    );
+}
+
+#[test]
+fn remove_keeps_order() {
+    let mut value = toml_span::parse("c = 1\nb = 2\na = 3\nd = 4").unwrap();
+    let ValueInner::Table(mut table) = value.take() else {
+        unreachable!()
+    };
+
+    assert!(table.remove("b").is_some());
+    assert_eq!(table.remove_entry("d").unwrap().0.name, "d");
+    assert!(table.remove("nope").is_none());
+
+    let keys: Vec<_> = table.keys().map(|k| k.name.as_ref()).collect();
+    if cfg!(feature = "preserve_order") {
+        assert_eq!(keys, ["c", "a"]);
+    } else {
+        assert_eq!(keys, ["a", "c"]);
+    }
+}
+
+/// The table has the same API whether `preserve_order` is enabled or not
+#[test]
+fn table_api() {
+    use toml_span::value::Key;
+
+    let key = |name: &'static str| Key {
+        name: name.into(),
+        span: Default::default(),
+    };
+    let mut table: Table<'_> = [("b", 1), ("a", 2)]
+        .into_iter()
+        .map(|(k, v)| (key(k), Value::new(ValueInner::Integer(v))))
+        .collect();
+
+    assert_eq!(table.len(), 2);
+    assert!(table.contains_key("a"));
+    assert_eq!(table["b"].as_integer(), Some(1));
+    assert_eq!(table.get_key_value("a").unwrap().0.name, "a");
+
+    table.insert(key("c"), Value::new(ValueInner::Integer(3)));
+    if let Some(value) = table.get_mut("c") {
+        value.set(ValueInner::Integer(4));
+    }
+    for value in table.values_mut() {
+        if let Some(i) = value.as_integer() {
+            value.set(ValueInner::Integer(i * 10));
+        }
+    }
+
+    let mut values: Vec<_> = table.values().filter_map(Value::as_integer).collect();
+    values.sort_unstable();
+    assert_eq!(values, [10, 20, 40]);
+    assert_eq!(table.iter().rev().len(), 3);
+    assert_eq!(table.into_keys().count(), 3);
+}
@@ -0,0 +1,149 @@
+use toml_span::{
+    Span,
+    ser::{self, Config, KeyOrder, StringStyle, TableStyle},
+    value::{Value, ValueInner},
+};
+
+/// Serializes the toml with the specified config and asserts the output
+/// matches a snapshot, that reparsing the output yields the same values, and
+/// that the serializer's spans match the spans of the reparsed values
+macro_rules! valid_ser {
+    ($name:ident, $toml:expr) => {
+        valid_ser!($name, $toml, Config::default());
+    };
+    ($name:ident, $toml:expr, $config:expr) => {
+        #[test]
+        fn $name() {
+            let toml_str = $toml;
+            let original = toml_span::parse(&toml_str).expect("failed to parse toml");
+            let (output, spans) =
+                ser::to_string_spanned(&original, &$config).expect("failed to serialize");
+
+            insta::assert_snapshot!(output);
+
+            let reparsed = toml_span::parse(&output).expect("failed to reparse output");
+            // Compare the json forms, whose keys are sorted, as the key order
+            // of the tables depends on the `preserve_order` feature
+            assert_eq!(
+                serde_json::to_value(&original).unwrap(),
+                serde_json::to_value(&reparsed).unwrap()
+            );
+
+            check_spans(&reparsed, &spans, &mut String::new());
+        }
+    };
+}
+
+fn check_spans(value: &Value<'_>, spans: &ser::SpanMap, pointer: &mut String) {
+    let expected = spans
+        .get(pointer.as_str())
+        .unwrap_or_else(|| panic!("no span emitted for '{pointer}'"));
+
+    // Tables defined with a header have a different span in the parser
+    // depending on how they are defined, so only check the values of tables
+    let len = pointer.len();
+    match value.as_ref() {
+        ValueInner::Table(tab) => {
+            for (k, v) in tab {
+                pointer.push('/');
+                pointer.push_str(&k.name.replace('~', "~0").replace('/', "~1"));
+                check_spans(v, spans, pointer);
+                pointer.truncate(len);
+            }
+        }
+        ValueInner::Array(arr) => {
+            if !arr.iter().all(|v| v.as_table().is_some()) {
+                assert_eq!(value.span, *expected, "span mismatch for '{pointer}'");
+            }
+
+            for (i, v) in arr.iter().enumerate() {
+                pointer.push_str(&format!("/{i}"));
+                check_spans(v, spans, pointer);
+                pointer.truncate(len);
+            }
+        }
+        _ => assert_eq!(value.span, *expected, "span mismatch for '{pointer}'"),
+    }
+}
+
+fn load(name: &str) -> String {
+    std::fs::read_to_string(format!("data/{name}.toml")).expect("failed to load toml")
+}
+
+const MIXED: &str = r#"
+title = "mixed"
+'quoted key' = "it's \"quoted\""
+'literal' = 'C:\Users'
+escapes = "tab\tnewline\nunicode\u0001"
+empty = ""
+float = 1.0
+nan = -nan
+dt = 1979-05-27T07:32:00.5Z
+
+[owner]
+name = "Tom"
+dob = 1979-05-27
+
+[servers.alpha]
+ip = "10.0.0.1"
+ports = [8000, 8001]
+
+[[products]]
+name = "Hammer"
+inline = { a = 1, b = { c = [] } }
+
+[[products]]
+name = "Nail"
+"#;
+
+valid_ser!(default_config, MIXED);
+valid_ser!(
+    inline_tables,
+    MIXED,
+    Config {
+        tables: TableStyle::Inline,
+        ..Default::default()
+    }
+);
+valid_ser!(
+    no_arrays_of_tables,
+    MIXED,
+    Config {
+        arrays_of_tables: false,
+        ..Default::default()
+    }
+);
+valid_ser!(
+    literal_strings_source_order,
+    MIXED,
+    Config {
+        strings: StringStyle::Literal,
+        key_order: KeyOrder::Source,
+        ..Default::default()
+    }
+);
+valid_ser!(fruit, load("fruit"));
+valid_ser!(tables_in_arrays, load("tables_in_arrays"));
+valid_ser!(key_names, load("key_names"));
+valid_ser!(strings, load("strings"));
+
+valid_ser!(
+    escaped_pointers,
+    "[t]\n'a/b' = 1\n'~' = 2\n'~1' = 3\n[t.a]\nb = 4\n"
+);
+
+#[test]
+fn escaped_pointer_spans() {
+    let value = toml_span::parse("[t]\n'a/b' = 1\n[t.a]\nb = 2\n").unwrap();
+    let (toml, spans) = ser::to_string_spanned(&value, &Config::default()).unwrap();
+
+    assert_eq!(&toml[spans["/t/a~1b"].start..spans["/t/a~1b"].end], "1");
+    assert_eq!(&toml[spans["/t/a/b"].start..spans["/t/a/b"].end], "2");
+}
+
+#[test]
+fn root_not_table() {
+    let value = Value::with_span(ValueInner::Integer(1), Span::new(1, 2));
+    let err = ser::to_string(&value).unwrap_err();
+    assert_eq!(err.to_string(), "expected a table, found integer");
+}
@@ -0,0 +1,58 @@
+use toml_span::value::Value;
+
+/// Finds the marker in the decoded string, and returns the source text its
+/// range maps to
+fn mapped<'t>(toml: &'t str, marker: &str) -> &'t str {
+    let mut value = toml_span::parse(toml).unwrap();
+    let s: &mut Value<'_> = value.pointer_mut("/s").unwrap();
+    let string = s.take_string(None).unwrap();
+
+    let start = string
+        .find(marker)
+        .unwrap_or_else(|| panic!("{marker:?} not in {string:?}"));
+    let span = s.string_span(toml, start..start + marker.len()).unwrap();
+    &toml[span.start..span.end]
+}
+
+#[test]
+fn maps_ranges() {
+    // Unescaped text maps to itself regardless of what precedes it
+    for toml in [
+        r#"s = "XY""#,
+        r#"s = "\t\"\\XY\n""#,
+        r#"s = "\u00e9\U0001F600XY""#,
+        r#"s = "\x41\eXY""#,
+        "s = 'a\\b XY'",
+        "s = \"\"\"\nline\r\nXY\"\"\"",
+        "s = \"\"\"\r\na \\\n\n   \tXY\"\"\"",
+        "s = \"\"\"a \\  \r\n  XY\"\"\"",
+        "s = '''\n\r\nXY'''",
+        "s = \"\"\"XY\"\"\"\"\"",
+    ] {
+        assert_eq!(mapped(toml, "XY"), "XY", "{toml}");
+    }
+
+    // Escapes map to the entire escape sequence
+    assert_eq!(mapped(r#"s = "a\tb""#, "\t"), r"\t");
+    assert_eq!(mapped(r#"s = "a\u00e9b""#, "é"), r"\u00e9");
+    assert_eq!(mapped(r#"s = "a\U0001F600b""#, "😀b"), r"\U0001F600b");
+    assert_eq!(mapped("s = \"\"\"a\r\nb\"\"\"", "a\nb"), "a\r\nb");
+    assert_eq!(mapped("s = \"\"\"a\\\n  b\"\"\"", "ab"), "a\\\n  b");
+
+    let toml = "s = 'abc'\ni = 1";
+    let value = toml_span::parse(toml).unwrap();
+    let s = value.pointer("/s").unwrap();
+    assert_eq!(s.string_span(toml, 3..3), Some(toml_span::Span::new(8, 8)));
+    assert_eq!(s.string_span(toml, 2..4), None);
+    assert_eq!(value.pointer("/i").unwrap().string_span(toml, 0..0), None);
+
+    let toml = "e = ''\nm = \"\"\"\r\n\"\"\"";
+    let value = toml_span::parse(toml).unwrap();
+    let e = value.pointer("/e").unwrap();
+    assert_eq!(e.string_span(toml, 0..0), Some(toml_span::Span::new(4, 4)));
+    let m = value.pointer("/m").unwrap();
+    assert_eq!(
+        m.string_span(toml, 0..0),
+        Some(toml_span::Span::new(16, 16))
+    );
+}
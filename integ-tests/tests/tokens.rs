@@ -171,4 +171,30 @@ fn bad_comment() {
     t.step().unwrap().unwrap();
     assert_eq!(t.step(), Err(Error::Unexpected(1, '\u{0}')));
     assert!(t.step().unwrap().is_none());
+
+    // DEL is a control character, which is not allowed in comments
+    let mut t = Tokenizer::new("#\u{7f}");
+    assert_eq!(t.step().unwrap().unwrap().1, Token::Comment("#"));
+    assert_eq!(t.step(), Err(Error::Unexpected(1, '\u{7f}')));
+    assert!(t.step().unwrap().is_none());
+}
+
+#[test]
+fn reset_to() {
+    let input = "a = 'é'\r\nb = 2";
+    let mut t = Tokenizer::new(input);
+    while t.step().unwrap().is_some() {}
+
+    t.reset_to(8);
+    assert_eq!(t.current(), 8);
+    let (span, token) = t.step().unwrap().unwrap();
+    assert_eq!((span.start, span.end), (8, 10));
+    assert_eq!(token, Token::Newline);
+    let (span, token) = t.step().unwrap().unwrap();
+    assert_eq!((span.start, span.end), (10, 11));
+    assert_eq!(token, Token::Keylike("b"));
+
+    t.reset_to(4);
+    let (span, _) = t.step().unwrap().unwrap();
+    assert_eq!((span.start, span.end), (5, 7));
 }
@@ -0,0 +1,87 @@
+//! Runs the vendored [toml-test](https://github.com/toml-lang/toml-test)
+//! corpus, see `toml-test/README.md`
+
+use integ_tests::toml_test;
+use std::path::{Path, PathBuf};
+use toml_span::TomlVersion;
+
+/// Tests that are known to fail, relative to the `toml-test` directory, each
+/// of which should be removed once the parser is fixed
+const KNOWN_FAILURES: &[&str] = &[];
+
+/// Recursively collects the files with the extension in the directory
+fn files(dir: &Path, ext: &str, out: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files(&path, ext, out);
+        } else if path.extension().is_some_and(|e| e == ext) {
+            out.push(path);
+        }
+    }
+}
+
+/// Runs every valid and invalid test for the version, returning the tests
+/// that fail, and the [`KNOWN_FAILURES`] that pass
+fn run(dir: &str, version: TomlVersion) -> Vec<String> {
+    let root = Path::new("toml-test").join(dir);
+    let mut failures = Vec::new();
+
+    let mut valid = Vec::new();
+    files(&root.join("valid"), "toml", &mut valid);
+    for path in valid {
+        let toml = std::fs::read(&path).unwrap();
+        let expected: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(path.with_extension("json")).unwrap()).unwrap();
+
+        let name = name(dir, &root, &path);
+        let failure = match toml_test::decode(&toml, version) {
+            Ok(actual) if toml_test::matches(&expected, &actual) => None,
+            Ok(actual) => Some(format!("decoded to {actual}")),
+            Err(err) => Some(err),
+        };
+        check(&name, failure, &mut failures);
+    }
+
+    let mut invalid = Vec::new();
+    files(&root.join("invalid"), "toml", &mut invalid);
+    for path in invalid {
+        let toml = std::fs::read(&path).unwrap();
+        let failure = toml_test::decode(&toml, version)
+            .ok()
+            .map(|actual| format!("decoded to {actual}"));
+        check(&name(dir, &root, &path), failure, &mut failures);
+    }
+
+    failures.sort();
+    failures
+}
+
+/// The name of a test, which is its path relative to the `toml-test`
+/// directory with `/` separators
+fn name(dir: &str, root: &Path, path: &Path) -> String {
+    let name = path.strip_prefix(root).unwrap().display().to_string();
+    format!("{dir}/{}", name.replace('\\', "/"))
+}
+
+/// Records the test as failed if its result doesn't match whether it is a
+/// known failure
+fn check(name: &str, failure: Option<String>, failures: &mut Vec<String>) {
+    match (failure, KNOWN_FAILURES.contains(&name)) {
+        (Some(failure), false) => failures.push(format!("{name}: {failure}")),
+        (None, true) => failures.push(format!("{name}: passed but is a known failure")),
+        _ => {}
+    }
+}
+
+#[test]
+fn toml_1_0() {
+    let failures = run("1.0.0", TomlVersion::V1_0);
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn toml_1_1() {
+    let failures = run("1.1.0", TomlVersion::V1_1);
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
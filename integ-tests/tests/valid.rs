@@ -98,3 +98,27 @@ mod numbers {
     valid!(integers);
     valid!(floats);
 }
+
+mod datetimes {
+    use super::valid;
+
+    valid!(utc, "utc = 2016-09-09T09:09:09Z");
+    valid!(utc_punkt, "utc = 2016-09-09T09:09:09.1Z");
+    valid!(tz, "tz = 2016-09-09T09:09:09.2+10:00");
+    valid!(tz_neg, "tz = 2016-09-09T09:09:09.123456789-02:00");
+    valid!(
+        all_forms,
+        r#"
+odt1 = 1979-05-27T07:32:00Z
+odt2 = 1979-05-27t00:32:00-07:00
+odt3 = 1979-05-27 00:32:00.999999+07:00
+ldt = 1979-05-27T07:32:00
+ld = 1979-05-27
+lt = 00:32:00.999999
+leap = 2024-02-29
+arr = [1979-05-27, 07:32:00, 1979-05-27T07:32:00Z]
+inline = { ld = 1979-05-27, lt = 07:32 }
+"#
+    );
+    valid!(optional_seconds, "ldt = 1979-05-27T07:32\nlt = 07:32");
+}
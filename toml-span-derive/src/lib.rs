@@ -0,0 +1,370 @@
+//! Provides `#[derive(Deserialize)]` for [`toml_span::Deserialize`](https://docs.rs/toml-span/latest/toml_span/trait.Deserialize.html)
+//!
+//! This crate should not be used directly, instead enable the `derive` feature
+//! of `toml-span`, which re-exports the macro as `toml_span::Deserialize`
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    Data, DeriveInput, ExprPath, Fields, GenericArgument, GenericParam, Lifetime, LifetimeParam,
+    LitStr, PathArguments, Type, ext::IdentExt, parse_quote,
+};
+
+/// Derives `toml_span::Deserialize` for a struct
+///
+/// The generated implementation uses `toml_span::de_helpers::TableHelper`, so
+/// all errors for the table, as well as any nested types, are accumulated
+/// rather than returning at the first failure.
+///
+/// # Container attributes
+///
+/// - `#[toml_span(rename_all = "...")]` - Renames all fields according to the
+///   specified case convention, one of `lowercase`, `UPPERCASE`, `PascalCase`,
+///   `camelCase`, `snake_case`, `SCREAMING_SNAKE_CASE`, `kebab-case`, or
+///   `SCREAMING-KEBAB-CASE`
+/// - `#[toml_span(deny_unknown_fields)]` - Keys in the table that don't
+///   correspond to a field produce an `ErrorKind::UnexpectedKeys` error. By
+///   default unknown keys are left in the table.
+/// - `#[toml_span(warn_unknown_fields)]` - Keys in the table that don't
+///   correspond to a field are reported as an `ErrorKind::UnexpectedKeys`
+///   warning, see `toml_span::Value::take_warnings`. Cannot be combined with
+///   `deny_unknown_fields`.
+///
+/// # Field attributes
+///
+/// - `#[toml_span(rename = "...")]` - Uses the specified key name instead of
+///   the field name
+/// - `#[toml_span(default)]` - Uses `Default::default()` if the key is not present
+/// - `#[toml_span(default = "path")]` - Calls the specified function if the key
+///   is not present
+/// - `#[toml_span(flatten)]` - Deserializes the field from the keys that remain
+///   in the table after all other fields have been deserialized
+///
+/// Fields of type `Option<T>` are optional, all other fields without a
+/// `default` are required.
+#[proc_macro_derive(Deserialize, attributes(toml_span))]
+pub fn derive_deserialize(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let container = Container::from_attrs(&input.attrs)?;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`Deserialize` can only be derived for structs",
+        ));
+    };
+
+    // Reuse the first lifetime of the type if there is one so that borrowed
+    // fields, eg. `Cow<'a, str>`, can borrow from the toml source
+    let mut generics = input.generics.clone();
+    let de = if let Some(lt) = generics.lifetimes().next() {
+        lt.lifetime.clone()
+    } else {
+        let de = Lifetime::new("'de", Span::call_site());
+        generics
+            .params
+            .insert(0, GenericParam::Lifetime(LifetimeParam::new(de.clone())));
+        de
+    };
+
+    for tp in generics.type_params_mut() {
+        tp.bounds.push(parse_quote!(::toml_span::Deserialize<#de>));
+    }
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let body = match &data.fields {
+        Fields::Named(fields) => {
+            let mut fetch = Vec::new();
+            let mut flatten = Vec::new();
+            let mut init = Vec::new();
+
+            for (i, field) in fields.named.iter().enumerate() {
+                let attrs = FieldAttrs::from_attrs(&field.attrs)?;
+                let ident = field.ident.as_ref().expect("named field");
+                let var = format_ident!("__field{i}");
+                let ty = &field.ty;
+
+                if attrs.flatten {
+                    if attrs.rename.is_some() || attrs.default.is_some() {
+                        return Err(syn::Error::new_spanned(
+                            ident,
+                            "`flatten` cannot be combined with `rename` or `default`",
+                        ));
+                    }
+
+                    flatten.push(quote! { let #var = __th.flatten::<#ty>(); });
+                    init.push(quote! { #ident: #var.unwrap() });
+                    continue;
+                }
+
+                let key = attrs.rename.unwrap_or_else(|| {
+                    let name = ident.unraw().to_string();
+                    container
+                        .rename_all
+                        .map_or_else(|| name.clone(), |rule| rule.apply(&name))
+                });
+
+                // Option fields deserialize their inner type, so that they
+                // don't need a `Deserialize` impl for `Option` itself
+                match (attrs.default, option_inner(ty)) {
+                    (Some(FieldDefault::Path(path)), Some(inner)) => {
+                        fetch.push(
+                            quote! { let #var = __th.optional::<#inner>(#key).or_else(#path); },
+                        );
+                        init.push(quote! { #ident: #var });
+                    }
+                    (Some(FieldDefault::Trait), None) => {
+                        fetch.push(
+                            quote! { let #var = __th.optional::<#ty>(#key).unwrap_or_default(); },
+                        );
+                        init.push(quote! { #ident: #var });
+                    }
+                    (Some(FieldDefault::Path(path)), None) => {
+                        fetch.push(
+                            quote! { let #var = __th.optional::<#ty>(#key).unwrap_or_else(#path); },
+                        );
+                        init.push(quote! { #ident: #var });
+                    }
+                    (Some(FieldDefault::Trait) | None, Some(inner)) => {
+                        fetch.push(quote! { let #var = __th.optional::<#inner>(#key); });
+                        init.push(quote! { #ident: #var });
+                    }
+                    (None, None) => {
+                        fetch.push(quote! { let #var = __th.required::<#ty>(#key).ok(); });
+                        init.push(quote! { #ident: #var.unwrap() });
+                    }
+                }
+            }
+
+            let finalize = if container.deny_unknown_fields {
+                quote! {
+                    __th.report_warnings(__value);
+                    __th.finalize(None)?;
+                }
+            } else if container.warn_unknown_fields {
+                quote! { __th.finalize_warn_unknown(__value)?; }
+            } else {
+                quote! { __th.finalize(Some(__value))?; }
+            };
+
+            quote! {
+                let mut __th = ::toml_span::de_helpers::TableHelper::new(__value)?;
+                #(#fetch)*
+                #(#flatten)*
+                #finalize
+                // If any field failed, finalize has already returned the errors
+                ::core::result::Result::Ok(Self { #(#init),* })
+            }
+        }
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            let ty = &fields.unnamed[0].ty;
+            quote! {
+                ::core::result::Result::Ok(Self(<#ty as ::toml_span::Deserialize<#de>>::deserialize(__value)?))
+            }
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`Deserialize` can only be derived for structs with named fields, or newtype structs",
+            ));
+        }
+    };
+
+    let name = &input.ident;
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::toml_span::Deserialize<#de> for #name #ty_generics #where_clause {
+            fn deserialize(
+                __value: &mut ::toml_span::value::Value<#de>,
+            ) -> ::core::result::Result<Self, ::toml_span::DeserError> {
+                #body
+            }
+        }
+    })
+}
+
+/// Returns the `T` in `Option<T>`
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(tp) = ty else {
+        return None;
+    };
+    if tp.qself.is_some() {
+        return None;
+    }
+
+    let last = tp.path.segments.last()?;
+    if last.ident != "Option" {
+        return None;
+    }
+
+    let PathArguments::AngleBracketed(args) = &last.arguments else {
+        return None;
+    };
+
+    if args.args.len() != 1 {
+        return None;
+    }
+
+    match &args.args[0] {
+        GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct Container {
+    rename_all: Option<RenameRule>,
+    deny_unknown_fields: bool,
+    warn_unknown_fields: bool,
+}
+
+impl Container {
+    fn from_attrs(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut container = Self::default();
+
+        for attr in attrs {
+            if !attr.path().is_ident("toml_span") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    let rule: LitStr = meta.value()?.parse()?;
+                    container.rename_all = Some(RenameRule::parse(&rule)?);
+                    Ok(())
+                } else if meta.path.is_ident("deny_unknown_fields") {
+                    container.deny_unknown_fields = true;
+                    Ok(())
+                } else if meta.path.is_ident("warn_unknown_fields") {
+                    container.warn_unknown_fields = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown toml_span container attribute"))
+                }
+            })?;
+
+            if container.deny_unknown_fields && container.warn_unknown_fields {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "deny_unknown_fields and warn_unknown_fields cannot be combined",
+                ));
+            }
+        }
+
+        Ok(container)
+    }
+}
+
+enum FieldDefault {
+    Trait,
+    Path(ExprPath),
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    default: Option<FieldDefault>,
+    flatten: bool,
+}
+
+impl FieldAttrs {
+    fn from_attrs(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut field = Self::default();
+
+        for attr in attrs {
+            if !attr.path().is_ident("toml_span") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    field.rename = Some(name.value());
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    if meta.input.peek(syn::Token![=]) {
+                        let path: LitStr = meta.value()?.parse()?;
+                        field.default = Some(FieldDefault::Path(path.parse()?));
+                    } else {
+                        field.default = Some(FieldDefault::Trait);
+                    }
+                    Ok(())
+                } else if meta.path.is_ident("flatten") {
+                    field.flatten = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown toml_span field attribute"))
+                }
+            })?;
+        }
+
+        Ok(field)
+    }
+}
+
+#[derive(Copy, Clone)]
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(lit: &LitStr) -> syn::Result<Self> {
+        Ok(match lit.value().as_str() {
+            "lowercase" => Self::Lower,
+            "UPPERCASE" => Self::Upper,
+            "PascalCase" => Self::Pascal,
+            "camelCase" => Self::Camel,
+            "snake_case" => Self::Snake,
+            "SCREAMING_SNAKE_CASE" => Self::ScreamingSnake,
+            "kebab-case" => Self::Kebab,
+            "SCREAMING-KEBAB-CASE" => Self::ScreamingKebab,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    "unknown rename rule, expected one of `lowercase`, `UPPERCASE`, `PascalCase`, `camelCase`, `snake_case`, `SCREAMING_SNAKE_CASE`, `kebab-case`, `SCREAMING-KEBAB-CASE`",
+                ));
+            }
+        })
+    }
+
+    /// Applies the rule to a field name, which is assumed to be `snake_case`
+    fn apply(self, field: &str) -> String {
+        match self {
+            Self::Lower | Self::Snake => field.to_owned(),
+            Self::Upper | Self::ScreamingSnake => field.to_ascii_uppercase(),
+            Self::Kebab => field.replace('_', "-"),
+            Self::ScreamingKebab => field.to_ascii_uppercase().replace('_', "-"),
+            Self::Pascal | Self::Camel => {
+                let mut out = String::with_capacity(field.len());
+                let mut capitalize = matches!(self, Self::Pascal);
+                for c in field.chars() {
+                    if c == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        out.push(c.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        out.push(c);
+                    }
+                }
+                out
+            }
+        }
+    }
+}
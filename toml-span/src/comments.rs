@@ -0,0 +1,160 @@
+//! Provides [`Comments`], which attaches the comments in a toml document to
+//! the keys, tables, and array elements they document
+
+use crate::{
+    Span,
+    tokens::{Token, Tokenizer},
+    value::{Key, Value},
+};
+use std::collections::BTreeMap;
+
+/// A single `#` comment
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Comment<'de> {
+    /// The raw comment, including the leading `#`, but not the line terminator
+    pub raw: &'de str,
+    /// The span of the raw comment
+    pub span: Span,
+}
+
+impl<'de> Comment<'de> {
+    /// The text of the comment, without the leading `#` and surrounding
+    /// whitespace
+    #[inline]
+    pub fn text(&self) -> &'de str {
+        self.raw[1..].trim()
+    }
+}
+
+/// The comments attached to a key, table header, or array element
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ItemComments<'de> {
+    /// The comments on the lines directly above the item, in order
+    ///
+    /// A blank line between a comment and the item detaches the comment, as
+    /// does another item on the same line as the item, before it.
+    pub leading: Vec<Comment<'de>>,
+    /// The comment on the same line as the end of the item, if there is one
+    pub trailing: Option<Comment<'de>>,
+}
+
+/// The comments in a toml document, attached to the keys, table headers, and
+/// array elements they document
+///
+/// ```toml
+/// # Leading comment for `name`
+/// name = "toml-span" # Trailing comment for `name`
+///
+/// # Leading comment for the `[bans]` table
+/// [bans] # Trailing comment for the `[bans]` table
+/// deny = [
+///     # Leading comment for the first element
+///     "openssl", # Trailing comment for the first element
+/// ]
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Comments<'de> {
+    all: Vec<Comment<'de>>,
+    attached: BTreeMap<usize, ItemComments<'de>>,
+}
+
+impl<'de> Comments<'de> {
+    /// Gets the comments attached to a key, either of a `key = value` pair,
+    /// or the last key of a table header
+    ///
+    /// For dotted keys the comments are attached to the last key, the one
+    /// the value belongs to.
+    #[inline]
+    pub fn key(&self, key: &Key<'_>) -> Option<&ItemComments<'de>> {
+        self.attached.get(&key.span.start)
+    }
+
+    /// Gets the comments attached to a value, either a table defined with a
+    /// header, including the elements of an array of tables, or an element
+    /// of an inline array
+    #[inline]
+    pub fn value(&self, value: &Value<'_>) -> Option<&ItemComments<'de>> {
+        self.attached.get(&value.span.start)
+    }
+
+    /// Gets all of the comments in the document, attached or not, in the
+    /// order they appear
+    #[inline]
+    pub fn all(&self) -> &[Comment<'de>] {
+        &self.all
+    }
+
+    /// Returns true if there are no comments in the document, or they were not
+    /// collected
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.all.is_empty()
+    }
+}
+
+/// An item that comments can be attached to, recorded by the parser
+pub(crate) struct Item {
+    /// The offset the comments are looked up by
+    pub(crate) key: usize,
+    /// The offset of the start of the item, used to find leading comments
+    pub(crate) start: usize,
+    /// The offset of the end of the item, used to find a trailing comment
+    pub(crate) end: usize,
+}
+
+/// Finds all of the comments in the document and attaches them to the items
+pub(crate) fn attach<'de>(input: &'de str, items: &[Item]) -> Comments<'de> {
+    // The document has already been parsed, so every comment the tokenizer
+    // encounters is an actual comment rather than part of a string
+    let mut all = Vec::new();
+    let mut tokens = Tokenizer::new(input);
+    while let Ok(Some((span, token))) = tokens.step() {
+        if let Token::Comment(raw) = token {
+            all.push(Comment { raw, span });
+        }
+    }
+
+    let line_start = |offset: usize| input[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let comment_at = |offset: usize| {
+        all.binary_search_by_key(&offset, |c| c.span.start)
+            .ok()
+            .map(|i| all[i])
+    };
+
+    let mut attached = BTreeMap::new();
+    for item in items {
+        let mut comments = ItemComments::default();
+
+        let mut line = line_start(item.start);
+        if input[line..item.start].trim().is_empty() {
+            // Walk up the lines that consist only of a comment
+            while line > 0 {
+                let prev = line_start(line - 1);
+                let text = &input[prev..line];
+                let Some(comment) =
+                    comment_at(prev + text.len() - text.trim_start_matches([' ', '\t']).len())
+                else {
+                    break;
+                };
+
+                comments.leading.push(comment);
+                line = prev;
+            }
+            comments.leading.reverse();
+        }
+
+        let after = &input[item.end..];
+        let before_comment = after.trim_start_matches([' ', '\t']);
+        let before_comment = before_comment
+            .strip_prefix(',')
+            .unwrap_or(before_comment)
+            .trim_start_matches([' ', '\t']);
+        comments.trailing = comment_at(input.len() - before_comment.len());
+
+        if !comments.leading.is_empty() || comments.trailing.is_some() {
+            attached.insert(item.key, comments);
+        }
+    }
+
+    Comments { all, attached }
+}
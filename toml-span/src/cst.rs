@@ -0,0 +1,794 @@
+//! Provides a lossless concrete syntax tree of a toml document, which keeps
+//! every token, including whitespace, comments, newlines, quotes, and commas,
+//! so that the document can be printed back exactly as it was written
+
+use crate::{
+    Error, Span,
+    de::{self, Deserializer, E, ParseOptions, TableValues, TreeBuilder, Val, ValueStart},
+    tokens::Token,
+    value::{self, Value},
+};
+use std::fmt;
+
+/// A single token of the document
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Leaf<'de> {
+    /// The token exactly as it appears in the document
+    pub raw: &'de str,
+    /// The span of the raw token
+    pub span: Span,
+}
+
+impl fmt::Display for Leaf<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.raw)
+    }
+}
+
+/// A token that doesn't affect the meaning of the document
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Trivia<'de> {
+    /// One or more spaces or tabs
+    Whitespace(Leaf<'de>),
+    /// A `#` comment, not including the line terminator
+    Comment(Leaf<'de>),
+    /// A `\n` or `\r\n` line terminator
+    Newline(Leaf<'de>),
+}
+
+impl<'de> Trivia<'de> {
+    /// The underlying token
+    #[inline]
+    pub fn leaf(&self) -> &Leaf<'de> {
+        match self {
+            Self::Whitespace(leaf) | Self::Comment(leaf) | Self::Newline(leaf) => leaf,
+        }
+    }
+}
+
+impl fmt::Display for Trivia<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.leaf().fmt(f)
+    }
+}
+
+/// A toml document parsed with [`parse`]
+#[derive(Debug)]
+pub struct Document<'de> {
+    /// The UTF-8 byte order mark at the start of the document, if present
+    pub bom: Option<Leaf<'de>>,
+    /// The lines of the document, in order
+    pub items: Vec<Item<'de>>,
+    input: &'de str,
+}
+
+impl<'de> Document<'de> {
+    /// Converts the document into a [`ValueInner::Table`](crate::value::ValueInner::Table),
+    /// the same one [`crate::parse`] returns for the document
+    ///
+    /// This is where semantic errors, such as duplicate keys or tables, are
+    /// detected.
+    ///
+    /// The [`Scalar`] values are moved out of the tree, so this panics if any
+    /// of them has been [taken](Value::take).
+    pub fn into_value(self) -> Result<Value<'de>, Error> {
+        let mut builder = TreeBuilder::new(self.input);
+        let mut items = self.items.into_iter().peekable();
+
+        while let Some(item) = items.next() {
+            match item {
+                Item::Trivia(_) => {}
+                Item::TableHeader(header) => {
+                    // The table extends to the end of the line the header is on
+                    let mut end = header.close.span.end;
+                    while let Some(Item::Trivia(trivia)) = items.peek() {
+                        let trivia = *trivia;
+                        items.next();
+                        end = trivia.leaf().span.end;
+                        if matches!(trivia, Trivia::Newline(_)) {
+                            break;
+                        }
+                    }
+
+                    let at = header.open.span.start;
+                    builder.table(header.key.into_keys(), header.array, at, end);
+                }
+                Item::KeyValue(kv) => {
+                    let at = kv.key.span().start;
+                    let end = kv.value.span().end;
+                    let (key, value) = kv.into_parts(&builder.de)?;
+                    builder.key_value(key, value, at, end)?;
+                }
+            }
+        }
+
+        builder.finish()
+    }
+}
+
+impl fmt::Display for Document<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(bom) = &self.bom {
+            bom.fmt(f)?;
+        }
+        self.items.iter().try_for_each(|item| item.fmt(f))
+    }
+}
+
+/// A top level element of a [`Document`]
+#[derive(Debug)]
+pub enum Item<'de> {
+    /// Whitespace, a comment, or a newline
+    Trivia(Trivia<'de>),
+    /// A `[table]` or `[[array.of.tables]]` header
+    TableHeader(TableHeader<'de>),
+    /// A `key = value` pair
+    KeyValue(KeyValue<'de>),
+}
+
+impl<'de> From<Trivia<'de>> for Item<'de> {
+    fn from(trivia: Trivia<'de>) -> Self {
+        Self::Trivia(trivia)
+    }
+}
+
+impl fmt::Display for Item<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Trivia(trivia) => trivia.fmt(f),
+            Self::TableHeader(header) => header.fmt(f),
+            Self::KeyValue(kv) => kv.fmt(f),
+        }
+    }
+}
+
+/// A `[table]` or `[[array.of.tables]]` header
+///
+/// The whitespace, comment, and newline following the header are separate
+/// [`Item::Trivia`]s.
+#[derive(Debug)]
+pub struct TableHeader<'de> {
+    /// The opening `[` or `[[`
+    pub open: Leaf<'de>,
+    /// The key of the table
+    pub key: Key<'de>,
+    /// The closing `]` or `]]`
+    pub close: Leaf<'de>,
+    /// True if this is an array of tables header
+    pub array: bool,
+}
+
+impl TableHeader<'_> {
+    /// The span from the opening to the closing bracket(s)
+    #[inline]
+    pub fn span(&self) -> Span {
+        Span::new(self.open.span.start, self.close.span.end)
+    }
+}
+
+impl fmt::Display for TableHeader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.open.fmt(f)?;
+        self.key.fmt(f)?;
+        self.close.fmt(f)
+    }
+}
+
+/// A single key in a [`Key`], bare or quoted
+#[derive(Clone, Debug)]
+pub struct KeySegment<'de> {
+    /// The key as it appears in the document, including any quotes
+    pub raw: Leaf<'de>,
+    /// The key, with its span excluding any quotes, as in a parsed [`Value`]
+    pub key: value::Key<'de>,
+}
+
+/// An element of a [`Key`]
+#[derive(Clone, Debug)]
+pub enum KeyPart<'de> {
+    /// Whitespace around a key segment or `.`, or, in an inline table,
+    /// newlines and comments between the key and the `=`
+    Trivia(Trivia<'de>),
+    /// A bare or quoted key
+    Segment(KeySegment<'de>),
+    /// The `.` between two segments of a dotted key
+    Dot(Leaf<'de>),
+}
+
+impl<'de> From<Trivia<'de>> for KeyPart<'de> {
+    fn from(trivia: Trivia<'de>) -> Self {
+        Self::Trivia(trivia)
+    }
+}
+
+/// A possibly dotted key, of either a table header or a `key = value` pair
+#[derive(Clone, Debug)]
+pub struct Key<'de> {
+    /// The segments of the key, along with the dots and whitespace between them
+    pub parts: Vec<KeyPart<'de>>,
+}
+
+impl<'de> Key<'de> {
+    /// The segments of the key, in order
+    pub fn segments(&self) -> impl Iterator<Item = &KeySegment<'de>> {
+        self.parts.iter().filter_map(|part| match part {
+            KeyPart::Segment(segment) => Some(segment),
+            _ => None,
+        })
+    }
+
+    /// The span from the start of the first segment to the end of the last
+    /// one, including any quotes
+    pub fn span(&self) -> Span {
+        let mut segments = self.segments();
+        let first = segments.next().map_or(Span::default(), |s| s.raw.span);
+        let last = segments.last().map_or(first, |s| s.raw.span);
+        Span::new(first.start, last.end)
+    }
+
+    fn into_keys(self) -> impl Iterator<Item = value::Key<'de>> {
+        self.parts.into_iter().filter_map(|part| match part {
+            KeyPart::Segment(segment) => Some(segment.key),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for Key<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                KeyPart::Trivia(trivia) => trivia.fmt(f)?,
+                KeyPart::Segment(segment) => segment.raw.fmt(f)?,
+                KeyPart::Dot(dot) => dot.fmt(f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A `key = value` pair, either in a table or an inline table
+///
+/// The trivia following the value are part of the enclosing [`Document`] or
+/// [`InlineTable`].
+#[derive(Debug)]
+pub struct KeyValue<'de> {
+    /// The key, along with the trivia between it and the `=`
+    pub key: Key<'de>,
+    /// The `=`
+    pub eq: Leaf<'de>,
+    /// The trivia between the `=` and the value
+    pub trivia: Vec<Trivia<'de>>,
+    /// The value
+    pub value: ValueNode<'de>,
+}
+
+impl<'de> KeyValue<'de> {
+    /// The span from the start of the key to the end of the value
+    #[inline]
+    pub fn span(&self) -> Span {
+        Span::new(self.key.span().start, self.value.span().end)
+    }
+
+    fn into_parts(self, de: &Deserializer<'de>) -> Result<(Vec<value::Key<'de>>, Val<'de>), Error> {
+        Ok((self.key.into_keys().collect(), self.value.into_val(de)?))
+    }
+}
+
+impl fmt::Display for KeyValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.key.fmt(f)?;
+        self.eq.fmt(f)?;
+        self.trivia.iter().try_for_each(|trivia| trivia.fmt(f))?;
+        self.value.fmt(f)
+    }
+}
+
+/// A string, integer, float, boolean, or datetime
+#[derive(Debug)]
+pub struct Scalar<'de> {
+    /// The value as it appears in the document, including any quotes
+    pub raw: Leaf<'de>,
+    /// The parsed value, with its span excluding any quotes, as in a parsed
+    /// [`Value`]
+    pub value: Value<'de>,
+}
+
+/// An element of an [`Array`]
+#[derive(Debug)]
+pub enum ArrayPart<'de> {
+    /// Whitespace, a comment, or a newline
+    Trivia(Trivia<'de>),
+    /// An element of the array
+    Value(ValueNode<'de>),
+    /// The `,` after an element
+    Comma(Leaf<'de>),
+}
+
+impl<'de> From<Trivia<'de>> for ArrayPart<'de> {
+    fn from(trivia: Trivia<'de>) -> Self {
+        Self::Trivia(trivia)
+    }
+}
+
+/// An inline `[array]`
+#[derive(Debug)]
+pub struct Array<'de> {
+    /// The opening `[`
+    pub open: Leaf<'de>,
+    /// The elements of the array, along with the commas and trivia between them
+    pub parts: Vec<ArrayPart<'de>>,
+    /// The closing `]`
+    pub close: Leaf<'de>,
+}
+
+impl<'de> Array<'de> {
+    /// The elements of the array, in order
+    pub fn values(&self) -> impl Iterator<Item = &ValueNode<'de>> {
+        self.parts.iter().filter_map(|part| match part {
+            ArrayPart::Value(value) => Some(value),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for Array<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.open.fmt(f)?;
+        for part in &self.parts {
+            match part {
+                ArrayPart::Trivia(trivia) => trivia.fmt(f)?,
+                ArrayPart::Value(value) => value.fmt(f)?,
+                ArrayPart::Comma(comma) => comma.fmt(f)?,
+            }
+        }
+        self.close.fmt(f)
+    }
+}
+
+/// An element of an [`InlineTable`]
+#[derive(Debug)]
+pub enum InlineTablePart<'de> {
+    /// Whitespace, or, in TOML 1.1, a comment or a newline
+    Trivia(Trivia<'de>),
+    /// A `key = value` pair
+    KeyValue(KeyValue<'de>),
+    /// The `,` after a pair
+    Comma(Leaf<'de>),
+}
+
+impl<'de> From<Trivia<'de>> for InlineTablePart<'de> {
+    fn from(trivia: Trivia<'de>) -> Self {
+        Self::Trivia(trivia)
+    }
+}
+
+/// An `{ inline = "table" }`
+#[derive(Debug)]
+pub struct InlineTable<'de> {
+    /// The opening `{`
+    pub open: Leaf<'de>,
+    /// The pairs of the table, along with the commas and trivia between them
+    pub parts: Vec<InlineTablePart<'de>>,
+    /// The closing `}`
+    pub close: Leaf<'de>,
+}
+
+impl<'de> InlineTable<'de> {
+    /// The `key = value` pairs of the table, in order
+    pub fn entries(&self) -> impl Iterator<Item = &KeyValue<'de>> {
+        self.parts.iter().filter_map(|part| match part {
+            InlineTablePart::KeyValue(kv) => Some(kv),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for InlineTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.open.fmt(f)?;
+        for part in &self.parts {
+            match part {
+                InlineTablePart::Trivia(trivia) => trivia.fmt(f)?,
+                InlineTablePart::KeyValue(kv) => kv.fmt(f)?,
+                InlineTablePart::Comma(comma) => comma.fmt(f)?,
+            }
+        }
+        self.close.fmt(f)
+    }
+}
+
+/// The value of a `key = value` pair or an element of an array
+#[derive(Debug)]
+pub enum ValueNode<'de> {
+    /// A string, integer, float, boolean, or datetime
+    Scalar(Scalar<'de>),
+    /// An inline array
+    Array(Array<'de>),
+    /// An inline table
+    InlineTable(InlineTable<'de>),
+}
+
+impl<'de> ValueNode<'de> {
+    /// The span of the value, including any quotes, brackets, or braces
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Scalar(scalar) => scalar.raw.span,
+            Self::Array(array) => Span::new(array.open.span.start, array.close.span.end),
+            Self::InlineTable(table) => Span::new(table.open.span.start, table.close.span.end),
+        }
+    }
+
+    /// The span of the value as in a parsed [`Value`], which excludes the
+    /// quotes of strings
+    fn value_span(&self) -> Span {
+        match self {
+            Self::Scalar(scalar) => scalar.value.span,
+            _ => self.span(),
+        }
+    }
+
+    fn into_val(self, de: &Deserializer<'de>) -> Result<Val<'de>, Error> {
+        let Span { start, end } = self.value_span();
+        let e = match self {
+            Self::Scalar(mut scalar) => match scalar.value.take() {
+                value::ValueInner::String(s) => E::String(s),
+                value::ValueInner::Integer(i) => E::Integer(i),
+                value::ValueInner::Float(f) => E::Float(f),
+                value::ValueInner::Boolean(b) => E::Boolean(b),
+                value::ValueInner::Datetime(dt) => E::Datetime(dt),
+                value::ValueInner::Array(_) | value::ValueInner::Table(_) => {
+                    unreachable!("scalars are never arrays or tables")
+                }
+            },
+            Self::Array(array) => E::Array(
+                array
+                    .parts
+                    .into_iter()
+                    .filter_map(|part| match part {
+                        ArrayPart::Value(value) => Some(value.into_val(de)),
+                        _ => None,
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            Self::InlineTable(table) => {
+                let mut values = TableValues::default();
+                for part in table.parts {
+                    if let InlineTablePart::KeyValue(kv) = part {
+                        let (key, value) = kv.into_parts(de)?;
+                        de.add_dotted_key(key, value, &mut values)?;
+                    }
+                }
+                E::InlineTable(values)
+            }
+        };
+        Ok(Val { e, start, end })
+    }
+}
+
+impl fmt::Display for ValueNode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Scalar(scalar) => scalar.raw.fmt(f),
+            Self::Array(array) => array.fmt(f),
+            Self::InlineTable(table) => table.fmt(f),
+        }
+    }
+}
+
+/// Parses a toml string into a [`Document`]
+///
+/// This only checks that the document is syntactically valid, semantic
+/// errors such as duplicate keys or tables are detected by
+/// [`Document::into_value`].
+///
+/// ```
+/// let toml = "# The package\n[package]\nname = 'toml-span' # the name\n";
+///
+/// let doc = toml_span::cst::parse(toml).unwrap();
+/// assert_eq!(doc.to_string(), toml);
+///
+/// let value = doc.into_value().unwrap();
+/// assert_eq!(value.pointer("/package/name").unwrap().as_str(), Some("toml-span"));
+/// ```
+pub fn parse(input: &str) -> Result<Document<'_>, Error> {
+    Parser::new(Deserializer::new(input), input).document()
+}
+
+/// Parses a toml string into a [`Document`] with the specified options
+///
+/// The [`version`](ParseOptions::version) and
+/// [`limits`](ParseOptions::limits) apply as they do when parsing, while
+/// [`comments`](ParseOptions::comments) are always kept in the tree.
+pub fn parse_with<'de>(input: &'de str, options: &ParseOptions) -> Result<Document<'de>, Error> {
+    let de = Deserializer::with_options(input, options)?;
+    Parser::new(de, input).document()
+}
+
+/// Where trivia is being read, which determines what it can contain
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Context {
+    /// Within a single line, only whitespace
+    Line,
+    /// Between lines or array elements, whitespace, comments, and newlines
+    Lines,
+    /// Within an inline table, where comments and newlines require TOML 1.1
+    InlineTable,
+}
+
+struct Parser<'de> {
+    de: Deserializer<'de>,
+    input: &'de str,
+    num_tables: usize,
+}
+
+impl<'de> Parser<'de> {
+    fn new(de: Deserializer<'de>, input: &'de str) -> Self {
+        Self {
+            de,
+            input,
+            num_tables: 0,
+        }
+    }
+
+    fn document(mut self) -> Result<Document<'de>, Error> {
+        let bom = self.input.starts_with('\u{feff}').then(|| Leaf {
+            raw: &self.input[..3],
+            span: Span::new(0, 3),
+        });
+
+        let mut items = Vec::new();
+        loop {
+            self.trivia(Context::Lines, &mut items)?;
+            match self.de.peek()? {
+                Some((_, Token::LeftBracket)) => {
+                    items.push(Item::TableHeader(self.table_header()?));
+                }
+                Some(_) => {
+                    self.de.depth = self.de.header_depth;
+                    items.push(Item::KeyValue(self.key_value(Context::Line)?));
+                }
+                None => break,
+            }
+
+            // Only whitespace and a comment can follow on the same line, which
+            // are read along with the newline by the next iteration
+            self.trivia(Context::Line, &mut items)?;
+            if !matches!(
+                self.de.peek()?,
+                None | Some((_, Token::Comment(_) | Token::Newline))
+            ) {
+                self.de.eat_newline_or_eof()?;
+            }
+        }
+
+        Ok(Document {
+            bom,
+            items,
+            input: self.input,
+        })
+    }
+
+    fn table_header(&mut self) -> Result<TableHeader<'de>, Error> {
+        let start = self.de.tokens.current();
+        self.de.expect_spanned(Token::LeftBracket)?;
+        let array = self.de.eat(Token::LeftBracket)?;
+        let open = self.leaf(start);
+
+        let key = self.key()?;
+
+        let start = self.de.tokens.current();
+        self.de.expect_spanned(Token::RightBracket)?;
+        if array {
+            self.de.expect_spanned(Token::RightBracket)?;
+        }
+        let close = self.leaf(start);
+
+        self.num_tables += 1;
+        let span = Span::new(open.span.start, close.span.end);
+        let max = self.de.limits.max_tables;
+        self.de.limit("table count", self.num_tables, max, span)?;
+
+        let keys: Vec<_> = key.segments().map(|s| s.key.clone()).collect();
+        self.de.depth = 0;
+        self.de.descend_keys(&keys)?;
+        self.de.header_depth = self.de.depth;
+
+        Ok(TableHeader {
+            open,
+            key,
+            close,
+            array,
+        })
+    }
+
+    /// Reads a possibly dotted key, along with the whitespace around it
+    fn key(&mut self) -> Result<Key<'de>, Error> {
+        let mut parts = Vec::new();
+        loop {
+            self.trivia(Context::Line, &mut parts)?;
+            let start = self.de.tokens.current();
+            let key = self.de.table_key()?;
+            parts.push(KeyPart::Segment(KeySegment {
+                raw: self.leaf(start),
+                key,
+            }));
+            self.trivia(Context::Line, &mut parts)?;
+
+            match self.eat(Token::Period)? {
+                Some(dot) => parts.push(KeyPart::Dot(dot)),
+                None => return Ok(Key { parts }),
+            }
+        }
+    }
+
+    fn key_value(&mut self, context: Context) -> Result<KeyValue<'de>, Error> {
+        let mut key = self.key()?;
+        let keys: Vec<_> = key.segments().map(|s| s.key.clone()).collect();
+        self.de.descend_keys(&keys)?;
+        self.trivia(context, &mut key.parts)?;
+
+        let start = self.de.tokens.current();
+        self.de.expect_spanned(Token::Equals)?;
+        let eq = self.leaf(start);
+
+        let mut trivia = Vec::new();
+        self.trivia(context, &mut trivia)?;
+        let value = self.value()?;
+        self.de.depth -= keys.len();
+
+        Ok(KeyValue {
+            key,
+            eq,
+            trivia,
+            value,
+        })
+    }
+
+    fn value(&mut self) -> Result<ValueNode<'de>, Error> {
+        let start = self.de.tokens.current();
+        match self.de.value_start()? {
+            ValueStart::Scalar(val) => {
+                let value = de::to_value(val, &mut self.de)?;
+                Ok(ValueNode::Scalar(Scalar {
+                    raw: self.leaf(start),
+                    value,
+                }))
+            }
+            ValueStart::Array(span) => {
+                self.de.descend(span)?;
+                let array = self.array(start)?;
+                self.de.depth -= 1;
+                Ok(ValueNode::Array(array))
+            }
+            ValueStart::InlineTable(span) => {
+                self.de.descend(span)?;
+                let table = self.inline_table(start)?;
+                self.de.depth -= 1;
+                Ok(ValueNode::InlineTable(table))
+            }
+        }
+    }
+
+    /// Reads the rest of an array after its opening bracket
+    fn array(&mut self, start: usize) -> Result<Array<'de>, Error> {
+        let open = self.leaf_to(start, start + 1);
+        let mut parts = Vec::new();
+        let mut len = 0;
+
+        let close = loop {
+            self.trivia(Context::Lines, &mut parts)?;
+            if let Some(close) = self.eat(Token::RightBracket)? {
+                break close;
+            }
+
+            let value = self.value()?;
+            len += 1;
+            let max = self.de.limits.max_array_len;
+            self.de
+                .limit("array length", len, max, value.value_span())?;
+            parts.push(ArrayPart::Value(value));
+
+            self.trivia(Context::Lines, &mut parts)?;
+            match self.eat(Token::Comma)? {
+                Some(comma) => parts.push(ArrayPart::Comma(comma)),
+                None => break self.expect(Token::RightBracket)?,
+            }
+        };
+
+        Ok(Array { open, parts, close })
+    }
+
+    /// Reads the rest of an inline table after its opening brace
+    fn inline_table(&mut self, start: usize) -> Result<InlineTable<'de>, Error> {
+        let open = self.leaf_to(start, start + 1);
+        let mut parts = Vec::new();
+
+        self.trivia(Context::InlineTable, &mut parts)?;
+        let close = match self.eat(Token::RightBrace)? {
+            Some(close) => close,
+            None => loop {
+                let kv = self.key_value(Context::InlineTable)?;
+                parts.push(InlineTablePart::KeyValue(kv));
+
+                self.trivia(Context::InlineTable, &mut parts)?;
+                if let Some(close) = self.eat(Token::RightBrace)? {
+                    break close;
+                }
+
+                let comma = self.expect(Token::Comma)?;
+                parts.push(InlineTablePart::Comma(comma));
+                self.trivia(Context::InlineTable, &mut parts)?;
+                // TOML 1.1: trailing comma is allowed
+                if let Some(close) = self.eat(Token::RightBrace)? {
+                    self.de
+                        .unsupported(comma.span, "trailing commas in inline tables")?;
+                    break close;
+                }
+            },
+        };
+
+        Ok(InlineTable { open, parts, close })
+    }
+
+    /// Reads the trivia allowed in the context
+    fn trivia<T: From<Trivia<'de>>>(
+        &mut self,
+        context: Context,
+        out: &mut Vec<T>,
+    ) -> Result<(), Error> {
+        loop {
+            let start = self.de.tokens.current();
+            let trivia: fn(Leaf<'de>) -> Trivia<'de> = match self.de.peek()? {
+                Some((_, Token::Whitespace(_))) => Trivia::Whitespace,
+                Some((span, Token::Comment(_))) if context != Context::Line => {
+                    if context == Context::InlineTable {
+                        self.de.unsupported(span, "comments in inline tables")?;
+                    }
+                    Trivia::Comment
+                }
+                Some((span, Token::Newline)) if context != Context::Line => {
+                    if context == Context::InlineTable {
+                        self.de.unsupported(span, "newlines in inline tables")?;
+                    }
+                    Trivia::Newline
+                }
+                _ => return Ok(()),
+            };
+            self.de.next()?;
+            let trivia = trivia(self.leaf(start));
+            out.push(trivia.into());
+
+            // A comment always extends to the end of the line
+            if matches!(trivia, Trivia::Comment(_))
+                && !matches!(self.de.peek()?, None | Some((_, Token::Newline)))
+            {
+                self.de.eat_newline_or_eof()?;
+            }
+        }
+    }
+
+    fn eat(&mut self, token: Token<'de>) -> Result<Option<Leaf<'de>>, Error> {
+        let start = self.de.tokens.current();
+        Ok(self.de.eat(token)?.then(|| self.leaf(start)))
+    }
+
+    fn expect(&mut self, token: Token<'de>) -> Result<Leaf<'de>, Error> {
+        let start = self.de.tokens.current();
+        self.de.expect_spanned(token)?;
+        Ok(self.leaf(start))
+    }
+
+    /// The leaf from `start` to the current position
+    fn leaf(&mut self, start: usize) -> Leaf<'de> {
+        let end = self.de.tokens.current();
+        self.leaf_to(start, end)
+    }
+
+    fn leaf_to(&self, start: usize, end: usize) -> Leaf<'de> {
+        Leaf {
+            raw: &self.input[start..end],
+            span: Span::new(start, end),
+        }
+    }
+}
@@ -0,0 +1,616 @@
+//! Provides the [`Datetime`] type, which represents all of the datetime forms
+//! supported by toml
+
+use crate::{DeserError, Deserialize, value::Value};
+use std::fmt;
+
+/// A toml datetime
+///
+/// Toml supports 4 different forms of datetimes, which are represented by
+/// which parts are present
+///
+/// | Form             | Example                     | `date` | `time` | `offset` |
+/// |------------------|-----------------------------|--------|--------|----------|
+/// | Offset date-time | `1979-05-27T07:32:00-08:00` | ✅     | ✅     | ✅       |
+/// | Local date-time  | `1979-05-27T07:32:00`       | ✅     | ✅     | ❌       |
+/// | Local date       | `1979-05-27`                | ✅     | ❌     | ❌       |
+/// | Local time       | `07:32:00`                  | ❌     | ✅     | ❌       |
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Datetime {
+    /// The date component
+    pub date: Option<Date>,
+    /// The time component
+    pub time: Option<Time>,
+    /// The offset from UTC, only present if both a date and time are present
+    pub offset: Option<Offset>,
+}
+
+/// A calendar date
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Date {
+    /// The year, `0000` - `9999`
+    pub year: u16,
+    /// The month, `1` - `12`
+    pub month: u8,
+    /// The day of the month, `1` - `28/29/30/31` depending on the month and year
+    pub day: u8,
+}
+
+/// A time of day
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Time {
+    /// The hour, `0` - `23`
+    pub hour: u8,
+    /// The minute, `0` - `59`
+    pub minute: u8,
+    /// The second, `0` - `60`, `60` is only used for leap seconds
+    ///
+    /// Toml 1.1 allows seconds to be omitted, in which case this is `0`
+    pub second: u8,
+    /// The fractional seconds, in nanoseconds
+    ///
+    /// Any precision beyond nanoseconds is truncated
+    pub nanosecond: u32,
+}
+
+/// The offset from UTC of an offset date-time
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Offset {
+    /// UTC, specified with `Z` or `z`
+    Z,
+    /// A custom offset, eg. `-08:00`
+    Custom {
+        /// The offset from UTC in minutes, `-1439` - `1439`
+        minutes: i16,
+    },
+}
+
+impl Datetime {
+    /// Describes the form of this datetime, eg. "an offset date-time"
+    pub fn kind_str(&self) -> &'static str {
+        match (
+            self.date.is_some(),
+            self.time.is_some(),
+            self.offset.is_some(),
+        ) {
+            (true, true, true) => "an offset date-time",
+            (true, true, false) => "a local date-time",
+            (true, false, _) => "a local date",
+            (false, ..) => "a local time",
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)?;
+        if self.nanosecond != 0 {
+            let nanos = format!("{:09}", self.nanosecond);
+            write!(f, ".{}", nanos.trim_end_matches('0'))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Offset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Z => f.write_str("Z"),
+            Self::Custom { minutes } => {
+                let sign = if minutes < 0 { '-' } else { '+' };
+                let minutes = minutes.unsigned_abs();
+                write!(f, "{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Datetime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(date) = &self.date {
+            write!(f, "{date}")?;
+        }
+        if let Some(time) = &self.time {
+            if self.date.is_some() {
+                f.write_str("T")?;
+            }
+            write!(f, "{time}")?;
+        }
+        if let Some(offset) = &self.offset {
+            write!(f, "{offset}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when parsing a [`Datetime`] from a string fails
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatetimeParseError {
+    reason: &'static str,
+}
+
+impl fmt::Display for DatetimeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid datetime: {}", self.reason)
+    }
+}
+
+impl std::error::Error for DatetimeParseError {}
+
+impl std::str::FromStr for Datetime {
+    type Err = DatetimeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Parser { s: s.as_bytes() };
+
+        let date = if s.len() >= 5 && s.as_bytes()[4] == b'-' {
+            Some(p.date()?)
+        } else {
+            None
+        };
+
+        let time = match (date.is_some(), p.s.first()) {
+            (false, _) => Some(p.time()?),
+            (true, Some(b'T' | b't' | b' ')) => {
+                p.s = &p.s[1..];
+                Some(p.time()?)
+            }
+            (true, _) => None,
+        };
+
+        let offset = if date.is_some() && time.is_some() && !p.s.is_empty() {
+            Some(p.offset()?)
+        } else {
+            None
+        };
+
+        if !p.s.is_empty() {
+            return Err(Parser::err("unexpected trailing characters"));
+        }
+
+        Ok(Self { date, time, offset })
+    }
+}
+
+struct Parser<'s> {
+    s: &'s [u8],
+}
+
+impl Parser<'_> {
+    #[inline]
+    fn err(reason: &'static str) -> DatetimeParseError {
+        DatetimeParseError { reason }
+    }
+
+    /// Parses exactly `N` ascii digits
+    fn digits<const N: usize>(&mut self, what: &'static str) -> Result<u32, DatetimeParseError> {
+        if self.s.len() < N || !self.s[..N].iter().all(u8::is_ascii_digit) {
+            return Err(Self::err(what));
+        }
+
+        let n = self.s[..N]
+            .iter()
+            .fold(0, |acc, d| acc * 10 + u32::from(d - b'0'));
+        self.s = &self.s[N..];
+        Ok(n)
+    }
+
+    fn expect(&mut self, c: u8, what: &'static str) -> Result<(), DatetimeParseError> {
+        if self.s.first() == Some(&c) {
+            self.s = &self.s[1..];
+            Ok(())
+        } else {
+            Err(Self::err(what))
+        }
+    }
+
+    fn date(&mut self) -> Result<Date, DatetimeParseError> {
+        let year = self.digits::<4>("year must be 4 digits")?;
+        self.expect(b'-', "expected '-' after year")?;
+        let month = self.digits::<2>("month must be 2 digits")?;
+        self.expect(b'-', "expected '-' after month")?;
+        let day = self.digits::<2>("day must be 2 digits")?;
+
+        if !(1..=12).contains(&month) {
+            return Err(Self::err("month must be between 01 and 12"));
+        }
+
+        let is_leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let max_day = match month {
+            2 if is_leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        };
+
+        if day == 0 || day > max_day {
+            return Err(Self::err("day is out of range for the month"));
+        }
+
+        Ok(Date {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+        })
+    }
+
+    fn time(&mut self) -> Result<Time, DatetimeParseError> {
+        let hour = self.digits::<2>("hour must be 2 digits")?;
+        self.expect(b':', "expected ':' after hour")?;
+        let minute = self.digits::<2>("minute must be 2 digits")?;
+
+        let mut second = 0;
+        let mut nanosecond = 0;
+
+        // TOML 1.1: seconds are optional
+        if self.s.first() == Some(&b':') {
+            self.s = &self.s[1..];
+            second = self.digits::<2>("second must be 2 digits")?;
+
+            if self.s.first() == Some(&b'.') {
+                self.s = &self.s[1..];
+
+                let len = self.s.iter().take_while(|b| b.is_ascii_digit()).count();
+                if len == 0 {
+                    return Err(Self::err("expected fractional seconds after '.'"));
+                }
+
+                // Truncate any precision beyond nanoseconds
+                for (i, d) in self.s[..len.min(9)].iter().enumerate() {
+                    nanosecond += u32::from(d - b'0') * 10u32.pow(8 - i as u32);
+                }
+
+                self.s = &self.s[len..];
+            }
+        }
+
+        if hour > 23 {
+            return Err(Self::err("hour must be between 00 and 23"));
+        }
+        if minute > 59 {
+            return Err(Self::err("minute must be between 00 and 59"));
+        }
+        if second > 60 {
+            return Err(Self::err("second must be between 00 and 60"));
+        }
+
+        Ok(Time {
+            hour: hour as u8,
+            minute: minute as u8,
+            second: second as u8,
+            nanosecond,
+        })
+    }
+
+    fn offset(&mut self) -> Result<Offset, DatetimeParseError> {
+        let sign = match self.s.first() {
+            Some(b'Z' | b'z') => {
+                self.s = &self.s[1..];
+                return Ok(Offset::Z);
+            }
+            Some(b'+') => 1,
+            Some(b'-') => -1,
+            _ => return Err(Self::err("expected 'Z' or a '+'/'-' offset")),
+        };
+        self.s = &self.s[1..];
+
+        let hours = self.digits::<2>("offset hours must be 2 digits")?;
+        self.expect(b':', "expected ':' in offset")?;
+        let minutes = self.digits::<2>("offset minutes must be 2 digits")?;
+
+        if hours > 23 {
+            return Err(Self::err("offset hours must be between 00 and 23"));
+        }
+        if minutes > 59 {
+            return Err(Self::err("offset minutes must be between 00 and 59"));
+        }
+
+        Ok(Offset::Custom {
+            minutes: sign * (hours * 60 + minutes) as i16,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Datetime {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        match value.take() {
+            crate::value::ValueInner::Datetime(dt) => Ok(dt),
+            other => Err(crate::de_helpers::expected("a datetime", other, value.span).into()),
+        }
+    }
+}
+
+/// Creates an error for a datetime that doesn't have the form required by the
+/// type being deserialized
+#[cfg(any(feature = "chrono", feature = "time", feature = "jiff"))]
+fn wrong_form(expected: &'static str, dt: &Datetime, span: crate::Span) -> DeserError {
+    crate::Error::from((
+        crate::ErrorKind::Wanted {
+            expected,
+            found: dt.kind_str(),
+        },
+        span,
+    ))
+    .into()
+}
+
+#[cfg(any(feature = "chrono", feature = "time", feature = "jiff"))]
+fn out_of_range(ty: &'static str, span: crate::Span) -> DeserError {
+    crate::Error::from((crate::ErrorKind::OutOfRange(ty), span)).into()
+}
+
+/// Conversions to [`chrono`](https://docs.rs/chrono) types
+#[cfg(feature = "chrono")]
+mod impl_chrono {
+    use super::*;
+    use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+
+    fn date(d: Date) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(d.year.into(), d.month.into(), d.day.into())
+    }
+
+    fn time(t: Time) -> Option<NaiveTime> {
+        NaiveTime::from_hms_nano_opt(
+            t.hour.into(),
+            t.minute.into(),
+            t.second.into(),
+            t.nanosecond,
+        )
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    impl<'de> Deserialize<'de> for NaiveDate {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            match Datetime::deserialize(value)? {
+                Datetime {
+                    date: Some(d),
+                    time: None,
+                    ..
+                } => date(d).ok_or_else(|| out_of_range("NaiveDate", value.span)),
+                other => Err(wrong_form("a local date", &other, value.span)),
+            }
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    impl<'de> Deserialize<'de> for NaiveTime {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            match Datetime::deserialize(value)? {
+                Datetime {
+                    date: None,
+                    time: Some(t),
+                    ..
+                } => time(t).ok_or_else(|| out_of_range("NaiveTime", value.span)),
+                other => Err(wrong_form("a local time", &other, value.span)),
+            }
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    impl<'de> Deserialize<'de> for NaiveDateTime {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            match Datetime::deserialize(value)? {
+                Datetime {
+                    date: Some(d),
+                    time: Some(t),
+                    offset: None,
+                } => date(d)
+                    .zip(time(t))
+                    .map(|(d, t)| NaiveDateTime::new(d, t))
+                    .ok_or_else(|| out_of_range("NaiveDateTime", value.span)),
+                other => Err(wrong_form("a local date-time", &other, value.span)),
+            }
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    impl<'de> Deserialize<'de> for DateTime<FixedOffset> {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            match Datetime::deserialize(value)? {
+                Datetime {
+                    date: Some(d),
+                    time: Some(t),
+                    offset: Some(o),
+                } => {
+                    let minutes = match o {
+                        Offset::Z => 0,
+                        Offset::Custom { minutes } => i32::from(minutes),
+                    };
+
+                    date(d)
+                        .zip(time(t))
+                        .zip(FixedOffset::east_opt(minutes * 60))
+                        .and_then(|((d, t), o)| {
+                            NaiveDateTime::new(d, t).and_local_timezone(o).single()
+                        })
+                        .ok_or_else(|| out_of_range("DateTime<FixedOffset>", value.span))
+                }
+                other => Err(wrong_form("an offset date-time", &other, value.span)),
+            }
+        }
+    }
+}
+
+/// Conversions to [`time`](https://docs.rs/time) types
+#[cfg(feature = "time")]
+mod impl_time {
+    use super::*;
+    use time::{Month, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+    fn date(d: Date) -> Option<time::Date> {
+        time::Date::from_calendar_date(d.year.into(), Month::try_from(d.month).ok()?, d.day).ok()
+    }
+
+    fn time(t: Time) -> Option<time::Time> {
+        time::Time::from_hms_nano(t.hour, t.minute, t.second, t.nanosecond).ok()
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    impl<'de> Deserialize<'de> for time::Date {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            match Datetime::deserialize(value)? {
+                Datetime {
+                    date: Some(d),
+                    time: None,
+                    ..
+                } => date(d).ok_or_else(|| out_of_range("Date", value.span)),
+                other => Err(wrong_form("a local date", &other, value.span)),
+            }
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    impl<'de> Deserialize<'de> for time::Time {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            match Datetime::deserialize(value)? {
+                Datetime {
+                    date: None,
+                    time: Some(t),
+                    ..
+                } => time(t).ok_or_else(|| out_of_range("Time", value.span)),
+                other => Err(wrong_form("a local time", &other, value.span)),
+            }
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    impl<'de> Deserialize<'de> for PrimitiveDateTime {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            match Datetime::deserialize(value)? {
+                Datetime {
+                    date: Some(d),
+                    time: Some(t),
+                    offset: None,
+                } => date(d)
+                    .zip(time(t))
+                    .map(|(d, t)| PrimitiveDateTime::new(d, t))
+                    .ok_or_else(|| out_of_range("PrimitiveDateTime", value.span)),
+                other => Err(wrong_form("a local date-time", &other, value.span)),
+            }
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    impl<'de> Deserialize<'de> for OffsetDateTime {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            match Datetime::deserialize(value)? {
+                Datetime {
+                    date: Some(d),
+                    time: Some(t),
+                    offset: Some(o),
+                } => {
+                    let minutes = match o {
+                        Offset::Z => 0,
+                        Offset::Custom { minutes } => i32::from(minutes),
+                    };
+
+                    date(d)
+                        .zip(time(t))
+                        .zip(UtcOffset::from_whole_seconds(minutes * 60).ok())
+                        .map(|((d, t), o)| PrimitiveDateTime::new(d, t).assume_offset(o))
+                        .ok_or_else(|| out_of_range("OffsetDateTime", value.span))
+                }
+                other => Err(wrong_form("an offset date-time", &other, value.span)),
+            }
+        }
+    }
+}
+
+/// Conversions to [`jiff`](https://docs.rs/jiff) types
+#[cfg(feature = "jiff")]
+mod impl_jiff {
+    use super::*;
+    use jiff::{Timestamp, civil, tz};
+
+    fn date(d: Date) -> Option<civil::Date> {
+        civil::Date::new(d.year as i16, d.month as i8, d.day as i8).ok()
+    }
+
+    fn time(t: Time) -> Option<civil::Time> {
+        civil::Time::new(
+            t.hour as i8,
+            t.minute as i8,
+            t.second as i8,
+            t.nanosecond as i32,
+        )
+        .ok()
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "jiff")))]
+    impl<'de> Deserialize<'de> for civil::Date {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            match Datetime::deserialize(value)? {
+                Datetime {
+                    date: Some(d),
+                    time: None,
+                    ..
+                } => date(d).ok_or_else(|| out_of_range("civil::Date", value.span)),
+                other => Err(wrong_form("a local date", &other, value.span)),
+            }
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "jiff")))]
+    impl<'de> Deserialize<'de> for civil::Time {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            match Datetime::deserialize(value)? {
+                Datetime {
+                    date: None,
+                    time: Some(t),
+                    ..
+                } => time(t).ok_or_else(|| out_of_range("civil::Time", value.span)),
+                other => Err(wrong_form("a local time", &other, value.span)),
+            }
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "jiff")))]
+    impl<'de> Deserialize<'de> for civil::DateTime {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            match Datetime::deserialize(value)? {
+                Datetime {
+                    date: Some(d),
+                    time: Some(t),
+                    offset: None,
+                } => date(d)
+                    .zip(time(t))
+                    .map(|(d, t)| civil::DateTime::from_parts(d, t))
+                    .ok_or_else(|| out_of_range("civil::DateTime", value.span)),
+                other => Err(wrong_form("a local date-time", &other, value.span)),
+            }
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "jiff")))]
+    impl<'de> Deserialize<'de> for Timestamp {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            match Datetime::deserialize(value)? {
+                Datetime {
+                    date: Some(d),
+                    time: Some(t),
+                    offset: Some(o),
+                } => {
+                    let minutes = match o {
+                        Offset::Z => 0,
+                        Offset::Custom { minutes } => i32::from(minutes),
+                    };
+
+                    date(d)
+                        .zip(time(t))
+                        .zip(tz::Offset::from_seconds(minutes * 60).ok())
+                        .and_then(|((d, t), o)| {
+                            o.to_timestamp(civil::DateTime::from_parts(d, t)).ok()
+                        })
+                        .ok_or_else(|| out_of_range("Timestamp", value.span))
+                }
+                other => Err(wrong_form("an offset date-time", &other, value.span)),
+            }
+        }
+    }
+}
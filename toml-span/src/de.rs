@@ -2,47 +2,176 @@
 
 use crate::{
     Span,
+    comments::{self, Comments},
+    datetime::Datetime,
     error::{Error, ErrorKind},
+    span::{ColumnUnit, LineIndex},
     tokens::{Error as TokenError, Token, Tokenizer},
     value::{self, Key, Value, ValueInner},
 };
 use smallvec::SmallVec;
-use std::{
-    borrow::Cow,
-    collections::{BTreeMap, btree_map::Entry},
-    ops::Range,
-};
+use std::{borrow::Cow, cell::OnceCell, collections::BTreeMap, fmt, ops::Range};
+
+#[cfg(feature = "preserve_order")]
+use indexmap::map::Entry;
+#[cfg(not(feature = "preserve_order"))]
+use std::collections::btree_map::Entry;
 
 type DeStr<'de> = Cow<'de, str>;
 type TablePair<'de> = (Key<'de>, Val<'de>);
 type InlineVec<T> = SmallVec<[T; 5]>;
 
 /// Parses a toml string into a [`ValueInner::Table`]
+///
+/// The document is parsed with the default [`Limits`]
 pub fn parse(s: &str) -> Result<Value<'_>, Error> {
     let mut de = Deserializer::new(s);
-
-    let raw_tables = de.tables()?;
-    let mut ctx = DeserializeCtx {
-        table_indices: &build_table_indices(&raw_tables),
-        table_pindices: &build_table_pindices(&raw_tables),
-        raw_tables,
-        de: &de,
-    };
-    let root = ctx.deserialize_entry(
-        DeserializeTableIdx {
-            table_idx: 0,
-            depth: 0,
-            idx_range: 0..ctx.raw_tables.len(),
-        },
-        Vec::new(),
-    )?;
+    let root = de.root()?;
 
     Ok(Value::with_span(root, Span::new(0, s.len())))
 }
 
-struct Deserializer<'a> {
+/// Options for [`parse_with`]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ParseOptions {
+    /// Collects the comments in the document and attaches them to the keys,
+    /// tables, and array elements they document, see [`Comments`]
+    pub comments: bool,
+    /// The limits the document must stay within
+    pub limits: Limits,
+    /// The version of the TOML spec to parse the document as
+    pub version: TomlVersion,
+}
+
+/// A version of the TOML spec
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TomlVersion {
+    /// [TOML 1.0](https://toml.io/en/v1.0.0), which rejects the syntax added
+    /// in TOML 1.1 with [`ErrorKind::UnsupportedFeature`]
+    V1_0,
+    /// [TOML 1.1](https://toml.io/en/v1.1.0)
+    #[default]
+    V1_1,
+}
+
+impl fmt::Display for TomlVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::V1_0 => "1.0",
+            Self::V1_1 => "1.1",
+        })
+    }
+}
+
+/// Limits on the size and complexity of a document, for parsing untrusted
+/// input
+///
+/// Exceeding a limit fails the parse with [`ErrorKind::LimitExceeded`], with
+/// the span of the offending construct.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum nesting depth of a value, where every key of a table header
+    /// or dotted key, and every array and inline table, is another level.
+    ///
+    /// Parsing recurses for every level, so this guards against stack
+    /// overflows. Defaults to 128.
+    pub max_depth: usize,
+    /// The maximum length of the document, in bytes. Unlimited by default.
+    pub max_input_len: usize,
+    /// The maximum number of elements in an array. Unlimited by default.
+    pub max_array_len: usize,
+    /// The maximum length of a string or key, in bytes. Unlimited by default.
+    pub max_string_len: usize,
+    /// The maximum number of table headers. Unlimited by default.
+    pub max_tables: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            max_input_len: usize::MAX,
+            max_array_len: usize::MAX,
+            max_string_len: usize::MAX,
+            max_tables: usize::MAX,
+        }
+    }
+}
+
+/// A toml document parsed with [`parse_with`]
+pub struct Document<'de> {
+    /// The root [`ValueInner::Table`]
+    pub root: Value<'de>,
+    /// The comments in the document, empty unless [`ParseOptions::comments`]
+    /// is set
+    pub comments: Comments<'de>,
+}
+
+/// Parses a toml string into a [`Document`] with the specified options
+pub fn parse_with<'de>(s: &'de str, options: &ParseOptions) -> Result<Document<'de>, Error> {
+    let mut de = Deserializer::with_options(s, options)?;
+    if options.comments {
+        de.items = Some(Vec::new());
+    }
+
+    let root = de.root()?;
+    let comments = de
+        .items
+        .map(|items| comments::attach(s, &items))
+        .unwrap_or_default();
+
+    Ok(Document {
+        root: Value::with_span(root, Span::new(0, s.len())),
+        comments,
+    })
+}
+
+/// Parses a toml string into a [`ValueInner::Table`], continuing past errors
+/// so that all of them can be reported at once
+///
+/// After a syntax error the parser skips ahead to the next line, or, if the
+/// error occurred inside an array or inline table, the line after the one
+/// where it is closed, or the next table header, whichever comes first. The
+/// returned [`Value`] contains everything that could be parsed, while keys
+/// and tables that were invalid, or that were defined on a line that could
+/// not be parsed, are left out. The errors are the same as would be returned
+/// by [`parse`], in the order they were encountered, and the document is
+/// valid if there are none.
+pub fn parse_recovering(s: &str) -> (Value<'_>, Vec<Error>) {
+    let mut de = Deserializer::new(s);
+    de.errors = Some(Vec::new());
+
+    // All errors are recorded rather than returned when recovering
+    let root = de.root().unwrap_or_else(|err| {
+        de.errors.get_or_insert_with(Vec::new).push(err);
+        ValueInner::Table(value::Table::new())
+    });
+
+    (
+        Value::with_span(root, Span::new(0, s.len())),
+        de.errors.unwrap_or_default(),
+    )
+}
+
+pub(crate) struct Deserializer<'a> {
     input: &'a str,
-    tokens: Tokenizer<'a>,
+    pub(crate) tokens: Tokenizer<'a>,
+    /// The errors that have been recovered from, only `Some` when recovering
+    errors: Option<Vec<Error>>,
+    /// The number of arrays and inline tables the tokenizer is currently in,
+    /// used to skip the rest of a value that failed to parse
+    nesting: usize,
+    /// Used to get the line information for errors, only built if one occurs
+    line_index: OnceCell<LineIndex>,
+    /// The items that comments can be attached to, only `Some` when
+    /// collecting comments
+    items: Option<Vec<comments::Item>>,
+    pub(crate) limits: Limits,
+    /// The depth of the value currently being parsed, see [`Limits::max_depth`]
+    pub(crate) depth: usize,
+    /// The depth of the keys in the current table
+    pub(crate) header_depth: usize,
+    version: TomlVersion,
 }
 
 struct DeserializeCtx<'de, 'b> {
@@ -53,7 +182,7 @@ struct DeserializeCtx<'de, 'b> {
     // maps table headers to a list of all subtables
     // (the list contains indices into `raw_tables` and is ordered)
     table_pindices: &'b BTreeMap<InlineVec<DeStr<'de>>, Vec<usize>>,
-    de: &'b Deserializer<'de>,
+    de: &'b mut Deserializer<'de>,
 }
 // specifies the table/array that is currently being deserialized, namely the
 // table/array with the header `raw_tables[table_idx].header[0..depth]`
@@ -99,13 +228,15 @@ impl<'de, 'b> DeserializeCtx<'de, 'b> {
             //   [[a]]
             if table_idx.table_idx < matching_tables[0] {
                 let array_tbl = &self.raw_tables[matching_tables[0]];
-                return Err(self.de.error(
+                let err = self.de.error(
                     array_tbl.at,
                     Some(array_tbl.end),
                     ErrorKind::RedefineAsArray,
-                ));
+                );
+                self.de.recover(err)?;
+            } else {
+                assert!(additional_values.is_empty());
             }
-            assert!(additional_values.is_empty());
 
             let mut array = value::Array::new();
             for (i, array_entry_idx) in matching_tables.iter().copied().enumerate() {
@@ -127,15 +258,23 @@ impl<'de, 'b> DeserializeCtx<'de, 'b> {
         } else {
             if matching_tables.len() > 1 {
                 let first_tbl = &self.raw_tables[matching_tables[0]];
-                let second_tbl = &self.raw_tables[matching_tables[1]];
-                return Err(self.de.error(
-                    second_tbl.at,
-                    Some(second_tbl.end),
-                    ErrorKind::DuplicateTable {
-                        name: current_header.last().unwrap().to_string(),
-                        first: Span::new(first_tbl.at, first_tbl.end),
-                    },
-                ));
+                for &dupe_idx in &matching_tables[1..] {
+                    let dupe_tbl = &self.raw_tables[dupe_idx];
+                    let err = self.de.error(
+                        dupe_tbl.at,
+                        Some(dupe_tbl.end),
+                        ErrorKind::DuplicateTable {
+                            name: current_header.last().unwrap().to_string(),
+                            first: Span::new(first_tbl.at, first_tbl.end),
+                        },
+                    );
+                    self.de.recover(err)?;
+                }
+
+                // Only the first definition is kept
+                for &dupe_idx in &matching_tables[1..] {
+                    self.raw_tables[dupe_idx].values = None;
+                }
             }
 
             let values = matching_tables
@@ -183,31 +322,34 @@ impl<'de, 'b> DeserializeCtx<'de, 'b> {
 
             let subtable_name = &self.raw_tables[subtable_idx].header[header.len()];
 
-            let dotted_entries = match dotted_keys_map.remove_entry(subtable_name) {
-                // Detect redefinitions of tables created via dotted keys, as
-                // these are considered errors, e.g:
-                //   apple.color = "red"
-                //   [apple]  # INVALID
-                // However adding subtables is allowed:
-                //   apple.color = "red"
-                //   [apple.texture]  # VALID
-                Some((previous_key, _))
-                    if self.raw_tables[subtable_idx].header.len() == header.len() + 1 =>
-                {
-                    return Err(self.de.error(
+            // Detect redefinitions of tables created via dotted keys, as
+            // these are considered errors, e.g:
+            //   apple.color = "red"
+            //   [apple]  # INVALID
+            // However adding subtables is allowed:
+            //   apple.color = "red"
+            //   [apple.texture]  # VALID
+            if let Some((previous_key, _)) = dotted_keys_map.get_key_value(subtable_name) {
+                if self.raw_tables[subtable_idx].header.len() == header.len() + 1 {
+                    let err = self.de.error(
                         subtable_name.span.start,
                         Some(subtable_name.span.end),
                         ErrorKind::DuplicateKey {
                             key: subtable_name.to_string(),
                             first: previous_key.span,
                         },
-                    ));
+                    );
+                    self.de.recover(err)?;
+                    continue;
                 }
-                Some((_, dotted_entries)) => dotted_entries.values,
-                None => Vec::new(),
-            };
+            }
+
+            let dotted_entries = dotted_keys_map
+                .remove(subtable_name)
+                .map(|dotted_entries| dotted_entries.values)
+                .unwrap_or_default();
 
-            match table.entry(subtable_name.clone()) {
+            match table.map.entry(subtable_name.clone()) {
                 Entry::Vacant(vac) => {
                     let subtable_span = Self::get_table_span(&self.raw_tables[subtable_idx]);
                     let subtable_idx = DeserializeTableIdx {
@@ -219,14 +361,15 @@ impl<'de, 'b> DeserializeCtx<'de, 'b> {
                     vac.insert(Value::with_span(entry, subtable_span));
                 }
                 Entry::Occupied(occ) => {
-                    return Err(self.de.error(
+                    let err = self.de.error(
                         subtable_name.span.start,
                         Some(subtable_name.span.end),
                         ErrorKind::DuplicateKey {
                             key: subtable_name.to_string(),
                             first: occ.key().span,
                         },
-                    ));
+                    );
+                    self.de.recover(err)?;
                 }
             };
         }
@@ -241,6 +384,13 @@ impl<'de, 'b> DeserializeCtx<'de, 'b> {
             table_insert(&mut table, key, val, self.de)?;
         }
 
+        // Subtables and dotted keys are inserted after the plain keys, so
+        // restore the order in which the keys first appear in the document
+        #[cfg(feature = "preserve_order")]
+        table
+            .map
+            .sort_by(|a, _, b, _| a.span.start.cmp(&b.span.start));
+
         Ok(table)
     }
 
@@ -274,12 +424,16 @@ impl<'de, 'b> DeserializeCtx<'de, 'b> {
     }
 }
 
-fn to_value<'de>(val: Val<'de>, de: &Deserializer<'de>) -> Result<Value<'de>, Error> {
+pub(crate) fn to_value<'de>(
+    val: Val<'de>,
+    de: &mut Deserializer<'de>,
+) -> Result<Value<'de>, Error> {
     let value = match val.e {
         E::String(s) => ValueInner::String(s),
         E::Boolean(b) => ValueInner::Boolean(b),
         E::Integer(i) => ValueInner::Integer(i),
         E::Float(f) => ValueInner::Float(f),
+        E::Datetime(dt) => ValueInner::Datetime(dt),
         E::Array(arr) => {
             let mut varr = Vec::new();
             for val in arr {
@@ -305,17 +459,20 @@ fn table_insert<'de>(
     table: &mut value::Table<'de>,
     key: Key<'de>,
     val: Val<'de>,
-    de: &Deserializer<'de>,
+    de: &mut Deserializer<'de>,
 ) -> Result<(), Error> {
-    match table.entry(key.clone()) {
-        Entry::Occupied(occ) => Err(de.error(
-            key.span.start,
-            Some(key.span.end),
-            ErrorKind::DuplicateKey {
-                key: key.name.to_string(),
-                first: occ.key().span,
-            },
-        )),
+    match table.map.entry(key.clone()) {
+        Entry::Occupied(occ) => {
+            let err = de.error(
+                key.span.start,
+                Some(key.span.end),
+                ErrorKind::DuplicateKey {
+                    key: key.name.to_string(),
+                    first: occ.key().span,
+                },
+            );
+            de.recover(err)
+        }
         Entry::Vacant(vac) => {
             vac.insert(to_value(val, de)?);
             Ok(())
@@ -384,7 +541,7 @@ struct Table<'de> {
     array: bool,
 }
 
-struct TableValues<'de> {
+pub(crate) struct TableValues<'de> {
     values: Vec<TablePair<'de>>,
     span: Option<Span>,
 }
@@ -399,11 +556,191 @@ impl Default for TableValues<'_> {
     }
 }
 
+/// Builds a [`Value`] from the table headers and `key = value` pairs of a
+/// document, in the order they appear
+pub(crate) struct TreeBuilder<'de> {
+    pub(crate) de: Deserializer<'de>,
+    tables: Vec<Table<'de>>,
+    cur: Table<'de>,
+}
+
+impl<'de> TreeBuilder<'de> {
+    pub(crate) fn new(input: &'de str) -> Self {
+        Self {
+            de: Deserializer::new(input),
+            tables: Vec::new(),
+            cur: Table {
+                at: 0,
+                end: 0,
+                header: InlineVec::new(),
+                values: None,
+                array: false,
+            },
+        }
+    }
+
+    /// Starts a new table, `end` is the end of the line the header is on
+    pub(crate) fn table(
+        &mut self,
+        header: impl IntoIterator<Item = Key<'de>>,
+        array: bool,
+        at: usize,
+        end: usize,
+    ) {
+        let table = Table {
+            at,
+            end,
+            header: header.into_iter().collect(),
+            values: Some(TableValues::default()),
+            array,
+        };
+        let prev = std::mem::replace(&mut self.cur, table);
+        if !prev.header.is_empty() || prev.values.is_some() {
+            self.tables.push(prev);
+        }
+    }
+
+    /// Adds a `key = value` pair to the current table
+    pub(crate) fn key_value(
+        &mut self,
+        key: Vec<Key<'de>>,
+        value: Val<'de>,
+        at: usize,
+        end: usize,
+    ) -> Result<(), Error> {
+        self.de.add_key_value(&mut self.cur, key, value, at, end)
+    }
+
+    pub(crate) fn finish(mut self) -> Result<Value<'de>, Error> {
+        if !self.cur.header.is_empty() || self.cur.values.is_some() {
+            self.tables.push(self.cur);
+        }
+        let root = self.de.build(self.tables)?;
+        Ok(Value::with_span(root, Span::new(0, self.de.input.len())))
+    }
+}
+
 impl<'a> Deserializer<'a> {
-    fn new(input: &'a str) -> Deserializer<'a> {
+    pub(crate) fn new(input: &'a str) -> Deserializer<'a> {
         Deserializer {
             tokens: Tokenizer::new(input),
             input,
+            errors: None,
+            nesting: 0,
+            line_index: OnceCell::new(),
+            items: None,
+            limits: Limits::default(),
+            depth: 0,
+            header_depth: 0,
+            version: TomlVersion::default(),
+        }
+    }
+
+    /// Creates a deserializer for a document parsed with the specified options
+    pub(crate) fn with_options(input: &'a str, options: &ParseOptions) -> Result<Self, Error> {
+        let mut de = Self::new(input);
+        de.limits = options.limits;
+        de.version = options.version;
+        de.tokens = Tokenizer::with_version(input, options.version);
+
+        let max = options.limits.max_input_len;
+        de.limit(
+            "input length",
+            input.len(),
+            max,
+            Span::new(max.min(input.len()), input.len()),
+        )?;
+        Ok(de)
+    }
+
+    /// Parses the entire document into the root table
+    fn root(&mut self) -> Result<ValueInner<'a>, Error> {
+        let raw_tables = self.tables()?;
+        self.build(raw_tables)
+    }
+
+    /// Builds the root table from the tables of the document
+    fn build(&mut self, raw_tables: Vec<Table<'a>>) -> Result<ValueInner<'a>, Error> {
+        let mut ctx = DeserializeCtx {
+            table_indices: &build_table_indices(&raw_tables),
+            table_pindices: &build_table_pindices(&raw_tables),
+            raw_tables,
+            de: self,
+        };
+        ctx.deserialize_entry(
+            DeserializeTableIdx {
+                table_idx: 0,
+                depth: 0,
+                idx_range: 0..ctx.raw_tables.len(),
+            },
+            Vec::new(),
+        )
+    }
+
+    /// Records the error if recovering from errors, otherwise returns it
+    fn recover(&mut self, error: Error) -> Result<(), Error> {
+        match &mut self.errors {
+            Some(errors) => {
+                errors.push(error);
+                Ok(())
+            }
+            None => Err(error),
+        }
+    }
+
+    /// Skips the remainder of a line that failed to parse, starting at `start`,
+    /// along with any lines that are part of an array or inline table that
+    /// was opened on it
+    fn resync(&mut self, start: usize) {
+        let mut depth = std::mem::take(&mut self.nesting);
+        let at_line_start = |me: &mut Self| {
+            let current = me.tokens.current();
+            current > start && me.input[..current].ends_with('\n')
+        };
+
+        // The error may have been caused by the newline itself
+        if depth == 0 && at_line_start(self) {
+            return;
+        }
+
+        // If a value was never closed, a following table header will be
+        // parsed as part of it, so resume at the header instead of skipping it
+        if depth > 0 {
+            let current = self.tokens.current();
+            let line_start = self.input[..current].rfind('\n').map_or(0, |i| i + 1);
+            if line_start > start && self.input[line_start..].starts_with('[') {
+                self.tokens.reset_to(line_start);
+                return;
+            }
+        }
+
+        loop {
+            match self.tokens.step() {
+                Ok(Some((_, Token::LeftBracket | Token::LeftBrace))) => depth += 1,
+                Ok(Some((_, Token::RightBracket | Token::RightBrace))) => {
+                    depth = depth.saturating_sub(1);
+                }
+                Ok(Some((_, Token::Newline))) => {}
+                Ok(Some(_)) => continue,
+                Ok(None) => return,
+                Err(_) => {
+                    // Strings can fail after consuming the newline
+                    if !at_line_start(self) {
+                        self.tokens.skip_to_newline();
+                    }
+                }
+            }
+
+            if !at_line_start(self) {
+                continue;
+            }
+
+            // Stop at a table header even if a value was never closed, as
+            // it's far more likely the value is missing a bracket than that
+            // the header is part of the value
+            if depth == 0 || self.input[self.tokens.current()..].starts_with('[') {
+                return;
+            }
         }
     }
 
@@ -417,7 +754,23 @@ impl<'a> Deserializer<'a> {
             array: false,
         };
 
-        while let Some(line) = self.line()? {
+        // Set when a table header is invalid, in which case it, along with the
+        // keys that follow it, are discarded
+        let mut discard = false;
+        let mut num_tables = 0;
+
+        loop {
+            let start = self.tokens.current();
+            let line = match self.line() {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(err) => {
+                    self.recover(err)?;
+                    self.resync(start);
+                    continue;
+                }
+            };
+
             match line {
                 Line::Table {
                     at,
@@ -425,9 +778,10 @@ impl<'a> Deserializer<'a> {
                     mut header,
                     array,
                 } => {
-                    if !cur_table.header.is_empty() || cur_table.values.is_some() {
+                    if !discard && (!cur_table.header.is_empty() || cur_table.values.is_some()) {
                         tables.push(cur_table);
                     }
+                    discard = false;
                     cur_table = Table {
                         at,
                         end,
@@ -435,10 +789,51 @@ impl<'a> Deserializer<'a> {
                         values: Some(TableValues::default()),
                         array,
                     };
-                    while let Some(part) = header.next().map_err(|e| self.token_error(e))? {
-                        cur_table.header.push(part);
+                    loop {
+                        match header.next() {
+                            Ok(Some(part)) => {
+                                if let Err(err) = self.limit_string(&part) {
+                                    self.recover(err)?;
+                                    discard = true;
+                                }
+                                cur_table.header.push(part);
+                            }
+                            Ok(None) => break,
+                            Err(err) => {
+                                let err = self.token_error(err);
+                                self.recover(err)?;
+                                discard = true;
+                                break;
+                            }
+                        }
                     }
                     cur_table.end = header.tokens.current();
+
+                    num_tables += 1;
+                    self.limit(
+                        "table count",
+                        num_tables,
+                        self.limits.max_tables,
+                        Span::new(at, header.close),
+                    )?;
+
+                    self.depth = 0;
+                    if let Err(err) = self.descend_keys(&cur_table.header) {
+                        self.recover(err)?;
+                        discard = true;
+                    }
+                    self.header_depth = self.depth;
+
+                    if let (Some(items), Some(last)) = (&mut self.items, cur_table.header.last()) {
+                        // Comments can be looked up by either the key or the table
+                        for key in [at, last.span.start] {
+                            items.push(comments::Item {
+                                key,
+                                start: at,
+                                end: header.close,
+                            });
+                        }
+                    }
                 }
                 Line::KeyValue {
                     key,
@@ -446,30 +841,53 @@ impl<'a> Deserializer<'a> {
                     at,
                     end,
                 } => {
-                    let table_values = cur_table.values.get_or_insert_with(|| TableValues {
-                        values: Vec::new(),
-                        span: None,
-                    });
-                    self.add_dotted_key(key, value, table_values)?;
-                    match table_values.span {
-                        Some(ref mut span) => {
-                            span.start = span.start.min(at);
-                            span.end = span.end.max(end);
-                        }
-                        None => {
-                            table_values.span = Some(Span::new(at, end));
-                        }
+                    if let Err(err) = self.add_key_value(&mut cur_table, key, value, at, end) {
+                        self.recover(err)?;
                     }
                 }
             }
         }
-        if !cur_table.header.is_empty() || cur_table.values.is_some() {
+        if !discard && (!cur_table.header.is_empty() || cur_table.values.is_some()) {
             tables.push(cur_table);
         }
         Ok(tables)
     }
 
+    /// Adds a `key = value` pair to the table, extending the span of its values
+    fn add_key_value(
+        &self,
+        table: &mut Table<'a>,
+        key: Vec<Key<'a>>,
+        value: Val<'a>,
+        at: usize,
+        end: usize,
+    ) -> Result<(), Error> {
+        let table_values = table.values.get_or_insert_with(TableValues::default);
+        self.add_dotted_key(key, value, table_values)?;
+        match table_values.span {
+            Some(ref mut span) => {
+                span.start = span.start.min(at);
+                span.end = span.end.max(end);
+            }
+            None => {
+                table_values.span = Some(Span::new(at, end));
+            }
+        }
+        Ok(())
+    }
+
     fn line(&mut self) -> Result<Option<Line<'a>>, Error> {
+        self.skip_blank_lines()?;
+
+        match self.peek()? {
+            Some((_, Token::LeftBracket)) => self.table_header().map(Some),
+            Some(_) => self.key_value().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Skips lines that are empty or only contain a comment
+    pub(crate) fn skip_blank_lines(&mut self) -> Result<(), Error> {
         loop {
             self.eat_whitespace();
             if self.eat_comment()? {
@@ -478,14 +896,30 @@ impl<'a> Deserializer<'a> {
             if self.eat(Token::Newline)? {
                 continue;
             }
-            break;
+            return Ok(());
         }
+    }
 
-        match self.peek()? {
-            Some((_, Token::LeftBracket)) => self.table_header().map(Some),
-            Some(_) => self.key_value().map(Some),
-            None => Ok(None),
+    /// Parses a `[table]` or `[[array]]` header, returning its keys, whether
+    /// it is an array of tables, and the span from the opening to the closing
+    /// bracket(s)
+    pub(crate) fn header(&mut self) -> Result<(Vec<Key<'a>>, bool, Span), Error> {
+        let Line::Table {
+            at,
+            mut header,
+            array,
+            ..
+        } = self.table_header()?
+        else {
+            unreachable!("table_header always returns a table");
+        };
+
+        let mut keys = Vec::new();
+        while let Some(key) = header.next().map_err(|err| self.token_error(err))? {
+            self.limit_string(&key)?;
+            keys.push(key);
         }
+        Ok((keys, array, Span::new(at, header.close)))
     }
 
     fn table_header(&mut self) -> Result<Line<'a>, Error> {
@@ -506,12 +940,15 @@ impl<'a> Deserializer<'a> {
     fn key_value(&mut self) -> Result<Line<'a>, Error> {
         let start = self.tokens.current();
         let key = self.dotted_key()?;
+        self.depth = self.header_depth;
+        self.descend_keys(&key)?;
         self.eat_whitespace();
         self.expect(Token::Equals)?;
         self.eat_whitespace();
 
         let value = self.value()?;
         let end = self.tokens.current();
+        self.push_item(&key, start, end);
         self.eat_whitespace();
         if !self.eat_comment()? {
             self.eat_newline_or_eof()?;
@@ -526,13 +963,63 @@ impl<'a> Deserializer<'a> {
     }
 
     fn value(&mut self) -> Result<Val<'a>, Error> {
-        let at = self.tokens.current();
-        let value = match self.next()? {
-            Some((Span { start, end }, Token::String { val, .. })) => Val {
-                e: E::String(val),
+        let value = match self.value_start()? {
+            ValueStart::Scalar(value) => value,
+            ValueStart::InlineTable(span) => {
+                self.descend(span)?;
+                self.nesting += 1;
+                let value = self.inline_table().map(|(Span { end, .. }, table)| Val {
+                    e: E::InlineTable(table),
+                    start: span.start,
+                    end,
+                })?;
+                self.nesting -= 1;
+                self.depth -= 1;
+                value
+            }
+            ValueStart::Array(span) => {
+                self.descend(span)?;
+                self.nesting += 1;
+                let value = self.array().map(|(Span { end, .. }, array)| Val {
+                    e: E::Array(array),
+                    start: span.start,
+                    end,
+                })?;
+                self.nesting -= 1;
+                self.depth -= 1;
+                value
+            }
+        };
+        Ok(value)
+    }
+
+    /// Parses a scalar value, or the opening brace or bracket of an inline
+    /// table or array
+    pub(crate) fn value_start(&mut self) -> Result<ValueStart<'a>, Error> {
+        if let Some((span @ Span { start, end }, raw)) = self.tokens.datetime() {
+            let dt = raw
+                .parse()
+                .map_err(|_e| self.error(start, Some(end), ErrorKind::InvalidDatetime))?;
+            if omits_seconds(raw) {
+                self.unsupported(span, "times without seconds")?;
+            }
+            return Ok(ValueStart::Scalar(Val {
+                e: E::Datetime(dt),
                 start,
                 end,
-            },
+            }));
+        }
+
+        let at = self.tokens.current();
+        let value = match self.next()? {
+            Some((span @ Span { start, end }, Token::String { val, .. })) => {
+                self.limit("string length", val.len(), self.limits.max_string_len, span)?;
+                Val {
+                    e: E::String(val),
+                    start,
+                    end,
+                }
+            }
             Some((Span { start, end }, Token::Keylike("true"))) => Val {
                 e: E::Boolean(true),
                 start,
@@ -545,20 +1032,8 @@ impl<'a> Deserializer<'a> {
             },
             Some((span, Token::Keylike(key))) => self.parse_keylike(at, span, key)?,
             Some((span, Token::Plus)) => self.number_leading_plus(span)?,
-            Some((Span { start, .. }, Token::LeftBrace)) => {
-                self.inline_table().map(|(Span { end, .. }, table)| Val {
-                    e: E::InlineTable(table),
-                    start,
-                    end,
-                })?
-            }
-            Some((Span { start, .. }, Token::LeftBracket)) => {
-                self.array().map(|(Span { end, .. }, array)| Val {
-                    e: E::Array(array),
-                    start,
-                    end,
-                })?
-            }
+            Some((span, Token::LeftBrace)) => return Ok(ValueStart::InlineTable(span)),
+            Some((span, Token::LeftBracket)) => return Ok(ValueStart::Array(span)),
             Some(token) => {
                 return Err(self.error(
                     at,
@@ -571,7 +1046,7 @@ impl<'a> Deserializer<'a> {
             }
             None => return Err(self.eof()),
         };
-        Ok(value)
+        Ok(ValueStart::Scalar(value))
     }
 
     fn parse_keylike(&mut self, at: usize, span: Span, key: &'a str) -> Result<Val<'a>, Error> {
@@ -648,6 +1123,14 @@ impl<'a> Deserializer<'a> {
     fn number_leading_plus(&mut self, Span { start, end }: Span) -> Result<Val<'a>, Error> {
         let start_token = self.tokens.current();
         match self.next()? {
+            // Only decimal numbers can have a leading plus
+            Some((Span { end, .. }, Token::Keylike(s)))
+                if ["0x", "0o", "0b"]
+                    .iter()
+                    .any(|prefix| s.starts_with(prefix)) =>
+            {
+                Err(self.error(start, Some(end), ErrorKind::InvalidNumber))
+            }
             Some((Span { end, .. }, Token::Keylike(s))) => self.number(Span { start, end }, s),
             _ => Err(self.error(start_token, Some(end), ErrorKind::InvalidNumber)),
         }
@@ -776,64 +1259,89 @@ impl<'a> Deserializer<'a> {
     // great to defer parsing everything until later.
     fn inline_table(&mut self) -> Result<(Span, TableValues<'a>), Error> {
         let mut ret = TableValues::default();
-
-        // TOML 1.1: inline tables can span multiple lines and have trailing commas
-        let intermediate = |me: &mut Deserializer<'_>| -> Result<(), Error> {
-            loop {
-                me.eat_whitespace();
-                if !me.eat(Token::Newline)? && !me.eat_comment()? {
-                    break;
-                }
-            }
-            Ok(())
-        };
+        let intermediate = Self::inline_table_whitespace;
 
         intermediate(self)?;
         if let Some(span) = self.eat_spanned(Token::RightBrace)? {
             return Ok((span, ret));
         }
         loop {
+            let start = self.tokens.current();
             let key = self.dotted_key()?;
+            self.descend_keys(&key)?;
             intermediate(self)?;
             self.expect(Token::Equals)?;
             intermediate(self)?;
             let value = self.value()?;
-            self.add_dotted_key(key, value, &mut ret)?;
+            self.depth -= key.len();
+            let end = self.tokens.current();
+            self.push_item(&key, start, end);
+            if let Err(err) = self.add_dotted_key(key, value, &mut ret) {
+                self.recover(err)?;
+            }
 
             intermediate(self)?;
             if let Some(span) = self.eat_spanned(Token::RightBrace)? {
                 return Ok((span, ret));
             }
-            self.expect(Token::Comma)?;
+            let comma = self.expect_spanned(Token::Comma)?;
             intermediate(self)?;
             // TOML 1.1: trailing comma is allowed
             if let Some(span) = self.eat_spanned(Token::RightBrace)? {
+                self.unsupported(comma, "trailing commas in inline tables")?;
                 return Ok((span, ret));
             }
         }
     }
 
+    /// Skips the whitespace, newlines, and comments between the elements of
+    /// an inline table
+    pub(crate) fn inline_table_whitespace(&mut self) -> Result<(), Error> {
+        // TOML 1.1: inline tables can span multiple lines and have trailing commas
+        loop {
+            self.eat_whitespace();
+            match self.peek()? {
+                Some((span, Token::Newline)) => {
+                    self.unsupported(span, "newlines in inline tables")?;
+                }
+                Some((span, Token::Comment(_))) => {
+                    self.unsupported(span, "comments in inline tables")?;
+                }
+                _ => break,
+            }
+            if !self.eat(Token::Newline)? {
+                self.eat_comment()?;
+            }
+        }
+        Ok(())
+    }
+
     // TODO(#140): shouldn't buffer up this entire array in memory, it'd be
     // great to defer parsing everything until later.
     fn array(&mut self) -> Result<(Span, Vec<Val<'a>>), Error> {
         let mut ret = Vec::new();
-
-        let intermediate = |me: &mut Deserializer<'_>| -> Result<(), Error> {
-            loop {
-                me.eat_whitespace();
-                if !me.eat(Token::Newline)? && !me.eat_comment()? {
-                    break;
-                }
-            }
-            Ok(())
-        };
+        let intermediate = Self::array_whitespace;
 
         loop {
             intermediate(self)?;
             if let Some(span) = self.eat_spanned(Token::RightBracket)? {
                 return Ok((span, ret));
             }
+            let start = self.tokens.current();
             let value = self.value()?;
+            self.limit(
+                "array length",
+                ret.len() + 1,
+                self.limits.max_array_len,
+                Span::new(value.start, value.end),
+            )?;
+            if let Some(items) = &mut self.items {
+                items.push(comments::Item {
+                    key: value.start,
+                    start,
+                    end: self.tokens.current(),
+                });
+            }
             ret.push(value);
             intermediate(self)?;
             if !self.eat(Token::Comma)? {
@@ -845,11 +1353,36 @@ impl<'a> Deserializer<'a> {
         Ok((span, ret))
     }
 
-    fn table_key(&mut self) -> Result<Key<'a>, Error> {
-        self.tokens.table_key().map_err(|e| self.token_error(e))
+    /// Skips the whitespace, newlines, and comments between the elements of
+    /// an array
+    pub(crate) fn array_whitespace(&mut self) -> Result<(), Error> {
+        loop {
+            self.eat_whitespace();
+            if !self.eat(Token::Newline)? && !self.eat_comment()? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a `key = value` pair that comments can be attached to
+    fn push_item(&mut self, key: &[Key<'a>], start: usize, end: usize) {
+        if let (Some(items), Some(last)) = (&mut self.items, key.last()) {
+            items.push(comments::Item {
+                key: last.span.start,
+                start,
+                end,
+            });
+        }
+    }
+
+    pub(crate) fn table_key(&mut self) -> Result<Key<'a>, Error> {
+        let key = self.tokens.table_key().map_err(|e| self.token_error(e))?;
+        self.limit_string(&key)?;
+        Ok(key)
     }
 
-    fn dotted_key(&mut self) -> Result<Vec<Key<'a>>, Error> {
+    pub(crate) fn dotted_key(&mut self) -> Result<Vec<Key<'a>>, Error> {
         let mut result = Vec::new();
         result.push(self.table_key()?);
         self.eat_whitespace();
@@ -873,7 +1406,7 @@ impl<'a> Deserializer<'a> {
     ///   `vec![Cow::Borrowed("part"), Cow::Borrowed("one")].`
     /// * `value`: The parsed value.
     /// * `values`: The `Vec` to store the value in.
-    fn add_dotted_key(
+    pub(crate) fn add_dotted_key(
         &self,
         mut key_parts: Vec<Key<'a>>,
         value: Val<'a>,
@@ -928,25 +1461,25 @@ impl<'a> Deserializer<'a> {
         Ok(())
     }
 
-    fn eat_whitespace(&mut self) {
+    pub(crate) fn eat_whitespace(&mut self) {
         self.tokens.eat_whitespace();
     }
 
-    fn eat_comment(&mut self) -> Result<bool, Error> {
+    pub(crate) fn eat_comment(&mut self) -> Result<bool, Error> {
         self.tokens.eat_comment().map_err(|e| self.token_error(e))
     }
 
-    fn eat_newline_or_eof(&mut self) -> Result<(), Error> {
+    pub(crate) fn eat_newline_or_eof(&mut self) -> Result<(), Error> {
         self.tokens
             .eat_newline_or_eof()
             .map_err(|e| self.token_error(e))
     }
 
-    fn eat(&mut self, expected: Token<'a>) -> Result<bool, Error> {
+    pub(crate) fn eat(&mut self, expected: Token<'a>) -> Result<bool, Error> {
         self.tokens.eat(expected).map_err(|e| self.token_error(e))
     }
 
-    fn eat_spanned(&mut self, expected: Token<'a>) -> Result<Option<Span>, Error> {
+    pub(crate) fn eat_spanned(&mut self, expected: Token<'a>) -> Result<Option<Span>, Error> {
         self.tokens
             .eat_spanned(expected)
             .map_err(|e| self.token_error(e))
@@ -958,17 +1491,17 @@ impl<'a> Deserializer<'a> {
             .map_err(|e| self.token_error(e))
     }
 
-    fn expect_spanned(&mut self, expected: Token<'a>) -> Result<Span, Error> {
+    pub(crate) fn expect_spanned(&mut self, expected: Token<'a>) -> Result<Span, Error> {
         self.tokens
             .expect_spanned(expected)
             .map_err(|e| self.token_error(e))
     }
 
-    fn next(&mut self) -> Result<Option<(Span, Token<'a>)>, Error> {
+    pub(crate) fn next(&mut self) -> Result<Option<(Span, Token<'a>)>, Error> {
         self.tokens.step().map_err(|e| self.token_error(e))
     }
 
-    fn peek(&mut self) -> Result<Option<(Span, Token<'a>)>, Error> {
+    pub(crate) fn peek(&mut self) -> Result<Option<(Span, Token<'a>)>, Error> {
         self.tokens.peek().map_err(|e| self.token_error(e))
     }
 
@@ -1007,37 +1540,110 @@ impl<'a> Deserializer<'a> {
             TokenError::MultilineStringKey(at, end) => {
                 self.error(at, Some(end), ErrorKind::MultilineStringKey)
             }
+            TokenError::Unsupported(at, end, feature) => self.error(
+                at,
+                Some(end),
+                ErrorKind::UnsupportedFeature {
+                    feature,
+                    version: self.version,
+                },
+            ),
         }
     }
 
-    fn error(&self, start: usize, end: Option<usize>, kind: ErrorKind) -> Error {
-        let span = Span::new(start, end.unwrap_or(start + 1));
-        let line_info = Some(self.to_linecol(start));
-        Error {
-            span,
-            kind,
-            line_info,
+    /// Fails if the document is parsed as TOML 1.0, as the feature was added
+    /// in TOML 1.1
+    pub(crate) fn unsupported(&mut self, span: Span, feature: &'static str) -> Result<(), Error> {
+        if self.version != TomlVersion::V1_0 {
+            return Ok(());
         }
+
+        let err = self.error(
+            span.start,
+            Some(span.end),
+            ErrorKind::UnsupportedFeature {
+                feature,
+                version: self.version,
+            },
+        );
+        self.recover(err)
     }
 
-    /// Converts a byte offset from an error message to a (line, column) pair
-    ///
-    /// All indexes are 0-based.
-    fn to_linecol(&self, offset: usize) -> (usize, usize) {
-        let mut cur = 0;
-        // Use split_terminator instead of lines so that if there is a `\r`, it
-        // is included in the offset calculation. The `+1` values below account
-        // for the `\n`.
-        for (i, line) in self.input.split_terminator('\n').enumerate() {
-            if cur + line.len() + 1 > offset {
-                return (i, offset - cur);
-            }
-            cur += line.len() + 1;
+    /// Fails if `value` exceeds the maximum of the specified limit
+    pub(crate) fn limit(
+        &self,
+        limit: &'static str,
+        value: usize,
+        max: usize,
+        span: Span,
+    ) -> Result<(), Error> {
+        if value > max {
+            Err(self.error(
+                span.start,
+                Some(span.end),
+                ErrorKind::LimitExceeded { limit, max },
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn limit_string(&self, key: &Key<'_>) -> Result<(), Error> {
+        self.limit(
+            "string length",
+            key.name.len(),
+            self.limits.max_string_len,
+            key.span,
+        )
+    }
+
+    /// Descends into an array or inline table
+    pub(crate) fn descend(&mut self, span: Span) -> Result<(), Error> {
+        self.limit("depth", self.depth + 1, self.limits.max_depth, span)?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Descends into the tables of a dotted key or table header
+    pub(crate) fn descend_keys(&mut self, keys: &[Key<'_>]) -> Result<(), Error> {
+        let within = self.limits.max_depth.saturating_sub(self.depth);
+        if let Some(key) = keys.get(within) {
+            let depth = self.depth + within + 1;
+            self.limit("depth", depth, self.limits.max_depth, key.span)?;
         }
-        (self.input.lines().count(), 0)
+        self.depth += keys.len();
+        Ok(())
+    }
+
+    fn error(&self, start: usize, end: Option<usize>, kind: ErrorKind) -> Error {
+        let span = Span::new(start, end.unwrap_or(start + 1));
+        let position = self
+            .line_index
+            .get_or_init(|| LineIndex::new(self.input))
+            .position(start, ColumnUnit::Utf8);
+        let mut error = Error::new(kind, span);
+        error.line_info = Some((position.line, position.column));
+        error
     }
 }
 
+/// Returns true if the time of a valid datetime omits the seconds
+fn omits_seconds(raw: &str) -> bool {
+    let time = if raw.as_bytes().get(2) == Some(&b':') {
+        raw
+    } else {
+        raw.get(11..).unwrap_or_default()
+    };
+    time.len() >= 5 && time.as_bytes().get(5) != Some(&b':')
+}
+
+/// The start of a value, see [`Deserializer::value_start`]
+pub(crate) enum ValueStart<'a> {
+    Scalar(Val<'a>),
+    InlineTable(Span),
+    Array(Span),
+}
+
 impl std::convert::From<Error> for std::io::Error {
     fn from(e: Error) -> Self {
         std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
@@ -1063,6 +1669,8 @@ struct Header<'a> {
     first: bool,
     array: bool,
     tokens: Tokenizer<'a>,
+    /// The offset after the closing bracket(s)
+    close: usize,
 }
 
 impl<'a> Header<'a> {
@@ -1071,6 +1679,7 @@ impl<'a> Header<'a> {
             first: true,
             array,
             tokens,
+            close: 0,
         }
     }
 
@@ -1086,6 +1695,7 @@ impl<'a> Header<'a> {
             if self.array {
                 self.tokens.expect(Token::RightBracket)?;
             }
+            self.close = self.tokens.current();
 
             self.tokens.eat_whitespace();
             if !self.tokens.eat_comment()? {
@@ -1096,16 +1706,17 @@ impl<'a> Header<'a> {
     }
 }
 
-struct Val<'a> {
-    e: E<'a>,
-    start: usize,
-    end: usize,
+pub(crate) struct Val<'a> {
+    pub(crate) e: E<'a>,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
 }
 
-enum E<'a> {
+pub(crate) enum E<'a> {
     Integer(i64),
     Float(f64),
     Boolean(bool),
+    Datetime(Datetime),
     String(DeStr<'a>),
     Array(Vec<Val<'a>>),
     InlineTable(TableValues<'a>),
@@ -1120,6 +1731,7 @@ impl E<'_> {
             E::Integer(..) => "integer",
             E::Float(..) => "float",
             E::Boolean(..) => "boolean",
+            E::Datetime(..) => "datetime",
             E::Array(..) => "array",
             E::InlineTable(..) => "inline table",
             E::DottedTable(..) => "dotted table",
@@ -1,23 +1,56 @@
 //! Provides helpers for deserializing [`Value`]/[`ValueInner`] into Rust types
 
 use crate::{
-    DeserError, Deserialize, Error, ErrorKind, Span,
+    DeserError, Deserialize, DeserializeKey, Error, ErrorKind, PathSegment, Severity, Span,
     span::Spanned,
     value::{self, Table, Value, ValueInner},
 };
-use std::{fmt::Display, str::FromStr};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, btree_map, hash_map},
+    fmt::Display,
+    hash::{BuildHasher, Hash},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    num,
+    ops::Range,
+    path::PathBuf,
+    rc::Rc,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+/// Deserializes the value of a key or array element, adding the segment to
+/// the path of the errors that occur while doing so, and of the warnings,
+/// which are added to `warnings`
+fn deserialize_at<'de, T: Deserialize<'de>>(
+    value: &mut Value<'de>,
+    segment: impl Fn() -> PathSegment,
+    warnings: &mut Vec<Error>,
+) -> Result<T, DeserError> {
+    let res = T::deserialize(value);
+
+    for mut warning in value.take_warnings() {
+        warning.path.prepend(segment());
+        warnings.push(warning);
+    }
+
+    res.map_err(|mut err| {
+        err.prepend_path(segment());
+        err
+    })
+}
 
 /// Helper for construction an [`ErrorKind::Wanted`]
 #[inline]
 pub fn expected(expected: &'static str, found: ValueInner<'_>, span: Span) -> Error {
-    Error {
-        kind: ErrorKind::Wanted {
+    Error::new(
+        ErrorKind::Wanted {
             expected,
             found: found.type_str(),
         },
         span,
-        line_info: None,
-    }
+    )
 }
 
 /// Attempts to acquire a [`ValueInner::String`] and parse it, returning an error
@@ -31,11 +64,212 @@ where
     let s = value.take_string(None)?;
     match s.parse() {
         Ok(v) => Ok(v),
-        Err(err) => Err(Error {
-            kind: ErrorKind::Custom(format!("failed to parse string: {err}").into()),
-            span: value.span,
-            line_info: None,
-        }),
+        Err(err) => Err(Error::new(
+            ErrorKind::Custom(format!("failed to parse string: {err}").into()),
+            value.span,
+        )),
+    }
+}
+
+/// Takes a string that must be one of `variants`, the names of the unit
+/// variants of an enum, returning the matching name
+///
+/// ```
+/// use toml_span::{DeserError, Deserialize, Value, de_helpers::unit_variant};
+///
+/// enum Level {
+///     Allow,
+///     Deny,
+/// }
+///
+/// impl<'de> Deserialize<'de> for Level {
+///     fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+///         Ok(match unit_variant(value, &["allow", "deny"])? {
+///             "allow" => Self::Allow,
+///             _ => Self::Deny,
+///         })
+///     }
+/// }
+/// ```
+///
+/// # Errors
+/// - The value is not a string
+/// - The string is not one of the variants, see [`ErrorKind::UnexpectedValue`]
+pub fn unit_variant(
+    value: &mut Value<'_>,
+    variants: &'static [&'static str],
+) -> Result<&'static str, Error> {
+    let s = value.take_string(Some("a string variant"))?;
+    find_variant(&s, variants, value.span)
+}
+
+/// Finds the variant with the specified name, or fails with an
+/// [`ErrorKind::UnexpectedValue`] at the span
+fn find_variant(
+    name: &str,
+    variants: &'static [&'static str],
+    span: Span,
+) -> Result<&'static str, Error> {
+    variants
+        .iter()
+        .find(|variant| **variant == name)
+        .copied()
+        .ok_or_else(|| {
+            Error::from((
+                ErrorKind::UnexpectedValue {
+                    expected: variants,
+                    value: Some(name.into()),
+                },
+                span,
+            ))
+        })
+}
+
+/// The variant of an externally tagged enum, see [`external_variant`]
+pub struct Variant<'v, 'de> {
+    /// The name of the variant
+    pub name: &'static str,
+    /// The span of the key the variant was named by
+    pub span: Span,
+    /// The content of the variant
+    pub value: Value<'de>,
+    /// The enum's value, which the warnings of the variant are reported to
+    original: &'v mut Value<'de>,
+}
+
+impl<'de> Variant<'_, 'de> {
+    /// Deserializes the content of the variant, adding the variant's name to
+    /// the path of any errors and warnings
+    pub fn deserialize<T: Deserialize<'de>>(mut self) -> Result<T, DeserError> {
+        let name = self.name;
+        let mut warnings = Vec::new();
+        let res = deserialize_at(
+            &mut self.value,
+            || PathSegment::Key(name.into()),
+            &mut warnings,
+        );
+        self.original.add_warnings(warnings);
+        res
+    }
+}
+
+/// Takes a table with a single key, the name of one of `variants`, whose
+/// value is the content of the variant, eg. `{ git = { url = "..." } }`
+///
+/// ```
+/// use toml_span::{DeserError, Deserialize, Value, de_helpers::external_variant};
+///
+/// enum Source {
+///     Git(String),
+///     Path(String),
+/// }
+///
+/// impl<'de> Deserialize<'de> for Source {
+///     fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+///         let variant = external_variant(value, &["git", "path"])?;
+///         Ok(match variant.name {
+///             "git" => Self::Git(variant.deserialize()?),
+///             _ => Self::Path(variant.deserialize()?),
+///         })
+///     }
+/// }
+///
+/// let mut value = toml_span::parse("git = 'https://github.com'").unwrap();
+/// assert!(matches!(Source::deserialize(&mut value), Ok(Source::Git(_))));
+/// ```
+///
+/// # Errors
+/// - The value is not a table
+/// - The table doesn't have exactly one key
+/// - The key is not one of the variants, see [`ErrorKind::UnexpectedValue`]
+pub fn external_variant<'v, 'de>(
+    value: &'v mut Value<'de>,
+    variants: &'static [&'static str],
+) -> Result<Variant<'v, 'de>, Error> {
+    let table = match value.take() {
+        ValueInner::Table(table) => table,
+        other => return Err(expected("a table with a single key", other, value.span)),
+    };
+
+    if table.len() != 1 {
+        return Err((
+            ErrorKind::Custom(
+                format!(
+                    "expected a table with a single key naming one of {variants:?}, found {} keys",
+                    table.len()
+                )
+                .into(),
+            ),
+            value.span,
+        )
+            .into());
+    }
+
+    let (key, content) = table.into_iter().next().unwrap();
+    Ok(Variant {
+        name: find_variant(&key.name, variants, key.span)?,
+        span: key.span,
+        value: content,
+        original: value,
+    })
+}
+
+/// A value that can be either a string or a table, see [`string_or_table`]
+pub enum StringOrTable<'de> {
+    /// The value is a string
+    String(Spanned<Cow<'de, str>>),
+    /// The value is a table
+    Table(TableHelper<'de>),
+}
+
+/// Takes a value that can be written either as a string, typically a
+/// shorthand, or as a table, eg. `"name:1.0"` or `{ name = "name" }`
+///
+/// As the table is taken out of the value, warnings reported with the
+/// [`TableHelper`] are only added to the value with
+/// [`TableHelper::report_warnings`], or by finalizing with it.
+///
+/// ```
+/// use toml_span::{
+///     DeserError, Deserialize, Value,
+///     de_helpers::{StringOrTable, string_or_table},
+/// };
+///
+/// struct Package {
+///     name: String,
+///     version: Option<String>,
+/// }
+///
+/// impl<'de> Deserialize<'de> for Package {
+///     fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+///         match string_or_table(value)? {
+///             StringOrTable::String(s) => Ok(Self {
+///                 name: s.value.into(),
+///                 version: None,
+///             }),
+///             StringOrTable::Table(mut th) => {
+///                 let name = th.required("name");
+///                 let version = th.optional("version");
+///                 th.finalize(None)?;
+///                 Ok(Self {
+///                     name: name.unwrap(),
+///                     version,
+///                 })
+///             }
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Errors
+/// - The value is neither a string nor a table
+pub fn string_or_table<'de>(value: &mut Value<'de>) -> Result<StringOrTable<'de>, Error> {
+    match value.take() {
+        ValueInner::String(s) => Ok(StringOrTable::String(Spanned::with_span(s, value.span))),
+        ValueInner::Table(table) => {
+            Ok(StringOrTable::Table(TableHelper::from((table, value.span))))
+        }
+        other => Err(expected("a string or table", other, value.span)),
     }
 }
 
@@ -45,6 +279,9 @@ pub struct TableHelper<'de> {
     pub table: Table<'de>,
     /// The errors accumulated while deserializing
     pub errors: Vec<Error>,
+    /// The warnings accumulated while deserializing, which unlike
+    /// [`Self::errors`] don't cause deserialization to fail, see [`Self::warn`]
+    pub warnings: Vec<Error>,
     /// The list of keys that have been requested by the user, this is used to
     /// show a list of keys that _could_ be used in the case the finalize method
     /// fails due to keys still being present in the map
@@ -54,12 +291,14 @@ pub struct TableHelper<'de> {
 }
 
 impl<'de> From<(Table<'de>, Span)> for TableHelper<'de> {
+    /// Creates a helper for a table that was already taken out of its value
     fn from((table, span): (Table<'de>, Span)) -> Self {
         Self {
             table,
             span,
             expected: Vec::new(),
             errors: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 }
@@ -74,6 +313,7 @@ impl<'de> TableHelper<'de> {
 
         Ok(Self {
             errors: Vec::new(),
+            warnings: Vec::new(),
             table,
             expected: Vec::new(),
             span: value.span,
@@ -114,21 +354,11 @@ impl<'de> TableHelper<'de> {
     ) -> Result<Spanned<T>, Error> {
         self.expected.push(name);
 
-        let Some(mut val) = self.table.remove(name) else {
-            let missing = Error {
-                kind: ErrorKind::MissingField(name),
-                span: self.span,
-                line_info: None,
-            };
-            self.errors.push(missing.clone());
-            return Err(missing);
+        let Some(val) = self.table.remove(name) else {
+            return Err(self.missing(name));
         };
 
-        Spanned::<T>::deserialize(&mut val).map_err(|mut errs| {
-            let err = errs.errors.last().unwrap().clone();
-            self.errors.append(&mut errs.errors);
-            err
-        })
+        self.deserialize_entry(name, val)
     }
 
     /// Attempts to deserialize the specified key, if it exists
@@ -145,9 +375,205 @@ impl<'de> TableHelper<'de> {
     pub fn optional_s<T: Deserialize<'de>>(&mut self, name: &'static str) -> Option<Spanned<T>> {
         self.expected.push(name);
 
-        let mut val = self.table.remove(name)?;
+        let val = self.table.remove(name)?;
+        self.deserialize_entry(name, val).ok()
+    }
+
+    /// Takes the string value of `key`, the tag of an internally tagged enum
+    /// that names one of `variants`, eg. `{ type = "git", url = "..." }`
+    ///
+    /// The fields of the variant can then be deserialized from the rest of
+    /// the table.
+    ///
+    /// # Errors
+    /// - The key does not exist
+    /// - The value is not a string
+    /// - The string is not one of the variants, see [`ErrorKind::UnexpectedValue`]
+    pub fn tag(
+        &mut self,
+        key: &'static str,
+        variants: &'static [&'static str],
+    ) -> Result<&'static str, Error> {
+        let tag: Spanned<Cow<'de, str>> = self.required_s(key)?;
+        find_variant(&tag.value, variants, tag.span).map_err(|mut err| {
+            err.path.prepend(PathSegment::Key(key.into()));
+            self.errors.push(err.clone());
+            err
+        })
+    }
+
+    /// Attempts to deserialize a field that can be specified with any of the
+    /// keys in `names`, the first of which is its primary name
+    ///
+    /// # Panics
+    /// - `names` is empty
+    ///
+    /// # Errors
+    /// - None of the keys exist, reported as the first name missing
+    /// - More than one of the keys exist, an [`ErrorKind::DuplicateField`] is
+    ///   recorded for each of them other than the first one found
+    /// - The [`Deserialize`] implementation for the type returns an error
+    pub fn required_aliased<T: Deserialize<'de>>(
+        &mut self,
+        names: &[&'static str],
+    ) -> Result<T, Error> {
+        let Some((key, val)) = self.take_aliased(names)? else {
+            return Err(self.missing(names[0]));
+        };
+
+        Ok(self.deserialize_entry(&key.name, val)?.value)
+    }
+
+    /// Attempts to deserialize a field that can be specified with any of the
+    /// keys in `names`, if one of them exists
+    ///
+    /// Errors are accumulated the same as [`Self::optional`], including if
+    /// more than one of the keys exist
+    ///
+    /// # Panics
+    /// - `names` is empty
+    pub fn optional_aliased<T: Deserialize<'de>>(&mut self, names: &[&'static str]) -> Option<T> {
+        let (key, val) = self.take_aliased(names).ok()??;
+        self.deserialize_entry(&key.name, val).ok().map(|v| v.value)
+    }
+
+    /// The same as [`Self::required`], except the field can also be specified
+    /// with its `old` key, which is reported as an [`ErrorKind::Deprecated`]
+    /// [`Self::warn`]ing
+    ///
+    /// It is an error to specify both keys, see [`ErrorKind::DuplicateField`]
+    pub fn required_or_deprecated<T: Deserialize<'de>>(
+        &mut self,
+        new: &'static str,
+        old: &'static str,
+    ) -> Result<T, Error> {
+        let Some((key, val)) = self.take_deprecated(new, old)? else {
+            return Err(self.missing(new));
+        };
+
+        Ok(self.deserialize_entry(&key.name, val)?.value)
+    }
+
+    /// The same as [`Self::optional`], except the field can also be specified
+    /// with its `old` key, which is reported as an [`ErrorKind::Deprecated`]
+    /// [`Self::warn`]ing
+    ///
+    /// It is an error to specify both keys, see [`ErrorKind::DuplicateField`]
+    pub fn optional_or_deprecated<T: Deserialize<'de>>(
+        &mut self,
+        new: &'static str,
+        old: &'static str,
+    ) -> Option<T> {
+        let (key, val) = self.take_deprecated(new, old).ok()??;
+        self.deserialize_entry(&key.name, val).ok().map(|v| v.value)
+    }
+
+    /// Records a [`ErrorKind::MissingField`] error
+    fn missing(&mut self, name: &'static str) -> Error {
+        let missing = Error::new(ErrorKind::MissingField(name), self.span);
+        self.errors.push(missing.clone());
+        missing
+    }
+
+    /// Deserializes the value of the key, recording any errors
+    fn deserialize_entry<T: Deserialize<'de>>(
+        &mut self,
+        name: &str,
+        mut val: Value<'de>,
+    ) -> Result<Spanned<T>, Error> {
+        deserialize_at::<Spanned<T>>(
+            &mut val,
+            || PathSegment::Key(name.into()),
+            &mut self.warnings,
+        )
+        .map_err(|mut errs| {
+            let err = errs.errors.last().unwrap().clone();
+            self.errors.append(&mut errs.errors);
+            err
+        })
+    }
+
+    /// Takes the first of the keys that exists, recording an error for each
+    /// of the other keys that also exist
+    ///
+    /// All of the keys are removed from the table, so that the duplicates are
+    /// not also reported as unexpected keys by [`Self::finalize`]
+    fn take_aliased(
+        &mut self,
+        names: &[&'static str],
+    ) -> Result<Option<(value::Key<'de>, Value<'de>)>, Error> {
+        assert!(
+            !names.is_empty(),
+            "an aliased field must have at least one name"
+        );
+        self.expected.push(names[0]);
+
+        let mut found: Option<(value::Key<'de>, Value<'de>)> = None;
+        let mut duplicate = None;
+        for &name in names {
+            let Some((key, val)) = self.table.remove_entry(name) else {
+                continue;
+            };
+
+            if let Some((first, _)) = &found {
+                let err = Error::from((
+                    ErrorKind::DuplicateField {
+                        field: names[0],
+                        first: first.span,
+                    },
+                    key.span,
+                ));
+                self.errors.push(err.clone());
+                duplicate.get_or_insert(err);
+                continue;
+            }
+
+            found = Some((key, val));
+        }
+
+        match duplicate {
+            Some(err) => Err(err),
+            None => Ok(found),
+        }
+    }
+
+    /// Takes the new or the old key, warning if it is the old one
+    fn take_deprecated(
+        &mut self,
+        new: &'static str,
+        old: &'static str,
+    ) -> Result<Option<(value::Key<'de>, Value<'de>)>, Error> {
+        let found = self.take_aliased(&[new, old])?;
+        if let Some((key, _)) = &found {
+            if key.name == old {
+                self.warn((ErrorKind::Deprecated { old, new }, key.span));
+            }
+        }
+        Ok(found)
+    }
 
-        match Spanned::<T>::deserialize(&mut val) {
+    /// Deserializes `T` from the keys still remaining in the table, the
+    /// equivalent of [`#[serde(flatten)]`](https://serde.rs/field-attrs.html#flatten)
+    ///
+    /// Any keys that `T` does not consume, ie. if it calls [`Self::finalize`]
+    /// with the original value, are put back into this table. Errors are
+    /// accumulated the same as [`Self::optional`].
+    pub fn flatten<T: Deserialize<'de>>(&mut self) -> Option<T> {
+        let mut value = Value::with_span(
+            ValueInner::Table(std::mem::take(&mut self.table)),
+            self.span,
+        );
+
+        let res = T::deserialize(&mut value);
+        self.warnings.append(&mut value.take_warnings());
+
+        if value.as_table().is_some() {
+            if let ValueInner::Table(table) = value.take() {
+                self.table = table;
+            }
+        }
+
+        match res {
             Ok(v) => Some(v),
             Err(mut err) => {
                 self.errors.append(&mut err.errors);
@@ -156,6 +582,61 @@ impl<'de> TableHelper<'de> {
         }
     }
 
+    /// Reports a warning, which unlike [`Self::errors`] doesn't cause
+    /// deserialization to fail
+    ///
+    /// The warning's [`Error::severity`] is set to [`Severity::Warning`]. The
+    /// warnings are added to the original value when the helper is finalized
+    /// with it, see [`Self::report_warnings`].
+    #[inline]
+    pub fn warn(&mut self, warning: impl Into<Error>) {
+        let mut warning = warning.into();
+        warning.severity = Severity::Warning;
+        self.warnings.push(warning);
+    }
+
+    /// Moves the [`Self::warnings`] to the original value the helper was
+    /// created for, so that they can be retrieved with [`Value::take_warnings`]
+    ///
+    /// This is done when finalizing with the original value, so only needs
+    /// to be called before [`Self::finalize`]ing without it, otherwise the
+    /// warnings are discarded.
+    #[inline]
+    pub fn report_warnings(&mut self, original: &mut Value<'de>) {
+        original.add_warnings(std::mem::take(&mut self.warnings));
+    }
+
+    /// Called when you are finished with this [`TableHelper`], reporting
+    /// any keys that were not handled as a [`Self::warn`]ing rather than an
+    /// error, which are added to the original value along with the other
+    /// warnings
+    pub fn finalize_warn_unknown(mut self, original: &mut Value<'de>) -> Result<(), DeserError> {
+        if !self.table.is_empty() {
+            let keys = std::mem::take(&mut self.table)
+                .into_keys()
+                .map(|key| (key.name.into(), key.span))
+                .collect();
+            let expected = std::mem::take(&mut self.expected);
+
+            self.warn((
+                ErrorKind::UnexpectedKeys {
+                    keys,
+                    expected: expected.into_iter().map(String::from).collect(),
+                },
+                self.span,
+            ));
+        }
+        self.report_warnings(original);
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(DeserError {
+                errors: self.errors,
+            })
+        }
+    }
+
     /// Called when you are finished with this [`TableHelper`]
     ///
     /// If errors have been accumulated when using this [`TableHelper`], this will
@@ -166,13 +647,14 @@ impl<'de> TableHelper<'de> {
     /// which can be considered equivalent to [`#[serde(deny_unknown_fields)]`](https://serde.rs/container-attrs.html#deny_unknown_fields)
     ///
     /// If you want simulate [`#[serde(flatten)]`](https://serde.rs/field-attrs.html#flatten)
-    /// you can instead put that table back in its original value during this step
+    /// you can instead put that table back in its original value during this step,
+    /// which also reports the [`Self::warnings`] to it
     pub fn finalize(mut self, original: Option<&mut Value<'de>>) -> Result<(), DeserError> {
         if let Some(original) = original {
-            original.set(ValueInner::Table(self.table));
+            original.set(ValueInner::Table(std::mem::take(&mut self.table)));
+            self.report_warnings(original);
         } else if !self.table.is_empty() {
-            let keys = self
-                .table
+            let keys = std::mem::take(&mut self.table)
                 .into_keys()
                 .map(|key| (key.name.into(), key.span))
                 .collect();
@@ -181,7 +663,7 @@ impl<'de> TableHelper<'de> {
                 (
                     ErrorKind::UnexpectedKeys {
                         keys,
-                        expected: self.expected.into_iter().map(String::from).collect(),
+                        expected: self.expected.iter().map(|s| String::from(*s)).collect(),
                     },
                     self.span,
                 )
@@ -208,7 +690,9 @@ impl<'de> Deserialize<'de> for String {
     }
 }
 
-impl<'de> Deserialize<'de> for std::borrow::Cow<'de, str> {
+/// Like serde's implementation, this can borrow for any lifetime the document
+/// outlives, so that it can be used in types that use `#[serde(borrow)]`
+impl<'de: 'a, 'a> Deserialize<'de> for Cow<'a, str> {
     fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
         value.take_string(None).map_err(DeserError::from)
     }
@@ -230,11 +714,10 @@ macro_rules! integer {
                 match value.take() {
                     ValueInner::Integer(i) => {
                         let i = i.try_into().map_err(|_| {
-                            DeserError::from(Error {
-                                kind: ErrorKind::OutOfRange(stringify!($num)),
-                                span: value.span,
-                                line_info: None,
-                            })
+                            DeserError::from(Error::new(
+                                ErrorKind::OutOfRange(stringify!($num)),
+                                value.span,
+                            ))
                         })?;
 
                         Ok(i)
@@ -260,7 +743,14 @@ integer!(isize);
 impl<'de> Deserialize<'de> for f32 {
     fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
         match value.take() {
-            ValueInner::Float(f) => Ok(f as f32),
+            ValueInner::Float(f) => {
+                let narrowed = f as f32;
+                if narrowed.is_infinite() && f.is_finite() {
+                    Err(Error::from((ErrorKind::OutOfRange("f32"), value.span)).into())
+                } else {
+                    Ok(narrowed)
+                }
+            }
             other => Err(expected("a float", other, value.span).into()),
         }
     }
@@ -275,6 +765,83 @@ impl<'de> Deserialize<'de> for f64 {
     }
 }
 
+/// A number that can be written as either an integer or a float, eg. so that
+/// `timeout = 5` is accepted for a float
+///
+/// Integers are converted to floats, and floats to integers, only if the
+/// conversion is lossless, otherwise an [`ErrorKind::OutOfRange`] error is
+/// returned, eg. for `1.5` or `1e100` as an `i32`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct Lenient<T>(pub T);
+
+macro_rules! lenient_integer {
+    ($num:ty) => {
+        impl<'de> Deserialize<'de> for Lenient<$num> {
+            fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+                match value.take() {
+                    // Casting saturates, so out of range floats also fail the
+                    // conversion from i128
+                    ValueInner::Float(f) if f.fract() == 0.0 => {
+                        <$num>::try_from(f as i128).ok().map(Self).ok_or_else(|| {
+                            Error::from((ErrorKind::OutOfRange(stringify!($num)), value.span))
+                                .into()
+                        })
+                    }
+                    ValueInner::Float(_) => Err(Error::from((
+                        ErrorKind::OutOfRange(stringify!($num)),
+                        value.span,
+                    ))
+                    .into()),
+                    other => {
+                        value.set(other);
+                        <$num>::deserialize(value).map(Self)
+                    }
+                }
+            }
+        }
+    };
+}
+
+lenient_integer!(u8);
+lenient_integer!(u16);
+lenient_integer!(u32);
+lenient_integer!(u64);
+lenient_integer!(i8);
+lenient_integer!(i16);
+lenient_integer!(i32);
+lenient_integer!(i64);
+lenient_integer!(usize);
+lenient_integer!(isize);
+
+macro_rules! lenient_float {
+    ($num:ty) => {
+        impl<'de> Deserialize<'de> for Lenient<$num> {
+            fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+                match value.take() {
+                    ValueInner::Integer(i) => {
+                        let f = i as $num;
+                        if f as i128 == i128::from(i) {
+                            Ok(Self(f))
+                        } else {
+                            Err(
+                                Error::from((ErrorKind::OutOfRange(stringify!($num)), value.span))
+                                    .into(),
+                            )
+                        }
+                    }
+                    other => {
+                        value.set(other);
+                        <$num>::deserialize(value).map(Self)
+                    }
+                }
+            }
+        }
+    };
+}
+
+lenient_float!(f32);
+lenient_float!(f64);
+
 impl<'de, T> Deserialize<'de> for Vec<T>
 where
     T: Deserialize<'de>,
@@ -283,14 +850,16 @@ where
         match value.take() {
             ValueInner::Array(arr) => {
                 let mut errors = Vec::new();
+                let mut warnings = Vec::new();
                 let mut s = Vec::new();
-                for mut v in arr {
-                    match T::deserialize(&mut v) {
+                for (i, mut v) in arr.into_iter().enumerate() {
+                    match deserialize_at(&mut v, || PathSegment::Index(i), &mut warnings) {
                         Ok(v) => s.push(v),
                         Err(mut err) => errors.append(&mut err.errors),
                     }
                 }
 
+                value.add_warnings(warnings);
                 if errors.is_empty() {
                     Ok(s)
                 } else {
@@ -301,3 +870,719 @@ where
         }
     }
 }
+
+impl<'de, T> Deserialize<'de> for Option<T>
+where
+    T: Deserialize<'de>,
+{
+    /// Toml has no null, so this is always `Some` if the value can be
+    /// deserialized, use [`TableHelper::optional`] for keys that may not exist
+    #[inline]
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        T::deserialize(value).map(Some)
+    }
+}
+
+macro_rules! pointer {
+    ($ptr:ident) => {
+        impl<'de, T> Deserialize<'de> for $ptr<T>
+        where
+            T: Deserialize<'de>,
+        {
+            #[inline]
+            fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+                T::deserialize(value).map($ptr::new)
+            }
+        }
+    };
+}
+
+pointer!(Box);
+pointer!(Rc);
+pointer!(Arc);
+
+/// Takes the elements of an array that must have exactly `len` elements
+fn take_array<'de>(value: &mut Value<'de>, len: usize) -> Result<Vec<Value<'de>>, DeserError> {
+    match value.take() {
+        ValueInner::Array(arr) if arr.len() == len => Ok(arr),
+        ValueInner::Array(arr) => Err(Error::from((
+            ErrorKind::Custom(
+                format!("expected an array of {len} elements, found {}", arr.len()).into(),
+            ),
+            value.span,
+        ))
+        .into()),
+        other => Err(expected("an array", other, value.span).into()),
+    }
+}
+
+macro_rules! tuple {
+    ($len:literal => $($ty:ident $var:ident)+) => {
+        impl<'de, $($ty),+> Deserialize<'de> for ($($ty,)+)
+        where
+            $($ty: Deserialize<'de>,)+
+        {
+            fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+                let mut arr = take_array(value, $len)?.into_iter().enumerate();
+                let mut errors = Vec::new();
+                let mut warnings = Vec::new();
+                $(
+                    let $var = arr.next().and_then(|(i, mut v)| {
+                        deserialize_at::<$ty>(&mut v, || PathSegment::Index(i), &mut warnings)
+                            .map_err(|mut err| errors.append(&mut err.errors))
+                            .ok()
+                    });
+                )+
+
+                value.add_warnings(warnings);
+                if errors.is_empty() {
+                    Ok(($($var.unwrap(),)+))
+                } else {
+                    Err(DeserError { errors })
+                }
+            }
+        }
+    };
+}
+
+tuple!(1 => T0 t0);
+tuple!(2 => T0 t0 T1 t1);
+tuple!(3 => T0 t0 T1 t1 T2 t2);
+tuple!(4 => T0 t0 T1 t1 T2 t2 T3 t3);
+tuple!(5 => T0 t0 T1 t1 T2 t2 T3 t3 T4 t4);
+tuple!(6 => T0 t0 T1 t1 T2 t2 T3 t3 T4 t4 T5 t5);
+tuple!(7 => T0 t0 T1 t1 T2 t2 T3 t3 T4 t4 T5 t5 T6 t6);
+tuple!(8 => T0 t0 T1 t1 T2 t2 T3 t3 T4 t4 T5 t5 T6 t6 T7 t7);
+tuple!(9 => T0 t0 T1 t1 T2 t2 T3 t3 T4 t4 T5 t5 T6 t6 T7 t7 T8 t8);
+tuple!(10 => T0 t0 T1 t1 T2 t2 T3 t3 T4 t4 T5 t5 T6 t6 T7 t7 T8 t8 T9 t9);
+tuple!(11 => T0 t0 T1 t1 T2 t2 T3 t3 T4 t4 T5 t5 T6 t6 T7 t7 T8 t8 T9 t9 T10 t10);
+tuple!(12 => T0 t0 T1 t1 T2 t2 T3 t3 T4 t4 T5 t5 T6 t6 T7 t7 T8 t8 T9 t9 T10 t10 T11 t11);
+
+impl<'de, T, const N: usize> Deserialize<'de> for [T; N]
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let arr = take_array(value, N)?;
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let mut s = Vec::with_capacity(N);
+        for (i, mut v) in arr.into_iter().enumerate() {
+            match deserialize_at(&mut v, || PathSegment::Index(i), &mut warnings) {
+                Ok(v) => s.push(v),
+                Err(mut err) => errors.append(&mut err.errors),
+            }
+        }
+
+        value.add_warnings(warnings);
+        if errors.is_empty() {
+            Ok(s.try_into()
+                .unwrap_or_else(|_| unreachable!("the length was checked")))
+        } else {
+            Err(DeserError { errors })
+        }
+    }
+}
+
+impl<'de> DeserializeKey<'de> for value::Key<'de> {
+    #[inline]
+    fn deserialize_key(key: value::Key<'de>) -> Result<Self, DeserError> {
+        Ok(key)
+    }
+}
+
+impl<'de> DeserializeKey<'de> for String {
+    #[inline]
+    fn deserialize_key(key: value::Key<'de>) -> Result<Self, DeserError> {
+        Ok(key.name.into())
+    }
+}
+
+impl<'de: 'a, 'a> DeserializeKey<'de> for Cow<'a, str> {
+    #[inline]
+    fn deserialize_key(key: value::Key<'de>) -> Result<Self, DeserError> {
+        Ok(key.name)
+    }
+}
+
+impl<'de, K> DeserializeKey<'de> for Spanned<K>
+where
+    K: DeserializeKey<'de>,
+{
+    #[inline]
+    fn deserialize_key(key: value::Key<'de>) -> Result<Self, DeserError> {
+        let span = key.span;
+        K::deserialize_key(key).map(|value| Spanned { value, span })
+    }
+}
+
+/// Deserializes each key and value of a table, accumulating errors, `insert`
+/// returns the span of the first instance of a key if it is a duplicate, as
+/// different keys in the table can deserialize to the same key
+fn deserialize_map<'de, K, V>(
+    value: &mut Value<'de>,
+    mut insert: impl FnMut(K, V, Span) -> Option<Span>,
+) -> Result<(), DeserError>
+where
+    K: DeserializeKey<'de>,
+    V: Deserialize<'de>,
+{
+    let table = match value.take() {
+        ValueInner::Table(table) => table,
+        other => return Err(expected("a table", other, value.span).into()),
+    };
+
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for (key, mut v) in table {
+        let name = key.name.clone();
+        let span = key.span;
+        let segment = || PathSegment::Key(name.as_ref().into());
+        let key = K::deserialize_key(key).map_err(|mut err| {
+            err.prepend_path(segment());
+            errors.append(&mut err.errors);
+        });
+        let v = deserialize_at(&mut v, segment, &mut warnings)
+            .map_err(|mut err| errors.append(&mut err.errors));
+
+        if let (Ok(key), Ok(v)) = (key, v) {
+            if let Some(first) = insert(key, v, span) {
+                let mut err = Error::from((
+                    ErrorKind::DuplicateKey {
+                        key: name.as_ref().into(),
+                        first,
+                    },
+                    span,
+                ));
+                err.path.prepend(segment());
+                errors.push(err);
+            }
+        }
+    }
+
+    value.add_warnings(warnings);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(DeserError { errors })
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for BTreeMap<K, V>
+where
+    K: DeserializeKey<'de> + Ord,
+    V: Deserialize<'de>,
+{
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut map = BTreeMap::<K, (V, Span)>::new();
+        deserialize_map(value, |k, v, span| match map.entry(k) {
+            btree_map::Entry::Occupied(first) => Some(first.get().1),
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert((v, span));
+                None
+            }
+        })?;
+        Ok(map.into_iter().map(|(k, (v, _))| (k, v)).collect())
+    }
+}
+
+impl<'de, K, V, S> Deserialize<'de> for HashMap<K, V, S>
+where
+    K: DeserializeKey<'de> + Hash + Eq,
+    V: Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut map = HashMap::<K, (V, Span), S>::with_hasher(S::default());
+        deserialize_map(value, |k, v, span| match map.entry(k) {
+            hash_map::Entry::Occupied(first) => Some(first.get().1),
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert((v, span));
+                None
+            }
+        })?;
+        Ok(map.into_iter().map(|(k, (v, _))| (k, v)).collect())
+    }
+}
+
+/// Deserializes each element of an array, accumulating errors, `insert`
+/// returns the span of the first instance of an element if it is a duplicate
+fn deserialize_set<'de, T>(
+    value: &mut Value<'de>,
+    mut insert: impl FnMut(T, Span) -> Option<Span>,
+) -> Result<(), DeserError>
+where
+    T: Deserialize<'de>,
+{
+    let arr = match value.take() {
+        ValueInner::Array(arr) => arr,
+        other => return Err(expected("an array", other, value.span).into()),
+    };
+
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for (i, mut v) in arr.into_iter().enumerate() {
+        let span = v.span;
+        match deserialize_at(&mut v, || PathSegment::Index(i), &mut warnings) {
+            Ok(v) => {
+                if let Some(first) = insert(v, span) {
+                    let mut err = Error::from((ErrorKind::DuplicateValue { first }, span));
+                    err.path.prepend(PathSegment::Index(i));
+                    errors.push(err);
+                }
+            }
+            Err(mut err) => errors.append(&mut err.errors),
+        }
+    }
+
+    value.add_warnings(warnings);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(DeserError { errors })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for BTreeSet<T>
+where
+    T: Deserialize<'de> + Ord,
+{
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut set = BTreeMap::new();
+        deserialize_set(value, |v, span| match set.entry(v) {
+            btree_map::Entry::Occupied(first) => Some(*first.get()),
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(span);
+                None
+            }
+        })?;
+        Ok(set.into_keys().collect())
+    }
+}
+
+impl<'de, T, S> Deserialize<'de> for HashSet<T, S>
+where
+    T: Deserialize<'de> + Hash + Eq,
+    S: BuildHasher + Default,
+{
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut set = HashMap::<T, Span, S>::with_hasher(S::default());
+        deserialize_set(value, |v, span| match set.entry(v) {
+            hash_map::Entry::Occupied(first) => Some(*first.get()),
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(span);
+                None
+            }
+        })?;
+        Ok(set.into_keys().collect())
+    }
+}
+
+impl<'de> Deserialize<'de> for char {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let s = value.take_string(Some("a character"))?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            (None, _) => {
+                Err(invalid_string("a character", "the string is empty", value.span).into())
+            }
+            (Some(c), Some(_)) => Err(invalid_string(
+                "a character",
+                "found more than one character",
+                sub_span(value.span, &s, c.len_utf8()..s.len()),
+            )
+            .into()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PathBuf {
+    #[inline]
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let s = value.take_string(Some("a path"))?;
+        Ok(s.into_owned().into())
+    }
+}
+
+/// Deserializes a [`std::net`] address type from a string with its `FromStr`
+/// implementation
+macro_rules! addr_from_str {
+    ($ty:ty, $expected:literal) => {
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+                let s = value.take_string(Some($expected))?;
+                s.parse().map_err(|err: std::net::AddrParseError| {
+                    invalid_string($expected, err.to_string(), value.span).into()
+                })
+            }
+        }
+    };
+}
+
+addr_from_str!(IpAddr, "an IP address");
+addr_from_str!(Ipv4Addr, "an IPv4 address");
+addr_from_str!(Ipv6Addr, "an IPv6 address");
+addr_from_str!(SocketAddr, "a socket address");
+addr_from_str!(SocketAddrV4, "an IPv4 socket address");
+addr_from_str!(SocketAddrV6, "an IPv6 socket address");
+
+macro_rules! non_zero {
+    ($nz:ident, $num:ty) => {
+        impl<'de> Deserialize<'de> for num::$nz {
+            fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+                let span = value.span;
+                let n = <$num>::deserialize(value)?;
+                Self::new(n).ok_or_else(|| {
+                    Error::from((ErrorKind::OutOfRange(stringify!($nz)), span)).into()
+                })
+            }
+        }
+    };
+}
+
+non_zero!(NonZeroU8, u8);
+non_zero!(NonZeroU16, u16);
+non_zero!(NonZeroU32, u32);
+non_zero!(NonZeroU64, u64);
+non_zero!(NonZeroUsize, usize);
+non_zero!(NonZeroI8, i8);
+non_zero!(NonZeroI16, i16);
+non_zero!(NonZeroI32, i32);
+non_zero!(NonZeroI64, i64);
+non_zero!(NonZeroIsize, isize);
+
+/// Creates an [`ErrorKind::InvalidString`]
+#[inline]
+fn invalid_string(
+    expected: &'static str,
+    reason: impl Into<Cow<'static, str>>,
+    span: Span,
+) -> Error {
+    (
+        ErrorKind::InvalidString {
+            expected,
+            reason: reason.into(),
+        },
+        span,
+    )
+        .into()
+}
+
+/// Gets the span of the byte range in a string value, or the span of the
+/// entire string if the string's contents differ from its source, eg. due to
+/// escapes
+fn sub_span(span: Span, s: &str, range: Range<usize>) -> Span {
+    if span.end - span.start == s.len() {
+        Span::new(span.start + range.start, span.start + range.end)
+    } else {
+        span
+    }
+}
+
+/// Splits a number, which may have a fractional part, off the start of the
+/// string, returning the integer and fraction digits, and the remainder
+fn split_number(s: &str) -> (&str, &str, &str) {
+    let int_len = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (int, rest) = s.split_at(int_len);
+    let Some(rest) = rest.strip_prefix('.') else {
+        return (int, "", rest);
+    };
+    let frac_len = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (frac, rest) = rest.split_at(frac_len);
+    (int, frac, rest)
+}
+
+/// Multiplies a number with a fractional part by a unit, truncating any
+/// remaining fraction, or returns `None` on overflow
+fn scale(int: &str, frac: &str, unit: u128) -> Option<u128> {
+    let int: u128 = if int.is_empty() { 0 } else { int.parse().ok()? };
+    let mut total = int.checked_mul(unit)?;
+
+    // Every unit is less than 10^18, so any further digits would change the
+    // result by less than one
+    let frac = &frac[..frac.len().min(18)];
+    if !frac.is_empty() {
+        let digits: u128 = frac.parse().ok()?;
+        total = total.checked_add(digits.checked_mul(unit)? / 10u128.pow(frac.len() as u32))?;
+    }
+    Some(total)
+}
+
+/// A [`Duration`] written as a human readable string, eg. `"30s"`,
+/// `"1h 30m"`, or `"1.5ms"`
+///
+/// A duration is one or more numbers, each followed by one of the units `ns`,
+/// `us` (or `µs`), `ms`, `s`, `m`, `h`, or `d`, which are summed together.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HumanDuration(pub Duration);
+
+impl HumanDuration {
+    /// Parses a duration, returning the range of the string that is invalid
+    /// and the reason if it fails
+    fn parse(s: &str) -> Result<Duration, (Range<usize>, &'static str)> {
+        const UNITS: &[(&str, u128)] = &[
+            ("ns", 1),
+            ("us", 1_000),
+            ("µs", 1_000),
+            ("ms", 1_000_000),
+            ("s", 1_000_000_000),
+            ("m", 60 * 1_000_000_000),
+            ("h", 60 * 60 * 1_000_000_000),
+            ("d", 24 * 60 * 60 * 1_000_000_000),
+        ];
+
+        if s.trim().is_empty() {
+            return Err((0..s.len(), "the duration is empty"));
+        }
+
+        let mut nanos = 0u128;
+        let mut rest = s.trim_start();
+        while !rest.is_empty() {
+            let start = s.len() - rest.len();
+            let (int, frac, after) = split_number(rest);
+            if int.is_empty() && frac.is_empty() {
+                let len = rest.chars().next().map_or(0, char::len_utf8);
+                return Err((start..start + len, "expected a number"));
+            }
+
+            let unit_len = after
+                .find(|c: char| !c.is_alphabetic())
+                .unwrap_or(after.len());
+            let unit = &after[..unit_len];
+            let end = s.len() - after.len() + unit_len;
+            let Some((_, unit)) = UNITS.iter().find(|(name, _)| *name == unit) else {
+                return Err(if unit.is_empty() {
+                    (start..end, "the number is missing a unit, eg. `s`")
+                } else {
+                    (
+                        end - unit_len..end,
+                        "expected one of the units `ns`, `us`, `ms`, `s`, `m`, `h`, or `d`",
+                    )
+                });
+            };
+
+            nanos = scale(int, frac, *unit)
+                .and_then(|n| nanos.checked_add(n))
+                .ok_or((start..end, "the duration is too large"))?;
+            rest = after[unit_len..].trim_start();
+        }
+
+        let secs = u64::try_from(nanos / 1_000_000_000)
+            .ok()
+            .ok_or((0..s.len(), "the duration is too large"))?;
+        Ok(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+    }
+}
+
+impl<'de> Deserialize<'de> for HumanDuration {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let s = value.take_string(Some("a duration string"))?;
+        Self::parse(&s).map(Self).map_err(|(range, reason)| {
+            invalid_string("a duration", reason, sub_span(value.span, &s, range)).into()
+        })
+    }
+}
+
+impl From<HumanDuration> for Duration {
+    #[inline]
+    fn from(d: HumanDuration) -> Self {
+        d.0
+    }
+}
+
+/// A number of bytes written as a human readable string, eg. `"10MiB"` or
+/// `"1.5 GB"`, or as an integer
+///
+/// The units are `B`, the decimal units `kB`, `MB`, `GB`, `TB`, and `PB`, and
+/// the binary units `KiB`, `MiB`, `GiB`, `TiB`, and `PiB`, matched case
+/// insensitively. A number without a unit is a number of bytes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    /// Parses a byte size, returning the range of the string that is invalid
+    /// and the reason if it fails
+    fn parse(s: &str) -> Result<u64, (Range<usize>, &'static str)> {
+        const UNITS: &[(&str, u128)] = &[
+            ("", 1),
+            ("b", 1),
+            ("kb", 1_000),
+            ("mb", 1_000_000),
+            ("gb", 1_000_000_000),
+            ("tb", 1_000_000_000_000),
+            ("pb", 1_000_000_000_000_000),
+            ("kib", 1 << 10),
+            ("mib", 1 << 20),
+            ("gib", 1 << 30),
+            ("tib", 1 << 40),
+            ("pib", 1 << 50),
+        ];
+
+        let trimmed = s.trim_start();
+        let start = s.len() - trimmed.len();
+        let (int, frac, after) = split_number(trimmed);
+        if int.is_empty() && frac.is_empty() {
+            let len = trimmed.chars().next().map_or(0, char::len_utf8);
+            return Err((start..start + len, "expected a number"));
+        }
+
+        let unit = after.trim_start();
+        let unit_start = s.len() - unit.len();
+        let unit_len = unit
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(unit.len());
+        let trailing = unit[unit_len..].trim();
+        if !trailing.is_empty() {
+            let trailing_start = s.len() - unit[unit_len..].trim_start().len();
+            return Err((
+                trailing_start..trailing_start + trailing.len(),
+                "unexpected characters",
+            ));
+        }
+
+        let unit = &unit[..unit_len];
+        let Some((_, scale_by)) = UNITS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(unit))
+        else {
+            return Err((
+                unit_start..unit_start + unit_len,
+                "expected a unit such as `B`, `kB`, `MB`, `KiB`, or `MiB`",
+            ));
+        };
+
+        scale(int, frac, *scale_by)
+            .and_then(|n| u64::try_from(n).ok())
+            .ok_or((start..unit_start + unit_len, "the size is too large"))
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        match value.take() {
+            ValueInner::Integer(i) => u64::try_from(i)
+                .ok()
+                .map(Self)
+                .ok_or_else(|| Error::from((ErrorKind::OutOfRange("ByteSize"), value.span)).into()),
+            ValueInner::String(s) => Self::parse(&s).map(Self).map_err(|(range, reason)| {
+                invalid_string("a byte size", reason, sub_span(value.span, &s, range)).into()
+            }),
+            other => Err(expected("a byte size string or integer", other, value.span).into()),
+        }
+    }
+}
+
+/// Deserialization of [`semver`](https://docs.rs/semver) types
+#[cfg(feature = "semver")]
+mod impl_semver {
+    use super::*;
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "semver")))]
+    impl<'de> Deserialize<'de> for semver::Version {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            let s = value.take_string(Some("a semver version"))?;
+            Self::parse(&s).map_err(|err| {
+                invalid_string("a semver version", err.to_string(), value.span).into()
+            })
+        }
+    }
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "semver")))]
+    impl<'de> Deserialize<'de> for semver::VersionReq {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            let s = value.take_string(Some("a semver version requirement"))?;
+            Self::parse(&s).map_err(|err| {
+                invalid_string("a semver version requirement", err.to_string(), value.span).into()
+            })
+        }
+    }
+}
+
+/// Deserialization of [`url`](https://docs.rs/url) types
+#[cfg(feature = "url")]
+mod impl_url {
+    use super::*;
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "url")))]
+    impl<'de> Deserialize<'de> for url::Url {
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            let s = value.take_string(Some("a url"))?;
+            Self::parse(&s)
+                .map_err(|err| invalid_string("a url", err.to_string(), value.span).into())
+        }
+    }
+}
+
+/// Deserialization of [`regex`](https://docs.rs/regex) types
+#[cfg(feature = "regex")]
+mod impl_regex {
+    use super::*;
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "regex")))]
+    impl<'de> Deserialize<'de> for regex::Regex {
+        /// Syntax errors are reported at the part of the pattern that is
+        /// invalid, rather than the entire string
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            let s = value.take_string(Some("a regex"))?;
+            Self::new(&s).map_err(|err| {
+                // The regex error only has the position of a syntax error in
+                // its message, so parse again to get it
+                let syntax = match regex_syntax::Parser::new().parse(&s) {
+                    Err(regex_syntax::Error::Parse(err)) => {
+                        Some((*err.span(), err.kind().to_string()))
+                    }
+                    Err(regex_syntax::Error::Translate(err)) => {
+                        Some((*err.span(), err.kind().to_string()))
+                    }
+                    _ => None,
+                };
+
+                match syntax {
+                    Some((span, reason)) => invalid_string(
+                        "a regex",
+                        reason,
+                        sub_span(value.span, &s, span.start.offset..span.end.offset),
+                    ),
+                    None => invalid_string("a regex", err.to_string(), value.span),
+                }
+                .into()
+            })
+        }
+    }
+}
+
+/// Deserialization of [`globset`](https://docs.rs/globset) types
+#[cfg(feature = "globset")]
+mod impl_globset {
+    use super::*;
+    use globset::ErrorKind as GlobError;
+
+    #[cfg_attr(docsrs, doc(cfg(feature = "globset")))]
+    impl<'de> Deserialize<'de> for globset::Glob {
+        /// Errors are reported at the part of the glob that is invalid, where
+        /// it can be determined, rather than the entire string
+        fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+            let s = value.take_string(Some("a glob"))?;
+            Self::new(&s).map_err(|err| {
+                // The glob error doesn't have a position, so locate it from the
+                // kind of error
+                let range = match err.kind() {
+                    GlobError::InvalidRange(start, end) => s
+                        .find(&format!("{start}-{end}"))
+                        .map(|i| i..i + start.len_utf8() + 1 + end.len_utf8()),
+                    GlobError::UnclosedClass => s.rfind('[').map(|i| i..s.len()),
+                    GlobError::UnclosedAlternates => s.rfind('{').map(|i| i..s.len()),
+                    GlobError::UnopenedAlternates => s.find('}').map(|i| i..i + 1),
+                    GlobError::DanglingEscape => Some(s.len() - 1..s.len()),
+                    _ => None,
+                };
+
+                let span = range.map_or(value.span, |range| sub_span(value.span, &s, range));
+                invalid_string("a glob", err.kind().to_string(), span).into()
+            })
+        }
+    }
+}
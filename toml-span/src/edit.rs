@@ -0,0 +1,1155 @@
+//! Provides the [`Editor`], which modifies a toml document while keeping all
+//! of the bytes that were not edited, including comments and whitespace,
+//! exactly as they were
+
+use crate::{
+    Span,
+    ser::{self, Config},
+    tokens::{Token, Tokenizer},
+    value::{Key, Value, ValueInner, parse_index},
+};
+use std::{borrow::Cow, fmt, ops::Range};
+
+/// An error that can occur when editing a document
+#[derive(Debug, Clone)]
+pub enum EditError {
+    /// The pointer does not point to a value in the document
+    NotFound(String),
+    /// The key being inserted already exists in the table
+    KeyExists {
+        /// The pointer to the table
+        pointer: String,
+        /// The key that already exists
+        key: String,
+    },
+    /// The value at the pointer is not the kind of value the edit requires
+    Wanted {
+        /// The pointer to the value
+        pointer: String,
+        /// The kind of value that was expected
+        expected: &'static str,
+        /// The kind of value that was found
+        found: &'static str,
+    },
+    /// The edit cannot be made without reformatting the document
+    Unsupported {
+        /// The pointer to the value
+        pointer: String,
+        /// The reason the edit is not supported
+        reason: &'static str,
+    },
+    /// The raw toml passed to [`Editor::set_raw`] is not a valid value
+    InvalidRaw(crate::Error),
+    /// Two edits modify the same part of the document
+    Overlapping {
+        /// The part of the original document modified by the first edit
+        first: Span,
+        /// The part of the original document modified by the second edit
+        second: Span,
+    },
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(pointer) => write!(f, "no value found at '{pointer}'"),
+            Self::KeyExists { pointer, key } => {
+                write!(f, "key '{key}' already exists in table '{pointer}'")
+            }
+            Self::Wanted {
+                pointer,
+                expected,
+                found,
+            } => write!(f, "expected {expected} at '{pointer}', found {found}"),
+            Self::Unsupported { pointer, reason } => {
+                write!(f, "unable to edit '{pointer}': {reason}")
+            }
+            Self::InvalidRaw(err) => write!(f, "invalid raw toml value: {err}"),
+            Self::Overlapping { first, second } => write!(
+                f,
+                "edit of {}..{} overlaps edit of {}..{}",
+                second.start, second.end, first.start, first.end
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// Edits a toml document using the spans of its parsed [`Value`]
+///
+/// Edits are addressed with the same pointer syntax as [`Value::pointer`] and
+/// always refer to the original document, regardless of the edits that have
+/// been made before them. They are only applied when [`Editor::finish`] is
+/// called.
+///
+/// ```rust
+/// use toml_span::{edit::Editor, value::{Value, ValueInner}};
+///
+/// let toml = "# Crates we don't want\n[bans]\ndeny = [\n  'openssl', # use rustls\n  'git2',\n]\n";
+/// let value = toml_span::parse(toml).unwrap();
+///
+/// let mut editor = Editor::new(toml, &value);
+/// editor.remove("/bans/deny/0").unwrap();
+/// editor.push("/bans/deny", &Value::new(ValueInner::String("libssh2-sys".into()))).unwrap();
+/// editor.insert("/bans", "multiple-versions", &Value::new(ValueInner::String("deny".into()))).unwrap();
+///
+/// assert_eq!(
+///     editor.finish().unwrap(),
+///     "# Crates we don't want\n[bans]\ndeny = [\n  'git2',\n  \"libssh2-sys\",\n]\nmultiple-versions = \"deny\"\n",
+/// );
+/// ```
+pub struct Editor<'e> {
+    src: &'e str,
+    root: &'e Value<'e>,
+    config: Config,
+    headers: Vec<HeaderLine<'e>>,
+    edits: Vec<Edit>,
+    /// New items for tables and arrays, grouped by the pointer of the
+    /// container, so that they can be emitted together
+    appends: Vec<(String, Vec<String>)>,
+    /// The indices of the elements removed from inline tables and arrays,
+    /// grouped by the pointer of the container
+    list_removals: Vec<(String, Vec<usize>)>,
+}
+
+/// A single replacement of a range of the original document
+struct Edit {
+    range: Range<usize>,
+    text: String,
+}
+
+/// A `[table]` or `[[array]]` header in the document
+struct HeaderLine<'e> {
+    path: Vec<Cow<'e, str>>,
+    array: bool,
+    /// The start of the opening bracket
+    start: usize,
+    /// The end of the closing bracket
+    end: usize,
+}
+
+/// How a table, or array, is defined in the document
+#[derive(Copy, Clone)]
+enum Def {
+    /// The root table
+    Root,
+    /// An inline table or array
+    Inline,
+    /// A table with its own header, with the index of the header
+    Header(usize),
+    /// An array of tables
+    ArrayOfTables,
+    /// A table defined by dotted keys, eg. `a.b = 1` defines the table `a`
+    Dotted,
+    /// A table only defined by the headers of its subtables, eg. `[a.b]`
+    /// defines the table `a`
+    Implicit,
+    /// A table defined by dotted keys within an inline table, eg.
+    /// `t = { a.b = 1 }` defines the table `a`
+    InlineDotted,
+    /// A value that isn't a table or array
+    Scalar,
+}
+
+/// A value in the document, along with the information needed to locate it
+#[derive(Clone)]
+struct Node<'e> {
+    value: &'e Value<'e>,
+    key: Option<&'e Key<'e>>,
+    /// The keys of the tables leading to this value, excluding array indices,
+    /// as they would be written in a table header
+    path: Vec<&'e str>,
+    /// The part of the document that can contain the headers of this value,
+    /// this is the entire document unless the value is part of an array of
+    /// tables, in which case it is limited to the part of the document that
+    /// belongs to the array element
+    scope: Range<usize>,
+    /// True if the value is nested in an inline table or array
+    in_inline: bool,
+}
+
+impl<'e> Editor<'e> {
+    /// Creates an editor for the document, values are serialized with the
+    /// default [`Config`]
+    ///
+    /// The value must have been parsed from the same source document
+    #[inline]
+    pub fn new(src: &'e str, root: &'e Value<'e>) -> Self {
+        Self::with_config(src, root, Config::default())
+    }
+
+    /// Creates an editor for the document, values are serialized with the
+    /// specified [`Config`]
+    ///
+    /// The value must have been parsed from the same source document
+    pub fn with_config(src: &'e str, root: &'e Value<'e>, config: Config) -> Self {
+        Self {
+            src,
+            root,
+            config,
+            headers: scan_headers(src),
+            edits: Vec::new(),
+            appends: Vec::new(),
+            list_removals: Vec::new(),
+        }
+    }
+
+    /// Replaces the value at the pointer with the specified value
+    ///
+    /// Only values that are part of a `key = value` pair or an array can be
+    /// replaced
+    pub fn set(&mut self, pointer: &str, value: &Value<'_>) -> Result<(), EditError> {
+        let text = ser::value_to_string(value, &self.config);
+        self.replace(pointer, text)
+    }
+
+    /// Replaces the value at the pointer with raw toml, allowing complete
+    /// control over the formatting of the value
+    ///
+    /// The raw toml must be a single valid toml value, eg. `"a string"` or
+    /// `[1, 2]`, optionally surrounded by whitespace, but not followed by a
+    /// comment or any other toml
+    pub fn set_raw(&mut self, pointer: &str, raw: &str) -> Result<(), EditError> {
+        const PREFIX: &str = "v = ";
+
+        let doc = format!("{PREFIX}{raw}");
+        let parsed = crate::parse(&doc).map_err(EditError::InvalidRaw)?;
+
+        // Eg. `3\nc = 4` is a valid document, but would add another key, so
+        // check that nothing but the value is present. The span of a string
+        // doesn't include its quotes, so they are also allowed
+        let is_trivia = |c: char| c.is_whitespace() || c == '"' || c == '\'';
+        let extra = match parsed
+            .as_table()
+            .filter(|table| table.len() == 1)
+            .and_then(|table| table.get("v"))
+        {
+            Some(value) => {
+                let before = &doc[PREFIX.len()..value.span.start];
+                let after = &doc[value.span.end..];
+                let start = doc.len() - after.trim_start_matches(is_trivia).len();
+                if before.chars().all(is_trivia) && start == doc.len() {
+                    None
+                } else {
+                    Some(start)
+                }
+            }
+            None => Some(PREFIX.len()),
+        };
+
+        if let Some(start) = extra {
+            return Err(EditError::InvalidRaw(crate::Error::from((
+                crate::ErrorKind::Custom("expected a single value".into()),
+                Span::new(start, doc.trim_end().len()),
+            ))));
+        }
+
+        self.replace(pointer, raw.to_owned())
+    }
+
+    /// Inserts a new key into the table at the pointer
+    ///
+    /// Keys are added after the last key of the table, or, in the case of a
+    /// table that is only implicitly defined by the headers of its subtables,
+    /// in a new table header at the end of the table's scope
+    pub fn insert(&mut self, pointer: &str, key: &str, value: &Value<'_>) -> Result<(), EditError> {
+        let node = self
+            .resolve(pointer)?
+            .pop()
+            .expect("resolve always returns the root");
+        let Some(table) = node.value.as_table() else {
+            return Err(wanted(pointer, "a table", node.value));
+        };
+
+        if table.contains_key(key) {
+            return Err(EditError::KeyExists {
+                pointer: pointer.to_owned(),
+                key: key.to_owned(),
+            });
+        }
+
+        let mut item = String::new();
+        ser::write_key(&mut item, key, self.config.strings);
+        item.push_str(" = ");
+        item.push_str(&ser::value_to_string(value, &self.config));
+
+        self.append(pointer, item);
+        Ok(())
+    }
+
+    /// Appends a value to the end of the array at the pointer
+    ///
+    /// If the array is an array of tables, the value must be a table and is
+    /// added as a new `[[header]]` element
+    pub fn push(&mut self, pointer: &str, value: &Value<'_>) -> Result<(), EditError> {
+        let node = self
+            .resolve(pointer)?
+            .pop()
+            .expect("resolve always returns the root");
+        if node.value.as_array().is_none() {
+            return Err(wanted(pointer, "an array", node.value));
+        }
+
+        let item = if let Def::ArrayOfTables = self.def(&node) {
+            let Some(table) = value.as_table() else {
+                return Err(wanted(pointer, "a table", value));
+            };
+            ser::array_table_to_string(&node.path, table, &self.config)
+        } else {
+            ser::value_to_string(value, &self.config)
+        };
+
+        self.append(pointer, item);
+        Ok(())
+    }
+
+    /// Removes the value at the pointer
+    ///
+    /// For values in a table, this removes the key as well as any comment on
+    /// the same line, and for tables with headers, the entire table
+    /// including any subtables
+    pub fn remove(&mut self, pointer: &str) -> Result<(), EditError> {
+        let mut chain = self.resolve(pointer)?;
+        let node = chain.pop().expect("resolve always returns the root");
+        let Some(parent) = chain.pop() else {
+            return Err(unsupported(pointer, "the root table cannot be removed"));
+        };
+
+        match self.def(&parent) {
+            Def::Inline => {
+                // Removing an element from an inline table or array also
+                // removes a separator, which depends on which of its siblings
+                // are also removed, so defer until all removals are known
+                let start = match node.key {
+                    Some(key) => self.key_start(key),
+                    None => self.value_range(node.value).start,
+                };
+                let index = self
+                    .siblings(&parent)
+                    .iter()
+                    .position(|r| r.start == start)
+                    .expect("node is a child of its parent");
+
+                let parent_pointer = &pointer[..pointer.rfind('/').unwrap_or_default()];
+                if let Some((_, removed)) = self
+                    .list_removals
+                    .iter_mut()
+                    .find(|(p, _)| p == parent_pointer)
+                {
+                    removed.push(index);
+                } else {
+                    self.list_removals
+                        .push((parent_pointer.to_owned(), vec![index]));
+                }
+            }
+            Def::InlineDotted => {
+                return Err(unsupported(
+                    pointer,
+                    "values of dotted keys in inline tables cannot be removed",
+                ));
+            }
+            _ => {
+                let mut ranges = Vec::new();
+                self.removal_ranges(&node, &mut ranges);
+                self.edits.extend(ranges.into_iter().map(|range| Edit {
+                    range,
+                    text: String::new(),
+                }));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies all of the edits, returning the modified document
+    pub fn finish(self) -> Result<String, EditError> {
+        let mut edits = Vec::with_capacity(self.edits.len() + self.appends.len());
+        for (pointer, removed) in &self.list_removals {
+            // Appending to a container whose elements are all removed replaces
+            // all of its contents instead
+            if self.appends.iter().any(|(p, _)| p == pointer)
+                && self.removes_all(pointer, removed)?
+            {
+                continue;
+            }
+
+            let parent = self
+                .resolve(pointer)?
+                .pop()
+                .expect("resolve always returns the root");
+            let siblings = self.siblings(&parent);
+            edits.extend(
+                remove_from_list(self.src, &siblings, removed)
+                    .into_iter()
+                    .map(|range| Edit {
+                        range,
+                        text: String::new(),
+                    }),
+            );
+        }
+        for (pointer, items) in &self.appends {
+            let removed = self
+                .list_removals
+                .iter()
+                .find(|(p, _)| p == pointer)
+                .map_or(&[][..], |(_, removed)| removed);
+            edits.push(self.append_edit(pointer, items, removed)?);
+        }
+
+        // Appends to inline containers are positioned after the last kept
+        // element, which is where the removal of the elements after it
+        // starts, so insertions are ordered before removals at the same offset
+        edits.splice(0..0, self.edits);
+        edits.sort_by_key(|e| (e.range.start, e.range.end));
+
+        let mut merged: Vec<Edit> = Vec::with_capacity(edits.len());
+        for edit in edits {
+            if let Some(prev) = merged.last_mut() {
+                if edit.range.start < prev.range.end {
+                    // Removals can overlap, eg. removing all of the elements of
+                    // an array, so just merge them
+                    if prev.text.is_empty() && edit.text.is_empty() {
+                        prev.range.end = prev.range.end.max(edit.range.end);
+                        continue;
+                    }
+
+                    return Err(EditError::Overlapping {
+                        first: prev.range.clone().into(),
+                        second: edit.range.into(),
+                    });
+                }
+            }
+
+            merged.push(edit);
+        }
+
+        let mut out = String::with_capacity(self.src.len());
+        let mut cursor = 0;
+        for edit in merged {
+            out.push_str(&self.src[cursor..edit.range.start]);
+            out.push_str(&edit.text);
+            cursor = edit.range.end;
+        }
+        out.push_str(&self.src[cursor..]);
+
+        Ok(out)
+    }
+
+    fn replace(&mut self, pointer: &str, text: String) -> Result<(), EditError> {
+        let node = self
+            .resolve(pointer)?
+            .pop()
+            .expect("resolve always returns the root");
+        match self.def(&node) {
+            Def::Inline | Def::Scalar => {}
+            Def::Root => return Err(unsupported(pointer, "the root table cannot be replaced")),
+            _ => {
+                return Err(unsupported(
+                    pointer,
+                    "only values in a `key = value` pair or array can be replaced",
+                ));
+            }
+        }
+
+        let range = self.value_range(node.value);
+        self.edits.push(Edit { range, text });
+        Ok(())
+    }
+
+    fn append(&mut self, pointer: &str, item: String) {
+        if let Some((_, items)) = self.appends.iter_mut().find(|(p, _)| p == pointer) {
+            items.push(item);
+        } else {
+            self.appends.push((pointer.to_owned(), vec![item]));
+        }
+    }
+
+    /// Resolves the pointer, returning the chain of values from the root to
+    /// the value the pointer points to
+    fn resolve(&self, pointer: &str) -> Result<Vec<Node<'e>>, EditError> {
+        let not_found = || EditError::NotFound(pointer.to_owned());
+
+        let mut chain = vec![Node {
+            value: self.root,
+            key: None,
+            path: Vec::new(),
+            scope: 0..self.src.len(),
+            in_inline: false,
+        }];
+
+        if pointer.is_empty() {
+            return Ok(chain);
+        } else if !pointer.starts_with('/') {
+            return Err(not_found());
+        }
+
+        for segment in pointer.split('/').skip(1) {
+            let parent = chain.last().expect("the root is always present");
+            let node = match parent.value.as_ref() {
+                ValueInner::Table(tab) => {
+                    let (key, value) = tab.get_key_value(segment).ok_or_else(not_found)?;
+                    self.child(parent, Some(key), value)
+                }
+                ValueInner::Array(arr) => {
+                    let index = parse_index(segment).ok_or_else(not_found)?;
+                    let value = arr.get(index).ok_or_else(not_found)?;
+                    let mut node = self.child(parent, None, value);
+
+                    // The scope of an element in an array of tables ends at the
+                    // header of the next element
+                    if let Def::ArrayOfTables = self.def(parent) {
+                        node.scope.start = value.span.start;
+                        if let Some(next) = arr.get(index + 1) {
+                            node.scope.end = self.line_start(next.span.start);
+                        }
+                    }
+
+                    node
+                }
+                _ => return Err(not_found()),
+            };
+
+            chain.push(node);
+        }
+
+        Ok(chain)
+    }
+
+    fn child(&self, parent: &Node<'e>, key: Option<&'e Key<'e>>, value: &'e Value<'e>) -> Node<'e> {
+        let mut path = parent.path.clone();
+        if let Some(key) = key {
+            path.push(key.name.as_ref());
+        }
+
+        Node {
+            value,
+            key,
+            path,
+            scope: parent.scope.clone(),
+            in_inline: parent.in_inline || matches!(self.def(parent), Def::Inline),
+        }
+    }
+
+    /// The ranges of the elements of an inline table or array, in the order
+    /// they appear in the document
+    fn siblings(&self, node: &Node<'e>) -> Vec<Range<usize>> {
+        let mut siblings: Vec<_> = match node.value.as_ref() {
+            ValueInner::Table(tab) => tab
+                .iter()
+                .map(|(k, v)| self.key_start(k)..self.extent_end(v))
+                .collect(),
+            ValueInner::Array(arr) => arr.iter().map(|v| self.value_range(v)).collect(),
+            _ => Vec::new(),
+        };
+        siblings.sort_by_key(|r| r.start);
+        siblings
+    }
+
+    fn children(&self, node: &Node<'e>) -> Vec<Node<'e>> {
+        match node.value.as_ref() {
+            ValueInner::Table(tab) => tab
+                .iter()
+                .map(|(k, v)| self.child(node, Some(k), v))
+                .collect(),
+            ValueInner::Array(arr) => {
+                let is_aot = matches!(self.def(node), Def::ArrayOfTables);
+                arr.iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        let mut child = self.child(node, None, v);
+                        if is_aot {
+                            child.scope.start = v.span.start;
+                            if let Some(next) = arr.get(i + 1) {
+                                child.scope.end = self.line_start(next.span.start);
+                            }
+                        }
+                        child
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Determines how the value is defined in the document
+    fn def(&self, node: &Node<'e>) -> Def {
+        let bytes = self.src.as_bytes();
+        let first = bytes.get(node.value.span.start).copied();
+
+        match node.value.as_ref() {
+            ValueInner::Table(tab) => {
+                if std::ptr::eq(node.value, self.root) {
+                    Def::Root
+                } else if first == Some(b'{') {
+                    Def::Inline
+                } else if node.in_inline {
+                    Def::InlineDotted
+                } else if let Some(i) = self.header(node) {
+                    Def::Header(i)
+                } else if tab.keys().any(|k| !self.in_header(k.span.start)) {
+                    Def::Dotted
+                } else {
+                    Def::Implicit
+                }
+            }
+            ValueInner::Array(arr) => {
+                // The elements of an array of tables have the span of their
+                // header, while inline tables start with a brace
+                let is_aot = arr.first().is_some_and(|v| {
+                    v.as_table().is_some() && bytes.get(v.span.start) == Some(&b'[')
+                });
+
+                if is_aot {
+                    Def::ArrayOfTables
+                } else {
+                    Def::Inline
+                }
+            }
+            _ => Def::Scalar,
+        }
+    }
+
+    /// Finds the header that defines the table
+    fn header(&self, node: &Node<'e>) -> Option<usize> {
+        if node.key.is_none() {
+            // Elements of an array of tables always start at their header
+            return self
+                .headers
+                .iter()
+                .position(|h| h.array && h.start == node.value.span.start);
+        }
+
+        self.headers.iter().position(|h| {
+            !h.array
+                && node.scope.contains(&h.start)
+                && h.path.len() == node.path.len()
+                && h.path.iter().zip(&node.path).all(|(a, b)| a == b)
+        })
+    }
+
+    #[inline]
+    fn in_header(&self, offset: usize) -> bool {
+        self.headers
+            .iter()
+            .any(|h| (h.start..h.end).contains(&offset))
+    }
+
+    /// The range of the value in the document, which, unlike the span, also
+    /// includes the quotes of strings
+    fn value_range(&self, value: &Value<'_>) -> Range<usize> {
+        let Span { start, end } = value.span;
+        let Some(s) = value.as_str() else {
+            return start..end;
+        };
+
+        let bytes = self.src.as_bytes();
+
+        // The span of an empty string points at the opening quote
+        if s.is_empty() && end == start + 1 {
+            return start..start + 2;
+        }
+
+        // Multiline strings skip a newline immediately after the delimiter
+        let mut open = start;
+        if open > 0 && bytes[open - 1] == b'\n' {
+            open -= 1;
+            if open > 0 && bytes[open - 1] == b'\r' {
+                open -= 1;
+            }
+        }
+
+        let quote = bytes[open - 1];
+        let delim = if open >= 3 && bytes[open - 3..open].iter().all(|b| *b == quote) {
+            3
+        } else {
+            1
+        };
+
+        open - delim..end + delim
+    }
+
+    /// The end of the value, including any values nested within it, which
+    /// may not be included in the span of tables defined with dotted keys
+    fn extent_end(&self, value: &Value<'_>) -> usize {
+        let end = self.value_range(value).end;
+        match value.as_ref() {
+            ValueInner::Table(tab) => tab
+                .values()
+                .map(|v| self.extent_end(v))
+                .fold(end, usize::max),
+            ValueInner::Array(arr) => arr.iter().map(|v| self.extent_end(v)).fold(end, usize::max),
+            _ => end,
+        }
+    }
+
+    /// The start of the key, including the opening quote of quoted keys
+    fn key_start(&self, key: &Key<'_>) -> usize {
+        let start = key.span.start;
+        if !key.name.is_empty()
+            && start > 0
+            && matches!(self.src.as_bytes()[start - 1], b'"' | b'\'')
+        {
+            start - 1
+        } else {
+            start
+        }
+    }
+
+    #[inline]
+    fn line_start(&self, offset: usize) -> usize {
+        self.src[..offset].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    /// The end of the line, including the newline
+    #[inline]
+    fn line_end(&self, offset: usize) -> usize {
+        self.src[offset..]
+            .find('\n')
+            .map_or(self.src.len(), |i| offset + i + 1)
+    }
+
+    /// The end of the last line in the range which contains something other
+    /// than whitespace or a comment, or the start of the range if there are
+    /// no such lines
+    fn content_end(&self, range: Range<usize>) -> usize {
+        let mut end = range.start;
+        let mut cursor = range.start;
+        while cursor < range.end {
+            let line_end = self.line_end(cursor).min(range.end);
+            let line = self.src[cursor..line_end].trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                end = line_end;
+            }
+            cursor = line_end;
+        }
+        end
+    }
+
+    /// The start of the line of the header following the specified header
+    fn next_header_start(&self, header: usize) -> usize {
+        self.headers
+            .get(header + 1)
+            .map_or(self.src.len(), |h| self.line_start(h.start))
+    }
+
+    /// The end of the last key in the table of the header, or the end of the
+    /// header line if the table is empty
+    fn section_end(&self, header: usize) -> usize {
+        let header_end = self.line_end(self.headers[header].end);
+        self.content_end(header_end..self.next_header_start(header))
+            .max(header_end)
+    }
+
+    /// Collects the ranges of the document that need to be removed to remove
+    /// the value from a table that is not an inline table
+    fn removal_ranges(&self, node: &Node<'e>, ranges: &mut Vec<Range<usize>>) {
+        match self.def(node) {
+            Def::Header(i) => {
+                let start = self.line_start(self.headers[i].start);
+                let mut end = self.section_end(i);
+
+                // Also remove the blank lines separating the table from the
+                // next one
+                let next = self.next_header_start(i);
+                while end < next && self.src[end..self.line_end(end)].trim().is_empty() {
+                    end = self.line_end(end);
+                }
+
+                ranges.push(start..end);
+                for child in self.children(node) {
+                    self.removal_ranges(&child, ranges);
+                }
+            }
+            Def::ArrayOfTables | Def::Dotted | Def::Implicit => {
+                for child in self.children(node) {
+                    self.removal_ranges(&child, ranges);
+                }
+            }
+            Def::Inline | Def::Scalar => {
+                let key = node.key.expect("values in non-inline tables have keys");
+                let start = self.line_start(self.key_start(key));
+                let end = self.line_end(self.extent_end(node.value));
+                ranges.push(start..end);
+            }
+            Def::Root | Def::InlineDotted => {
+                unreachable!(
+                    "the root table is never removed, and dotted keys in inline tables are rejected"
+                )
+            }
+        }
+    }
+
+    /// Creates the edit that adds all of the items appended to a container
+    /// True if the removed indices are all of the elements of the container at
+    /// the pointer
+    fn removes_all(&self, pointer: &str, removed: &[usize]) -> Result<bool, EditError> {
+        let node = self
+            .resolve(pointer)?
+            .pop()
+            .expect("resolve always returns the root");
+        let len = self.siblings(&node).len();
+        Ok((0..len).all(|i| removed.contains(&i)))
+    }
+
+    /// Creates the edit that adds the items to the container at the pointer,
+    /// `removed` being the indices of its elements that are removed by the
+    /// same editor, see [`Self::remove`]
+    fn append_edit(
+        &self,
+        pointer: &str,
+        items: &[String],
+        removed: &[usize],
+    ) -> Result<Edit, EditError> {
+        let node = self
+            .resolve(pointer)?
+            .pop()
+            .expect("resolve always returns the root");
+        let span = node.value.span;
+        let len = self.src.len();
+
+        let insert = |at: usize, text: String| Edit {
+            range: at..at,
+            text,
+        };
+
+        // Adds the items as lines at the specified position, adding a newline
+        // if the last line of the document doesn't have one
+        let lines = |at: usize, prefix: &str| {
+            let mut text = String::new();
+            if at == len && !self.src.is_empty() && !self.src.ends_with('\n') {
+                text.push('\n');
+            }
+            for item in items {
+                text.push_str(prefix);
+                text.push_str(item);
+                text.push('\n');
+            }
+            text
+        };
+
+        let edit = match (self.def(&node), node.value.as_ref()) {
+            (Def::Inline, ValueInner::Table(_)) => {
+                let end = self
+                    .siblings(&node)
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !removed.contains(i))
+                    .map(|(_, r)| r.end)
+                    .max();
+                match end {
+                    Some(end) => insert(end, format!(", {}", items.join(", "))),
+                    None => Edit {
+                        range: span.start + 1..span.end - 1,
+                        text: format!(" {} ", items.join(", ")),
+                    },
+                }
+            }
+            (Def::Inline, ValueInner::Array(arr)) => {
+                let last = arr
+                    .iter()
+                    .enumerate()
+                    .rfind(|(i, _)| !removed.contains(i))
+                    .map(|(_, v)| v);
+                if let Some(last) = last {
+                    let last_start = self.value_range(last).start;
+
+                    // Keep each element on its own line if the array is
+                    // already formatted that way
+                    let sep = if self.src[span.start..last_start].contains('\n') {
+                        let line_start = self.line_start(last_start);
+                        format!(",\n{}", &self.src[line_start..last_start])
+                    } else {
+                        ", ".to_owned()
+                    };
+
+                    insert(self.extent_end(last), format!("{sep}{}", items.join(&sep)))
+                } else {
+                    Edit {
+                        range: span.start + 1..span.end - 1,
+                        text: items.join(", "),
+                    }
+                }
+            }
+            (Def::Root, _) => {
+                let root_end = self
+                    .headers
+                    .first()
+                    .map_or(len, |h| self.line_start(h.start));
+
+                match self.content_end(0..root_end) {
+                    // Keep a blank line between the new keys and the first table
+                    0 if root_end < len => {
+                        let mut text = lines(root_end, "");
+                        text.push('\n');
+                        insert(root_end, text)
+                    }
+                    0 => insert(len, lines(len, "")),
+                    end => insert(end, lines(end, "")),
+                }
+            }
+            (Def::Header(i), _) => {
+                let end = self.section_end(i);
+                insert(end, lines(end, ""))
+            }
+            (Def::Dotted, ValueInner::Table(tab)) => {
+                // Reuse the dotted key prefix, and indentation, of the first key
+                let first = tab
+                    .keys()
+                    .map(|k| self.key_start(k))
+                    .min()
+                    .unwrap_or_default();
+                let prefix = &self.src[self.line_start(first)..first];
+
+                let end = self.line_end(self.extent_end(node.value));
+                insert(end, lines(end, prefix))
+            }
+            (Def::Implicit, _) => {
+                let at = node.scope.end;
+
+                let mut text = String::new();
+                if at == len {
+                    if !self.src.is_empty() && !self.src.ends_with('\n') {
+                        text.push('\n');
+                    }
+                    text.push('\n');
+                }
+
+                text.push('[');
+                for (i, key) in node.path.iter().enumerate() {
+                    if i > 0 {
+                        text.push('.');
+                    }
+                    ser::write_key(&mut text, key, self.config.strings);
+                }
+                text.push_str("]\n");
+
+                for item in items {
+                    text.push_str(item);
+                    text.push('\n');
+                }
+
+                if at != len {
+                    text.push('\n');
+                }
+
+                insert(at, text)
+            }
+            (Def::ArrayOfTables, ValueInner::Array(arr)) => {
+                let last = arr.last().expect("arrays of tables are never empty");
+                let header = self
+                    .headers
+                    .iter()
+                    .position(|h| h.start == last.span.start)
+                    .expect("the element of an array of tables has a header");
+
+                // Add the new elements after the subtables of the last element
+                let mut end_header = header;
+                while self.headers.get(end_header + 1).is_some_and(|h| {
+                    h.path.len() > node.path.len()
+                        && h.path.iter().zip(&node.path).all(|(a, b)| a == b)
+                }) {
+                    end_header += 1;
+                }
+
+                let at = self.section_end(end_header);
+                let mut text = String::new();
+                if at == len && !self.src.is_empty() && !self.src.ends_with('\n') {
+                    text.push('\n');
+                }
+                for item in items {
+                    text.push('\n');
+                    text.push_str(item);
+                }
+
+                insert(at, text)
+            }
+            (Def::InlineDotted, _) => {
+                return Err(unsupported(
+                    pointer,
+                    "keys cannot be inserted into tables defined by dotted keys in inline tables",
+                ));
+            }
+            _ => unreachable!("appends are only made to tables and arrays"),
+        };
+
+        Ok(edit)
+    }
+}
+
+/// Computes the ranges to remove for the removed elements of a list of comma
+/// separated elements, also removing the separators between them
+fn remove_from_list(src: &str, siblings: &[Range<usize>], removed: &[usize]) -> Vec<Range<usize>> {
+    if (0..siblings.len()).all(|i| removed.contains(&i)) {
+        // Remove everything from the first element to the end of the last,
+        // including a trailing comma, and if the elements were on their own
+        // lines, the lines as well
+        let (Some(first), Some(last)) = (siblings.first(), siblings.last()) else {
+            return Vec::new();
+        };
+
+        let mut start = first.start;
+        let mut end = last.end;
+
+        let rest = src[end..].trim_start_matches([' ', '\t']);
+        if let Some(rest) = rest.strip_prefix(',') {
+            end = src.len() - rest.len();
+        }
+        end = src.len() - src[end..].trim_start_matches([' ', '\t']).len();
+
+        let line_start = src[..start].rfind('\n').map_or(0, |i| i + 1);
+        if src[end..].starts_with('\n') && src[line_start..start].trim().is_empty() {
+            start = line_start;
+            end += 1;
+        }
+
+        return std::iter::once(start..end).collect();
+    }
+
+    // The removed elements at the end of the list, which are not followed by
+    // any kept element
+    let tail = (0..siblings.len())
+        .rev()
+        .take_while(|i| removed.contains(i))
+        .last();
+
+    // Remove the separator after each of the other elements, which, along
+    // with any removed siblings in between, runs up to the next kept element
+    let mut ranges: Vec<_> = removed
+        .iter()
+        .filter(|&&i| tail.map_or(true, |tail| i < tail))
+        .map(|&i| siblings[i].start..siblings[i + 1].start)
+        .collect();
+
+    if let Some(tail) = tail {
+        let kept = siblings[tail - 1].end;
+        let first = siblings[tail].start;
+        let last = siblings[siblings.len() - 1].end;
+
+        let after = skip_trivia(src, last);
+        if src[after..].starts_with(',') {
+            // The separator after the kept element becomes the trailing comma,
+            // so remove the elements along with the existing trailing comma
+            // and any comment following it on the same line
+            let rest = &src[after + 1..];
+            let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+            let end = if line.trim_start_matches([' ', '\t']).starts_with('#') {
+                after + 1 + line.trim_end_matches('\r').len()
+            } else {
+                after + 1
+            };
+            ranges.push(with_lines(src, first..end));
+        } else if src[kept..first].contains('#') {
+            // Only remove the separator itself, keeping the comments between
+            // it and the removed elements, which belong to the kept element
+            let comma = skip_trivia(src, kept);
+            ranges.push(comma..comma + 1);
+            ranges.push(with_lines(src, first..last));
+        } else {
+            ranges.push(kept..last);
+        }
+    }
+
+    ranges
+}
+
+/// The offset of the first character after any whitespace, newlines, and
+/// comments at the offset
+fn skip_trivia(src: &str, mut offset: usize) -> usize {
+    loop {
+        let rest = src[offset..].trim_start();
+        offset = src.len() - rest.len();
+        if !rest.starts_with('#') {
+            return offset;
+        }
+        offset += rest.find('\n').unwrap_or(rest.len());
+    }
+}
+
+/// Extends the range to cover the lines it is on if there is nothing else on
+/// them
+fn with_lines(src: &str, range: Range<usize>) -> Range<usize> {
+    let line_start = src[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let rest = src[range.end..].trim_start_matches([' ', '\t', '\r']);
+    if src[line_start..range.start].trim().is_empty() && rest.starts_with('\n') {
+        line_start..src.len() - rest.len() + 1
+    } else {
+        range
+    }
+}
+
+fn scan_headers(src: &str) -> Vec<HeaderLine<'_>> {
+    let mut headers = Vec::new();
+    let mut tokens = Tokenizer::new(src);
+    let mut depth = 0usize;
+    let mut line_start = true;
+
+    // The document has already been successfully parsed, so any tokenizer
+    // errors are unreachable
+    while let Ok(Some((span, token))) = tokens.step() {
+        match token {
+            Token::Newline => line_start = true,
+            Token::Whitespace(_) | Token::Comment(_) => {}
+            Token::LeftBracket if depth == 0 && line_start => {
+                let Ok(array) = tokens.eat(Token::LeftBracket) else {
+                    break;
+                };
+
+                let mut path = Vec::new();
+                loop {
+                    tokens.eat_whitespace();
+                    let Ok(key) = tokens.table_key() else {
+                        return headers;
+                    };
+                    path.push(key.name);
+                    tokens.eat_whitespace();
+                    if !matches!(tokens.eat(Token::Period), Ok(true)) {
+                        break;
+                    }
+                }
+
+                if tokens.expect(Token::RightBracket).is_err()
+                    || (array && tokens.expect(Token::RightBracket).is_err())
+                {
+                    break;
+                }
+
+                headers.push(HeaderLine {
+                    path,
+                    array,
+                    start: span.start,
+                    end: tokens.current(),
+                });
+                line_start = false;
+            }
+            Token::LeftBracket | Token::LeftBrace => {
+                depth += 1;
+                line_start = false;
+            }
+            Token::RightBracket | Token::RightBrace => {
+                depth = depth.saturating_sub(1);
+                line_start = false;
+            }
+            _ => line_start = false,
+        }
+    }
+
+    headers
+}
+
+#[inline]
+fn wanted(pointer: &str, expected: &'static str, found: &Value<'_>) -> EditError {
+    EditError::Wanted {
+        pointer: pointer.to_owned(),
+        expected,
+        found: found.as_ref().type_str(),
+    }
+}
+
+#[inline]
+fn unsupported(pointer: &str, reason: &'static str) -> EditError {
+    EditError::Unsupported {
+        pointer: pointer.to_owned(),
+        reason,
+    }
+}
@@ -1,8 +1,16 @@
-use crate::Span;
+use crate::{
+    Span,
+    span::{ColumnUnit, LineIndex},
+};
 use std::fmt::{self, Debug, Display};
 
 /// Error that can occur when deserializing TOML.
+///
+/// New fields may be added to this struct, so it is created with
+/// [`Error::new`] or from an `(ErrorKind, Span)` tuple rather than a struct
+/// literal.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct Error {
     /// The error kind
     pub kind: ErrorKind,
@@ -10,20 +18,137 @@ pub struct Error {
     ///
     /// Note some [`ErrorKind`] contain additional span information
     pub span: Span,
-    /// Line and column information, only available for errors coming from the parser
+    /// Line and column information, filled in for errors coming from the parser,
+    /// other errors can fill it in with [`Self::fill_line_info`]
     pub line_info: Option<(usize, usize)>,
+    /// The path to the value the error occurred in, filled in as the error is
+    /// returned up through [`TableHelper`](crate::de_helpers::TableHelper)
+    /// and `Vec<T>` deserialization, empty for errors coming from the parser
+    pub path: KeyPath,
+    /// Whether this is an error, or a warning that doesn't fail
+    /// deserialization, see [`TableHelper::warn`](crate::de_helpers::TableHelper::warn)
+    pub severity: Severity,
+}
+
+/// The severity of an [`Error`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Severity {
+    /// Parsing or deserialization failed
+    #[default]
+    Error,
+    /// A problem that doesn't prevent deserialization from succeeding, eg. a
+    /// deprecated field or an ignored key
+    Warning,
 }
 
 impl std::error::Error for Error {}
 
-impl From<(ErrorKind, Span)> for Error {
-    fn from((kind, span): (ErrorKind, Span)) -> Self {
+impl Error {
+    /// Creates an error of the specified kind at the span, with no line
+    /// information or path
+    #[inline]
+    pub fn new(kind: ErrorKind, span: Span) -> Self {
         Self {
             kind,
             span,
             line_info: None,
+            path: KeyPath::default(),
+            severity: Severity::Error,
         }
     }
+
+    /// Fills [`Self::line_info`] with the line and byte column of the start of
+    /// the error's span, if it was not already filled in by the parser
+    #[inline]
+    pub fn fill_line_info(&mut self, index: &LineIndex) {
+        if self.line_info.is_none() {
+            let position = index.position(self.span.start, ColumnUnit::Utf8);
+            self.line_info = Some((position.line, position.column));
+        }
+    }
+}
+
+impl From<(ErrorKind, Span)> for Error {
+    #[inline]
+    fn from((kind, span): (ErrorKind, Span)) -> Self {
+        Self::new(kind, span)
+    }
+}
+
+/// A single key or array index of a [`KeyPath`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// The key of a value in a table
+    Key(String),
+    /// The index of a value in an array
+    Index(usize),
+}
+
+/// The path of keys and array indices from the root of the document to a
+/// value, eg. `package.metadata.deny[3].name`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyPath(Vec<PathSegment>);
+
+impl KeyPath {
+    /// The segments of the path, outermost first
+    #[inline]
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    /// Returns true if the path is the root of the document
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Adds a segment to the front of the path, done as the error is returned
+    /// to the table or array that contains the value
+    #[inline]
+    pub fn prepend(&mut self, segment: PathSegment) {
+        self.0.insert(0, segment);
+    }
+}
+
+impl Display for KeyPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use std::fmt::Write as _;
+
+        for (i, segment) in self.0.iter().enumerate() {
+            let key = match segment {
+                PathSegment::Index(index) => {
+                    write!(f, "[{index}]")?;
+                    continue;
+                }
+                PathSegment::Key(key) => key,
+            };
+
+            if i > 0 {
+                f.write_char('.')?;
+            }
+
+            let bare = !key.is_empty()
+                && key
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+            if bare {
+                f.write_str(key)?;
+                continue;
+            }
+
+            f.write_char('"')?;
+            for c in key.chars() {
+                match c {
+                    '"' | '\\' => write!(f, "\\{c}")?,
+                    c if c.is_control() => write!(f, "\\u{:04X}", c as u32)?,
+                    c => f.write_char(c)?,
+                }
+            }
+            f.write_char('"')?;
+        }
+
+        Ok(())
+    }
 }
 
 /// Errors that can occur when deserializing a type.
@@ -57,10 +182,22 @@ pub enum ErrorKind {
     /// A number failed to parse.
     InvalidNumber,
 
+    /// A datetime failed to parse.
+    InvalidDatetime,
+
     /// The number in the toml file cannot be losslessly converted to the specified
     /// number type
     OutOfRange(&'static str),
 
+    /// A string could not be parsed as the type it was deserialized as, the
+    /// span of the error is the part of the string that is invalid, if known
+    InvalidString {
+        /// What the string was expected to be, eg. "an IP address"
+        expected: &'static str,
+        /// Why the string is invalid
+        reason: std::borrow::Cow<'static, str>,
+    },
+
     /// Wanted one sort of token, but found another.
     Wanted {
         /// Expected token type.
@@ -85,6 +222,12 @@ pub enum ErrorKind {
         first: Span,
     },
 
+    /// Duplicate value in an array deserialized as a set.
+    DuplicateValue {
+        /// The span where the first value is located
+        first: Span,
+    },
+
     /// A previously defined table was redefined as an array.
     RedefineAsArray,
 
@@ -125,6 +268,15 @@ pub enum ErrorKind {
         new: &'static str,
     },
 
+    /// A field was specified more than once in a table, using different
+    /// keys, eg. both its current name and a deprecated one
+    DuplicateField {
+        /// The name of the field
+        field: &'static str,
+        /// The span of the key the field was first specified with
+        first: Span,
+    },
+
     /// An unexpected value was encountered
     UnexpectedValue {
         /// The list of values that could have been used, eg. typically enum variants
@@ -132,6 +284,97 @@ pub enum ErrorKind {
         /// The actual value that was found.
         value: Option<String>,
     },
+
+    /// The document exceeded one of the [`Limits`](crate::Limits) it was
+    /// parsed with
+    LimitExceeded {
+        /// The limit that was exceeded, eg. "depth" or "array length"
+        limit: &'static str,
+        /// The maximum the limit was set to
+        max: usize,
+    },
+
+    /// Syntax was used that is not supported by the
+    /// [`TomlVersion`](crate::TomlVersion) the document was parsed as
+    UnsupportedFeature {
+        /// The feature that was used, eg. "trailing commas in inline tables"
+        feature: &'static str,
+        /// The version the document was parsed as
+        version: crate::TomlVersion,
+    },
+}
+
+impl ErrorKind {
+    /// Suggests corrections for the unexpected keys of [`Self::UnexpectedKeys`]
+    /// and the unexpected value of [`Self::UnexpectedValue`], as pairs of what
+    /// was found and the most similar of the expected keys or values
+    ///
+    /// Only close matches, eg. typos or differences in case, are suggested.
+    pub fn suggestions(&self) -> Vec<(&str, &str)> {
+        match self {
+            Self::UnexpectedKeys { keys, expected } => keys
+                .iter()
+                .filter_map(|(key, _)| {
+                    let suggestion = did_you_mean(key, expected.iter().map(String::as_str))?;
+                    Some((key.as_str(), suggestion))
+                })
+                .collect(),
+            Self::UnexpectedValue {
+                expected,
+                value: Some(value),
+            } => did_you_mean(value, expected.iter().copied())
+                .map(|suggestion| (value.as_str(), suggestion))
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Finds the candidate closest to what was found, if it is close enough to
+/// plausibly be what was intended
+fn did_you_mean<'a>(found: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let found_len = found.chars().count();
+    let max = found_len.max(3) / 3;
+
+    candidates
+        .filter_map(|candidate| {
+            let distance = if candidate.eq_ignore_ascii_case(found) {
+                0
+            } else {
+                edit_distance(found, candidate)
+            };
+            (distance <= max).then_some((distance, candidate))
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The number of single character insertions, deletions, substitutions, or
+/// adjacent transpositions needed to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<_> = a.chars().collect();
+    let b: Vec<_> = b.chars().collect();
+
+    // The rows for the prefixes of `a` ending 2, 1, and 0 characters back
+    let mut prev2 = vec![0; b.len() + 1];
+    let mut prev: Vec<_> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        cur[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                cur[j] = cur[j].min(prev2[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev[b.len()]
 }
 
 impl Display for ErrorKind {
@@ -142,6 +385,7 @@ impl Display for ErrorKind {
             Self::DottedKeyInvalidType { .. } => f.write_str("dotted-key-invalid-type"),
             Self::DuplicateKey { .. } => f.write_str("duplicate-key"),
             Self::DuplicateTable { .. } => f.write_str("duplicate-table"),
+            Self::DuplicateValue { .. } => f.write_str("duplicate-value"),
             Self::UnexpectedKeys { .. } => f.write_str("unexpected-keys"),
             Self::UnquotedString => f.write_str("unquoted-string"),
             Self::MultilineStringKey => f.write_str("multiline-string-key"),
@@ -153,11 +397,16 @@ impl Display for ErrorKind {
             Self::Unexpected(..) => f.write_str("unexpected"),
             Self::UnterminatedString => f.write_str("unterminated-string"),
             Self::InvalidNumber => f.write_str("invalid-number"),
+            Self::InvalidDatetime => f.write_str("invalid-datetime"),
             Self::OutOfRange(_) => f.write_str("out-of-range"),
+            Self::InvalidString { .. } => f.write_str("invalid-string"),
             Self::Wanted { .. } => f.write_str("wanted"),
             Self::MissingField(..) => f.write_str("missing-field"),
             Self::Deprecated { .. } => f.write_str("deprecated"),
+            Self::DuplicateField { .. } => f.write_str("duplicate-field"),
             Self::UnexpectedValue { .. } => f.write_str("unexpected-value"),
+            Self::LimitExceeded { .. } => f.write_str("limit-exceeded"),
+            Self::UnsupportedFeature { .. } => f.write_str("unsupported-feature"),
         }
     }
 }
@@ -181,6 +430,10 @@ impl fmt::Display for Escape {
 
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+
         match &self.kind {
             ErrorKind::UnexpectedEof => f.write_str("unexpected eof encountered")?,
             ErrorKind::InvalidCharInString(c) => {
@@ -201,13 +454,18 @@ impl Display for Error {
                 write!(f, "expected {expected}, found {found}")?;
             }
             ErrorKind::InvalidNumber => f.write_str("invalid number")?,
+            ErrorKind::InvalidDatetime => f.write_str("invalid datetime")?,
             ErrorKind::OutOfRange(kind) => write!(f, "out of range of '{kind}'")?,
+            ErrorKind::InvalidString { expected, reason } => {
+                write!(f, "invalid string, expected {expected}: {reason}")?;
+            }
             ErrorKind::DuplicateTable { name, .. } => {
                 write!(f, "redefinition of table `{name}`")?;
             }
             ErrorKind::DuplicateKey { key, .. } => {
                 write!(f, "duplicate key: `{key}`")?;
             }
+            ErrorKind::DuplicateValue { .. } => f.write_str("duplicate value in set")?,
             ErrorKind::RedefineAsArray => f.write_str("table redefined as array")?,
             ErrorKind::MultilineStringKey => {
                 f.write_str("multiline strings are not allowed for key")?;
@@ -227,7 +485,16 @@ impl Display for Error {
             ErrorKind::Deprecated { old, new } => {
                 write!(f, "field '{old}' is deprecated, '{new}' has replaced it")?;
             }
+            ErrorKind::DuplicateField { field, .. } => {
+                write!(f, "field '{field}' is specified more than once")?;
+            }
             ErrorKind::UnexpectedValue { expected, .. } => write!(f, "expected '{expected:?}'")?,
+            ErrorKind::LimitExceeded { limit, max } => {
+                write!(f, "exceeded the maximum {limit} of {max}")?;
+            }
+            ErrorKind::UnsupportedFeature { feature, version } => {
+                write!(f, "{feature} are not supported in TOML {version}")?;
+            }
         }
 
         Ok(())
@@ -242,16 +509,26 @@ impl Error {
         &self,
         fid: FileId,
     ) -> codespan_reporting::diagnostic::Diagnostic<FileId> {
-        let diag =
-            codespan_reporting::diagnostic::Diagnostic::error().with_code(self.kind.to_string());
+        let severity = match self.severity {
+            Severity::Error => codespan_reporting::diagnostic::Severity::Error,
+            Severity::Warning => codespan_reporting::diagnostic::Severity::Warning,
+        };
+        let diag = codespan_reporting::diagnostic::Diagnostic::new(severity)
+            .with_code(self.kind.to_string());
 
         use codespan_reporting::diagnostic::Label;
 
-        match &self.kind {
+        let diag = match &self.kind {
             ErrorKind::DuplicateKey { first, .. } => diag.with_labels(vec![
                 Label::secondary(fid, *first).with_message("first key instance"),
                 Label::primary(fid, self.span).with_message("duplicate key"),
             ]),
+            ErrorKind::DuplicateValue { first } => diag
+                .with_message("duplicate value in set")
+                .with_labels(vec![
+                    Label::secondary(fid, *first).with_message("first value instance"),
+                    Label::primary(fid, self.span).with_message("duplicate value"),
+                ]),
             ErrorKind::Unexpected(c) => diag.with_labels(vec![
                 Label::primary(fid, self.span)
                     .with_message(format!("unexpected character '{}'", Escape(*c))),
@@ -272,9 +549,17 @@ impl Error {
             ErrorKind::InvalidNumber => diag.with_labels(vec![
                 Label::primary(fid, self.span).with_message("unable to parse number"),
             ]),
+            ErrorKind::InvalidDatetime => diag.with_labels(vec![
+                Label::primary(fid, self.span).with_message("unable to parse datetime"),
+            ]),
             ErrorKind::OutOfRange(kind) => diag
                 .with_message(format!("number is out of range of '{kind}'"))
                 .with_labels(vec![Label::primary(fid, self.span)]),
+            ErrorKind::InvalidString { expected, reason } => diag
+                .with_message(format!("invalid string, expected {expected}"))
+                .with_labels(vec![
+                    Label::primary(fid, self.span).with_message(reason.as_ref()),
+                ]),
             ErrorKind::Wanted { expected, .. } => diag.with_labels(vec![
                 Label::primary(fid, self.span).with_message(format!("expected {expected}")),
             ]),
@@ -302,7 +587,15 @@ impl Error {
                 ))
                 .with_labels(
                     keys.iter()
-                        .map(|(_name, span)| Label::secondary(fid, *span))
+                        .map(|(name, span)| {
+                            let label = Label::secondary(fid, *span);
+                            match did_you_mean(name, expected.iter().map(String::as_str)) {
+                                Some(suggestion) => {
+                                    label.with_message(format!("did you mean '{suggestion}'?"))
+                                }
+                                None => label,
+                            }
+                        })
                         .collect(),
                 ),
             ErrorKind::MissingField(field) => diag
@@ -317,11 +610,25 @@ impl Error {
                 .with_labels(vec![
                     Label::primary(fid, self.span).with_message("deprecated field"),
                 ]),
-            ErrorKind::UnexpectedValue { expected, .. } => diag
-                .with_message(format!("expected '{expected:?}'"))
+            ErrorKind::DuplicateField { field, first } => diag
+                .with_message(format!("field '{field}' is specified more than once"))
                 .with_labels(vec![
-                    Label::primary(fid, self.span).with_message("unexpected value"),
+                    Label::secondary(fid, *first).with_message("first specified here"),
+                    Label::primary(fid, self.span).with_message("specified again here"),
                 ]),
+            ErrorKind::UnexpectedValue { expected, .. } => {
+                let diag = diag
+                    .with_message(format!("expected '{expected:?}'"))
+                    .with_labels(vec![
+                        Label::primary(fid, self.span).with_message("unexpected value"),
+                    ]);
+                match self.kind.suggestions().first() {
+                    Some((_, suggestion)) => {
+                        diag.with_notes(vec![format!("help: did you mean '{suggestion}'?")])
+                    }
+                    None => diag,
+                }
+            }
             ErrorKind::UnexpectedEof => diag
                 .with_message("unexpected end of file")
                 .with_labels(vec![Label::primary(fid, self.span)]),
@@ -331,12 +638,20 @@ impl Error {
                     Label::secondary(fid, *first).with_message("non-table"),
                 ])
             }
-            ErrorKind::RedefineAsArray => diag
+            ErrorKind::RedefineAsArray
+            | ErrorKind::LimitExceeded { .. }
+            | ErrorKind::UnsupportedFeature { .. } => diag
                 .with_message(self.to_string())
                 .with_labels(vec![Label::primary(fid, self.span)]),
             ErrorKind::Custom(msg) => diag
                 .with_message(msg.to_string())
                 .with_labels(vec![Label::primary(fid, self.span)]),
+        };
+
+        if self.path.is_empty() {
+            diag
+        } else {
+            diag.with_notes(vec![format!("key path: {}", self.path)])
         }
     }
 }
@@ -350,11 +665,30 @@ pub struct DeserError {
 }
 
 impl DeserError {
+    /// Adds a segment to the front of the [`Error::path`] of all of the
+    /// errors, for [`Deserialize`](crate::Deserialize) implementations that
+    /// deserialize the values of a table or array
+    #[inline]
+    pub fn prepend_path(&mut self, segment: PathSegment) {
+        for err in &mut self.errors {
+            err.path.prepend(segment.clone());
+        }
+    }
+
     /// Merges errors from another [`Self`]
     #[inline]
     pub fn merge(&mut self, mut other: Self) {
         self.errors.append(&mut other.errors);
     }
+
+    /// Fills the line information of all of the errors, see
+    /// [`Error::fill_line_info`]
+    #[inline]
+    pub fn fill_line_info(&mut self, index: &LineIndex) {
+        for err in &mut self.errors {
+            err.fill_line_info(index);
+        }
+    }
 }
 
 impl std::error::Error for DeserError {}
@@ -0,0 +1,335 @@
+//! Provides [`EventReader`], a pull parser that reports the structure of a
+//! toml document as a stream of [`Event`]s without building a [`Value`] tree
+
+use crate::{
+    Error, Span,
+    de::{self, Deserializer, ParseOptions, ValueStart},
+    tokens::Token,
+    value::{Key, Value},
+};
+
+/// A single event in a toml document, see [`EventReader`]
+#[derive(Debug)]
+pub enum Event<'de> {
+    /// A `[table]` or `[[array.of.tables]]` header
+    TableHeader {
+        /// The keys of the header
+        path: Vec<Key<'de>>,
+        /// True if this is an array of tables header
+        array: bool,
+        /// The span of the header, from the opening to the closing bracket(s)
+        span: Span,
+    },
+    /// The key of a `key = value` pair, either in the current table or in an
+    /// inline table, the events of the value follow
+    Key {
+        /// The keys of a dotted key, relative to the table the pair is in
+        path: Vec<Key<'de>>,
+        /// The span from the start of the first key to the end of the last key
+        span: Span,
+    },
+    /// A string, integer, float, boolean, or datetime, either the value of a
+    /// key or an element of an array
+    Value(Value<'de>),
+    /// The start of an array, the events of its elements follow until the
+    /// matching [`Self::ArrayEnd`]
+    ArrayStart {
+        /// The span of the opening bracket
+        span: Span,
+    },
+    /// The end of an array
+    ArrayEnd {
+        /// The span of the entire array
+        span: Span,
+    },
+    /// The start of an inline table, the events of its key/value pairs follow
+    /// until the matching [`Self::InlineTableEnd`]
+    InlineTableStart {
+        /// The span of the opening brace
+        span: Span,
+    },
+    /// The end of an inline table
+    InlineTableEnd {
+        /// The span of the entire inline table
+        span: Span,
+    },
+}
+
+/// An array or inline table the reader is currently in
+enum Frame {
+    Array {
+        /// The start of the opening bracket
+        start: usize,
+        /// The number of elements so far
+        len: usize,
+        /// True if an element was just read, and must be followed by a comma
+        /// or the closing bracket
+        has_value: bool,
+    },
+    InlineTable {
+        /// The start of the opening brace
+        start: usize,
+        /// The number of keys in the dotted key of the current pair
+        key_len: usize,
+        /// True if a pair was just read, and must be followed by a comma or
+        /// the closing brace
+        has_value: bool,
+        /// The span of the preceding comma, if a pair was just read and
+        /// followed by one
+        comma: Option<Span>,
+    },
+}
+
+/// A pull parser that reads a toml document one [`Event`] at a time
+///
+/// This only checks that the document is syntactically valid, semantic
+/// errors such as duplicate keys or tables are only detected when the
+/// document is parsed into a [`Value`] with [`parse`](crate::parse). Once an
+/// error is returned, the reader yields no more events.
+///
+/// ```
+/// use toml_span::events::{Event, EventReader};
+///
+/// let toml = "[package]\nname = 'toml-span'\n[dependencies]\nsmallvec = '1.13'";
+///
+/// // Find the package name without parsing the rest of the document
+/// let mut in_package = false;
+/// let mut events = EventReader::new(toml);
+/// let name = loop {
+///     match events.next().unwrap().unwrap() {
+///         Event::TableHeader { path, .. } => in_package = path[0].name == "package",
+///         Event::Key { path, .. } if in_package && path[0].name == "name" => {
+///             let Some(Ok(Event::Value(value))) = events.next() else {
+///                 panic!("expected a value");
+///             };
+///             break value;
+///         }
+///         _ => {}
+///     }
+/// };
+///
+/// assert_eq!(name.as_str(), Some("toml-span"));
+/// assert_eq!(&toml[name.span.start..name.span.end], "toml-span");
+/// ```
+pub struct EventReader<'de> {
+    de: Deserializer<'de>,
+    stack: Vec<Frame>,
+    /// The event following the one that was just returned, when a single
+    /// step produces two
+    pending: Option<Event<'de>>,
+    num_tables: usize,
+    finished: bool,
+}
+
+impl<'de> EventReader<'de> {
+    /// Creates a reader for the document with the default [`ParseOptions`]
+    pub fn new(input: &'de str) -> Self {
+        Self::from_de(Deserializer::new(input))
+    }
+
+    /// Creates a reader for the document with the specified options
+    ///
+    /// The [`version`](ParseOptions::version) and
+    /// [`limits`](ParseOptions::limits) apply as they do when parsing, while
+    /// [`comments`](ParseOptions::comments) are not collected.
+    pub fn with_options(input: &'de str, options: &ParseOptions) -> Result<Self, Error> {
+        Deserializer::with_options(input, options).map(Self::from_de)
+    }
+
+    fn from_de(de: Deserializer<'de>) -> Self {
+        Self {
+            de,
+            stack: Vec::new(),
+            pending: None,
+            num_tables: 0,
+            finished: false,
+        }
+    }
+
+    fn step(&mut self) -> Result<Option<Event<'de>>, Error> {
+        if let Some(event) = self.pending.take() {
+            return Ok(Some(event));
+        }
+
+        let de = &mut self.de;
+        match self.stack.last_mut() {
+            None => self.line(),
+            Some(Frame::Array {
+                start,
+                len,
+                has_value,
+            }) => {
+                de.array_whitespace()?;
+                if *has_value {
+                    *has_value = false;
+                    if de.eat(Token::Comma)? {
+                        de.array_whitespace()?;
+                    } else {
+                        let end = de.expect_spanned(Token::RightBracket)?;
+                        let span = Span::new(*start, end.end);
+                        return self.close(Event::ArrayEnd { span });
+                    }
+                }
+
+                if let Some(end) = de.eat_spanned(Token::RightBracket)? {
+                    let span = Span::new(*start, end.end);
+                    return self.close(Event::ArrayEnd { span });
+                }
+
+                *len += 1;
+                let len = *len;
+                let event = self.value()?;
+                let span = match &event {
+                    Event::Value(value) => value.span,
+                    Event::ArrayStart { span } | Event::InlineTableStart { span } => *span,
+                    _ => unreachable!("value only returns the start of a value"),
+                };
+                let max = self.de.limits.max_array_len;
+                self.de.limit("array length", len, max, span)?;
+                Ok(Some(event))
+            }
+            Some(Frame::InlineTable {
+                start,
+                key_len,
+                has_value,
+                comma,
+            }) => {
+                de.inline_table_whitespace()?;
+                if let Some(end) = de.eat_spanned(Token::RightBrace)? {
+                    if let Some(comma) = comma.take() {
+                        de.unsupported(comma, "trailing commas in inline tables")?;
+                    }
+                    let span = Span::new(*start, end.end);
+                    return self.close(Event::InlineTableEnd { span });
+                }
+
+                if *has_value {
+                    *has_value = false;
+                    *comma = Some(de.expect_spanned(Token::Comma)?);
+                    return self.step();
+                }
+
+                let key = de.dotted_key()?;
+                de.descend_keys(&key)?;
+                *key_len = key.len();
+                *comma = None;
+                de.inline_table_whitespace()?;
+                de.expect_spanned(Token::Equals)?;
+                de.inline_table_whitespace()?;
+                self.key_value(key)
+            }
+        }
+    }
+
+    /// Reads a table header or a `key = value` pair
+    fn line(&mut self) -> Result<Option<Event<'de>>, Error> {
+        let de = &mut self.de;
+        de.skip_blank_lines()?;
+
+        match de.peek()? {
+            Some((_, Token::LeftBracket)) => {
+                let (path, array, span) = de.header()?;
+
+                self.num_tables += 1;
+                de.limit("table count", self.num_tables, de.limits.max_tables, span)?;
+                de.depth = 0;
+                de.descend_keys(&path)?;
+                de.header_depth = de.depth;
+
+                Ok(Some(Event::TableHeader { path, array, span }))
+            }
+            Some(_) => {
+                let key = de.dotted_key()?;
+                de.depth = de.header_depth;
+                de.descend_keys(&key)?;
+                de.eat_whitespace();
+                de.expect_spanned(Token::Equals)?;
+                de.eat_whitespace();
+                self.key_value(key)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the event for the key, and stores the event for the start of
+    /// its value
+    fn key_value(&mut self, path: Vec<Key<'de>>) -> Result<Option<Event<'de>>, Error> {
+        let span = Span::new(path[0].span.start, path[path.len() - 1].span.end);
+        self.pending = Some(self.value()?);
+        Ok(Some(Event::Key { path, span }))
+    }
+
+    /// Reads a scalar value, or the start of an array or inline table
+    fn value(&mut self) -> Result<Event<'de>, Error> {
+        match self.de.value_start()? {
+            ValueStart::Scalar(val) => {
+                let value = de::to_value(val, &mut self.de)?;
+                self.end_value()?;
+                Ok(Event::Value(value))
+            }
+            ValueStart::Array(span) => {
+                self.de.descend(span)?;
+                self.stack.push(Frame::Array {
+                    start: span.start,
+                    len: 0,
+                    has_value: false,
+                });
+                Ok(Event::ArrayStart { span })
+            }
+            ValueStart::InlineTable(span) => {
+                self.de.descend(span)?;
+                self.stack.push(Frame::InlineTable {
+                    start: span.start,
+                    key_len: 0,
+                    has_value: false,
+                    comma: None,
+                });
+                Ok(Event::InlineTableStart { span })
+            }
+        }
+    }
+
+    /// Closes the current array or inline table
+    fn close(&mut self, event: Event<'de>) -> Result<Option<Event<'de>>, Error> {
+        self.stack.pop();
+        self.de.depth -= 1;
+        self.end_value()?;
+        Ok(Some(event))
+    }
+
+    /// Called when a value, including an array or inline table, is complete
+    fn end_value(&mut self) -> Result<(), Error> {
+        match self.stack.last_mut() {
+            Some(Frame::Array { has_value, .. }) => *has_value = true,
+            Some(Frame::InlineTable {
+                key_len, has_value, ..
+            }) => {
+                self.de.depth -= *key_len;
+                *has_value = true;
+            }
+            None => {
+                self.de.eat_whitespace();
+                if !self.de.eat_comment()? {
+                    self.de.eat_newline_or_eof()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Iterator for EventReader<'de> {
+    type Item = Result<Event<'de>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let event = self.step().transpose();
+        self.finished = !matches!(event, Some(Ok(_)));
+        event
+    }
+}
+
+impl std::iter::FusedIterator for EventReader<'_> {}
@@ -1,12 +1,22 @@
 #![cfg_attr(docsrs, doc(cfg(feature = "serde")))]
 
-//! Provides [`serde::Serialize`] support for [`Value`] and [`Spanned`]
+//! Provides [`serde::Serialize`] support for [`Value`], [`Spanned`], and [`Datetime`],
+//! as well as a [`serde::Deserializer`] implementation for [`Value`] so that
+//! types implementing [`serde::Deserialize`] can be deserialized from it
+//!
+//! Types deserialized with serde can still use this crate's
+//! [`Deserialize`](crate::Deserialize) for the fields where span information
+//! is needed via the [`Native`] adapter, or the [`native`] function
 
 use crate::{
-    Spanned,
-    value::{Value, ValueInner},
+    Datetime, DeserError, Error, ErrorKind, Span, Spanned,
+    value::{Key, Table, Value, ValueInner},
 };
-use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{
+    de::{self, IntoDeserializer},
+    ser::{SerializeMap, SerializeSeq},
+};
+use std::{borrow::Cow, cell::Cell, fmt, marker::PhantomData};
 
 impl serde::Serialize for Value<'_> {
     fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
@@ -18,6 +28,7 @@ impl serde::Serialize for Value<'_> {
             ValueInner::Integer(i) => ser.serialize_i64(*i),
             ValueInner::Float(f) => ser.serialize_f64(*f),
             ValueInner::Boolean(b) => ser.serialize_bool(*b),
+            ValueInner::Datetime(dt) => dt.serialize(ser),
             ValueInner::Array(arr) => {
                 let mut seq = ser.serialize_seq(Some(arr.len()))?;
                 for ele in arr {
@@ -47,3 +58,480 @@ where
         self.value.serialize(serializer)
     }
 }
+
+impl serde::Serialize for Datetime {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Datetime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct DatetimeVisitor;
+
+        impl de::Visitor<'_> for DatetimeVisitor {
+            type Value = Datetime;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a datetime")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Datetime, E> {
+                s.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(DatetimeVisitor)
+    }
+}
+
+/// Errors created by serde don't have a span, so they are given the span of
+/// the value or key that was being deserialized when they occurred
+fn with_span(mut errors: DeserError, span: Span) -> DeserError {
+    for err in &mut errors.errors {
+        if err.span == Span::default() && err.line_info.is_none() {
+            err.span = span;
+
+            if let ErrorKind::UnexpectedKeys { keys, .. } = &mut err.kind {
+                for (_, key_span) in keys {
+                    *key_span = span;
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        (ErrorKind::Custom(msg.to_string().into()), Span::default()).into()
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        (ErrorKind::MissingField(field), Span::default()).into()
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        (
+            ErrorKind::UnexpectedKeys {
+                keys: vec![(field.to_owned(), Span::default())],
+                expected: expected.iter().map(|s| (*s).to_owned()).collect(),
+            },
+            Span::default(),
+        )
+            .into()
+    }
+
+    fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+        (
+            ErrorKind::UnexpectedValue {
+                expected,
+                value: Some(variant.to_owned()),
+            },
+            Span::default(),
+        )
+            .into()
+    }
+}
+
+/// The error of the [`Value`] deserializer, so that all of the errors of a
+/// [`Native`] type are returned rather than only the first one
+impl de::Error for DeserError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::custom(msg).into()
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::missing_field(field).into()
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        Error::unknown_field(field, expected).into()
+    }
+
+    fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+        Error::unknown_variant(variant, expected).into()
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value<'de> {
+    type Error = DeserError;
+
+    fn deserialize_any<V>(mut self, visitor: V) -> Result<V::Value, DeserError>
+    where
+        V: de::Visitor<'de>,
+    {
+        let span = self.span;
+        let res = match self.take() {
+            ValueInner::String(Cow::Borrowed(s)) => visitor.visit_borrowed_str(s),
+            ValueInner::String(Cow::Owned(s)) => visitor.visit_string(s),
+            ValueInner::Integer(i) => visitor.visit_i64(i),
+            ValueInner::Float(f) => visitor.visit_f64(f),
+            ValueInner::Boolean(b) => visitor.visit_bool(b),
+            ValueInner::Datetime(dt) => visitor.visit_string(dt.to_string()),
+            ValueInner::Array(arr) => visitor.visit_seq(SeqDeserializer {
+                iter: arr.into_iter(),
+            }),
+            ValueInner::Table(tab) => visitor.visit_map(MapDeserializer {
+                iter: tab.into_iter(),
+                value: None,
+            }),
+        };
+
+        res.map_err(|err| with_span(err, span))
+    }
+
+    // toml has no null, so if a value is present it's always `Some`
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, DeserError>
+    where
+        V: de::Visitor<'de>,
+    {
+        let span = self.span;
+        visitor.visit_some(self).map_err(|err| with_span(err, span))
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserError>
+    where
+        V: de::Visitor<'de>,
+    {
+        let span = self.span;
+
+        if name == NATIVE {
+            NATIVE_VALUE.with(|native| native.set(Some(self.into_owned())));
+            let res = visitor.visit_unit();
+            // Ensure the value is not left around if the visitor didn't take it
+            NATIVE_VALUE.with(Cell::take);
+            let errors = NATIVE_ERRORS.with(Cell::take);
+            return res.map_err(|err| errors.unwrap_or(err));
+        }
+
+        visitor
+            .visit_newtype_struct(self)
+            .map_err(|err| with_span(err, span))
+    }
+
+    fn deserialize_enum<V>(
+        mut self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserError>
+    where
+        V: de::Visitor<'de>,
+    {
+        let span = self.span;
+        let res = match self.take() {
+            // Unit variants can be specified with just a string
+            ValueInner::String(s) => {
+                visitor.visit_enum(IntoDeserializer::<DeserError>::into_deserializer(s))
+            }
+            // Other variants are specified as a table with a single key, the
+            // name of the variant
+            ValueInner::Table(tab) if tab.len() == 1 => {
+                let (variant, value) = tab.into_iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer { variant, value })
+            }
+            ValueInner::Table(_) => Err(de::Error::invalid_value(
+                de::Unexpected::Map,
+                &"a table with a single key",
+            )),
+            other => Err(Error::new(
+                ErrorKind::Wanted {
+                    expected: "a string or table",
+                    found: other.type_str(),
+                },
+                span,
+            )
+            .into()),
+        };
+
+        res.map_err(|err| with_span(err, span))
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, DeserError>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeserError> for Value<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Deserializes the key of a table, errors are given the span of the key
+struct KeyDeserializer<'de>(Key<'de>);
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'de> {
+    type Error = DeserError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, DeserError>
+    where
+        V: de::Visitor<'de>,
+    {
+        let span = self.0.span;
+        let res = match self.0.name {
+            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Cow::Owned(s) => visitor.visit_string(s),
+        };
+
+        res.map_err(|err| with_span(err, span))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct SeqDeserializer<'de> {
+    iter: std::vec::IntoIter<Value<'de>>,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer<'de> {
+    type Error = DeserError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, DeserError>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        self.iter
+            .next()
+            .map(|value| seed.deserialize(value))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapDeserializer<'de> {
+    iter: <Table<'de> as IntoIterator>::IntoIter,
+    value: Option<Value<'de>>,
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer<'de> {
+    type Error = DeserError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, DeserError>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        let Some((key, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+
+        self.value = Some(value);
+        seed.deserialize(KeyDeserializer(key)).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, DeserError>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct EnumDeserializer<'de> {
+    variant: Key<'de>,
+    value: Value<'de>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer<'de> {
+    type Error = DeserError;
+    type Variant = VariantDeserializer<'de>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), DeserError>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(KeyDeserializer(self.variant))?;
+        Ok((variant, VariantDeserializer(self.value)))
+    }
+}
+
+struct VariantDeserializer<'de>(Value<'de>);
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer<'de> {
+    type Error = DeserError;
+
+    fn unit_variant(self) -> Result<(), DeserError> {
+        match self.0.as_ref() {
+            ValueInner::Table(tab) if tab.is_empty() => Ok(()),
+            other => Err(Error::new(
+                ErrorKind::Wanted {
+                    expected: "an empty table",
+                    found: other.type_str(),
+                },
+                self.0.span,
+            )
+            .into()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, DeserError>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.0)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, DeserError>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self.0, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserError>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(self.0, visitor)
+    }
+}
+
+/// The name of the newtype struct requested by [`Native`], which signals to
+/// the [`Value`] deserializer that it should pass itself via [`NATIVE_VALUE`]
+const NATIVE: &str = "$__toml_span_private_native";
+
+thread_local! {
+    /// Serde provides no way to get at the [`Value`] from within a
+    /// [`serde::Deserialize`] implementation, so the [`Value`] deserializer
+    /// instead passes it to [`Native`] here
+    static NATIVE_VALUE: Cell<Option<Value<'static>>> = const { Cell::new(None) };
+    /// Serde errors are created from a message, so [`Native`] passes the
+    /// errors of its inner type back to the [`Value`] deserializer here
+    static NATIVE_ERRORS: Cell<Option<DeserError>> = const { Cell::new(None) };
+}
+
+/// Adapter that deserializes a type implementing this crate's
+/// [`Deserialize`](crate::Deserialize) as part of a type implementing
+/// [`serde::Deserialize`]
+///
+/// This allows eg. [`Spanned`] fields in a struct that derives
+/// [`serde::Deserialize`] so that span information is available exactly where
+/// it is needed, without porting the entire struct.
+///
+/// This only works when deserializing directly from a [`Value`], if serde
+/// buffers the value first, as it does for `#[serde(untagged)]` enums and
+/// `#[serde(flatten)]`, deserialization fails rather than losing the span. In
+/// those cases implement [`Deserialize`](crate::Deserialize) for the entire
+/// enum or struct and use it via this adapter instead.
+///
+/// All of the errors of the inner type are returned by the [`Value`]
+/// deserializer. The inner type can borrow from the document, eg.
+/// `Spanned<Cow<'de, str>>` with `#[serde(borrow)]`, but the value is copied
+/// before it is passed to the inner type, so its strings are always owned.
+///
+/// ```
+/// use serde::Deserialize;
+/// use toml_span::{Spanned, impl_serde::Native};
+///
+/// #[derive(Deserialize)]
+/// struct Package {
+///     name: String,
+///     version: Native<Spanned<String>>,
+///     #[serde(deserialize_with = "toml_span::impl_serde::native")]
+///     license: Spanned<String>,
+/// }
+///
+/// let toml = "name = 'toml-span'\nversion = '0.7.1'\nlicense = 'MIT'";
+/// let package = Package::deserialize(toml_span::parse(toml).unwrap()).unwrap();
+///
+/// let span = package.version.0.span;
+/// assert_eq!(&toml[span.start..span.end], "0.7.1");
+/// let span = package.license.span;
+/// assert_eq!(&toml[span.start..span.end], "MIT");
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Native<T>(pub T);
+
+impl<'de, T> serde::Deserialize<'de> for Native<T>
+where
+    T: crate::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct NativeVisitor<T>(PhantomData<T>);
+
+        impl<'de, T> de::Visitor<'de> for NativeVisitor<T>
+        where
+            T: crate::Deserialize<'de>,
+        {
+            type Value = T;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a toml_span::value::Value")
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<T, E> {
+                let mut value: Value<'de> = NATIVE_VALUE.with(Cell::take).ok_or_else(|| {
+                    E::custom("only a toml_span::value::Value can deserialize a native type")
+                })?;
+
+                T::deserialize(&mut value).map_err(|err| {
+                    let msg = err.to_string();
+                    NATIVE_ERRORS.with(|errors| errors.set(Some(err)));
+                    E::custom(msg)
+                })
+            }
+        }
+
+        deserializer
+            .deserialize_newtype_struct(NATIVE, NativeVisitor(PhantomData))
+            .map(Self)
+    }
+}
+
+/// Deserializes a type implementing this crate's
+/// [`Deserialize`](crate::Deserialize) as a field of a type implementing
+/// [`serde::Deserialize`], for use with `#[serde(deserialize_with = "...")]`
+///
+/// See [`Native`] for details.
+pub fn native<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: crate::Deserialize<'de>,
+{
+    <Native<T> as serde::Deserialize<'de>>::deserialize(deserializer).map(|native| native.0)
+}
@@ -2,18 +2,29 @@
 #![doc = include_str!("../README.md")]
 #![deny(missing_docs)]
 
+pub mod comments;
+pub mod cst;
+pub mod datetime;
 pub mod de;
 pub mod de_helpers;
+pub mod edit;
 mod error;
+pub mod events;
+pub mod ser;
 pub mod span;
 pub mod tokens;
 pub mod value;
 
-pub use de::parse;
-pub use error::{DeserError, Error, ErrorKind};
+pub use datetime::Datetime;
+pub use de::{Document, Limits, ParseOptions, TomlVersion, parse, parse_recovering, parse_with};
+pub use error::{DeserError, Error, ErrorKind, KeyPath, PathSegment, Severity};
 pub use span::{Span, Spanned};
 pub use value::Value;
 
+#[cfg(feature = "derive")]
+#[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
+pub use toml_span_derive::Deserialize;
+
 #[cfg(feature = "serde")]
 pub mod impl_serde;
 
@@ -24,6 +35,46 @@ pub trait Deserialize<'de>: Sized {
     fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError>;
 }
 
+/// Deserializes a type from the [`Key`](value::Key) of a table entry, which
+/// allows it to be used as the key of a map such as
+/// [`BTreeMap`](std::collections::BTreeMap)
+///
+/// ```
+/// use toml_span::{DeserError, DeserializeKey, Error, ErrorKind, value::Key};
+///
+/// #[derive(PartialEq, Eq, PartialOrd, Ord)]
+/// enum Target {
+///     Linux,
+///     Windows,
+/// }
+///
+/// impl<'de> DeserializeKey<'de> for Target {
+///     fn deserialize_key(key: Key<'de>) -> Result<Self, DeserError> {
+///         match key.name.as_ref() {
+///             "linux" => Ok(Self::Linux),
+///             "windows" => Ok(Self::Windows),
+///             other => Err(Error::from((
+///                 ErrorKind::UnexpectedValue {
+///                     expected: &["linux", "windows"],
+///                     value: Some(other.into()),
+///                 },
+///                 key.span,
+///             ))
+///             .into()),
+///         }
+///     }
+/// }
+///
+/// let mut value = toml_span::parse("linux = 1\nwindows = 2").unwrap();
+/// let map: std::collections::BTreeMap<Target, u32> =
+///     toml_span::Deserialize::deserialize(&mut value).unwrap();
+/// assert_eq!(map[&Target::Windows], 2);
+/// ```
+pub trait DeserializeKey<'de>: Sized {
+    /// Deserializes the type from the key
+    fn deserialize_key(key: value::Key<'de>) -> Result<Self, DeserError>;
+}
+
 /// This crate's equivalent to [`serde::DeserializeOwned`](https://docs.rs/serde/latest/serde/de/trait.DeserializeOwned.html)
 ///
 /// This is useful if you want to use trait bounds
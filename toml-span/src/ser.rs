@@ -0,0 +1,463 @@
+//! Serializes a [`Value`] back into a toml document
+
+use crate::{
+    Error, ErrorKind, Span,
+    value::{Key, Table, Value, ValueInner},
+};
+use std::{collections::BTreeMap, fmt::Write as _};
+
+/// How tables nested in the root table are emitted
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TableStyle {
+    /// Tables are emitted with a `[header]`, this is the default
+    #[default]
+    Standard,
+    /// Tables are emitted as inline tables `key = { a = 1, b = 2 }`
+    Inline,
+}
+
+/// How strings, and keys that can't be bare, are quoted
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StringStyle {
+    /// Strings are emitted as basic strings `"string"`, with escapes where
+    /// needed, this is the default
+    #[default]
+    Basic,
+    /// Strings are emitted as literal strings `'string'`, falling back to a
+    /// basic string if the string cannot be represented as a literal string
+    Literal,
+}
+
+/// The order in which the keys of a table are emitted
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// Keys are emitted in sorted order, this is the default
+    #[default]
+    Sorted,
+    /// Keys are emitted in the order they appeared in the source document,
+    /// according to their [`Key::span`]
+    ///
+    /// Keys with identical spans, eg. keys that were added programmatically,
+    /// fall back to sorted order
+    Source,
+}
+
+/// Configures how a [`Value`] is serialized
+#[derive(Copy, Clone, Debug)]
+pub struct Config {
+    /// How tables nested in the root table are emitted
+    pub tables: TableStyle,
+    /// If true, arrays whose elements are all tables are emitted as arrays of
+    /// tables `[[header]]`, otherwise they are emitted as inline arrays of
+    /// inline tables
+    pub arrays_of_tables: bool,
+    /// How strings are quoted
+    pub strings: StringStyle,
+    /// The order keys are emitted in
+    pub key_order: KeyOrder,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tables: TableStyle::Standard,
+            arrays_of_tables: true,
+            strings: StringStyle::Basic,
+            key_order: KeyOrder::Sorted,
+        }
+    }
+}
+
+/// The [`Span`] of every value emitted during serialization, keyed by the
+/// [`Value::pointer`] path of the value
+///
+/// Unlike [`Value::pointer`], `~` and `/` in keys are escaped as `~0` and
+/// `~1` respectively, as in a JSON pointer, so that eg. the key `'a/b'` in
+/// table `t` has the pointer `/t/a~1b`, which is distinct from the pointer
+/// `/t/a/b` of the key `b` in the table `t.a`
+pub type SpanMap = BTreeMap<String, Span>;
+
+/// Serializes the root table to a toml string with the default [`Config`]
+#[inline]
+pub fn to_string(value: &Value<'_>) -> Result<String, Error> {
+    to_string_with(value, &Config::default())
+}
+
+/// Serializes the root table to a toml string
+#[inline]
+pub fn to_string_with(value: &Value<'_>, config: &Config) -> Result<String, Error> {
+    let mut ser = Serializer {
+        out: String::new(),
+        config,
+        spans: None,
+    };
+    ser.root(value)?;
+    Ok(ser.out)
+}
+
+/// Serializes the root table to a toml string, additionally returning the
+/// [`Span`] of every emitted value in the output
+///
+/// ```rust
+/// let value = toml_span::parse("b = 'two'\na = [1, 2]").unwrap();
+/// let (toml, spans) = toml_span::ser::to_string_spanned(&value, &Default::default()).unwrap();
+///
+/// assert_eq!(toml, "a = [1, 2]\nb = \"two\"\n");
+/// assert_eq!(&toml[spans["/a/1"].start..spans["/a/1"].end], "2");
+/// assert_eq!(&toml[spans["/b"].start..spans["/b"].end], "two");
+/// ```
+///
+/// Note that, like the parser, the span of a string does not include its quotes
+pub fn to_string_spanned(value: &Value<'_>, config: &Config) -> Result<(String, SpanMap), Error> {
+    let mut ser = Serializer {
+        out: String::new(),
+        config,
+        spans: Some(SpanMap::new()),
+    };
+    ser.root(value)?;
+    Ok((ser.out, ser.spans.unwrap_or_default()))
+}
+
+/// Serializes a single value in its inline form, eg. for use as the value of
+/// a `key = value` pair
+///
+/// Tables are emitted as inline tables and arrays as inline arrays, regardless
+/// of the [`Config::tables`] and [`Config::arrays_of_tables`] options
+pub fn value_to_string(value: &Value<'_>, config: &Config) -> String {
+    let mut ser = Serializer {
+        out: String::new(),
+        config,
+        spans: None,
+    };
+    ser.inline_value(&mut String::new(), value);
+    ser.out
+}
+
+/// Serializes a table as an element of an array of tables, ie. with a
+/// `[[path]]` header, followed by any of its subtables
+pub(crate) fn array_table_to_string(path: &[&str], table: &Table<'_>, config: &Config) -> String {
+    let mut ser = Serializer {
+        out: String::new(),
+        config,
+        spans: None,
+    };
+    ser.table(&mut path.to_vec(), &mut String::new(), table, Header::Array);
+    ser.out
+}
+
+/// Writes a key, quoting it only if it cannot be a bare key
+pub fn write_key(out: &mut String, key: &str, style: StringStyle) {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        out.push_str(key);
+    } else {
+        write_string(out, key, style);
+    }
+}
+
+/// Writes a quoted string, returning the span of its contents, excluding the
+/// quotes
+pub fn write_string(out: &mut String, s: &str, style: StringStyle) -> Span {
+    let can_be_literal = style == StringStyle::Literal
+        && !s
+            .chars()
+            .any(|c| c == '\'' || (c.is_control() && c != '\t'));
+
+    if can_be_literal {
+        out.push('\'');
+        let start = out.len();
+        out.push_str(s);
+        let span = string_span(start, out.len());
+        out.push('\'');
+        return span;
+    }
+
+    out.push('"');
+    let start = out.len();
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04X}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    let span = string_span(start, out.len());
+    out.push('"');
+    span
+}
+
+/// Appends a key to a [`SpanMap`] pointer, escaping it as in a JSON pointer
+fn push_key(pointer: &mut String, key: &str) {
+    pointer.push('/');
+    for c in key.chars() {
+        match c {
+            '~' => pointer.push_str("~0"),
+            '/' => pointer.push_str("~1"),
+            c => pointer.push(c),
+        }
+    }
+}
+
+/// Mirrors the tokenizer, which points the span of an empty string at the
+/// opening quote
+#[inline]
+fn string_span(start: usize, end: usize) -> Span {
+    if start == end {
+        Span::new(start - 1, start)
+    } else {
+        Span::new(start, end)
+    }
+}
+
+/// Writes a float, ensuring it is always parsed as a float and not an integer
+fn write_float(out: &mut String, f: f64) {
+    if f.is_nan() {
+        out.push_str(if f.is_sign_negative() { "-nan" } else { "nan" });
+    } else if f.is_infinite() {
+        out.push_str(if f.is_sign_negative() { "-inf" } else { "inf" });
+    } else {
+        // The Debug impl always includes either a decimal point or an exponent
+        let _ = write!(out, "{f:?}");
+    }
+}
+
+struct Serializer<'c> {
+    out: String,
+    config: &'c Config,
+    spans: Option<SpanMap>,
+}
+
+/// The kind of header, if any, a table section is emitted with
+#[derive(Copy, Clone)]
+enum Header {
+    Root,
+    Standard,
+    Array,
+}
+
+impl Serializer<'_> {
+    fn root(&mut self, value: &Value<'_>) -> Result<(), Error> {
+        let ValueInner::Table(table) = value.as_ref() else {
+            return Err(Error::from((
+                ErrorKind::Wanted {
+                    expected: "a table",
+                    found: value.as_ref().type_str(),
+                },
+                value.span,
+            )));
+        };
+
+        self.table(&mut Vec::new(), &mut String::new(), table, Header::Root);
+
+        let len = self.out.len();
+        self.span("", Span::new(0, len));
+        Ok(())
+    }
+
+    #[inline]
+    fn span(&mut self, pointer: &str, span: Span) {
+        if let Some(spans) = &mut self.spans {
+            spans.insert(pointer.to_owned(), span);
+        }
+    }
+
+    /// Returns true if the value is emitted as its own section, rather than as
+    /// a `key = value` pair
+    fn is_section(&self, value: &Value<'_>) -> bool {
+        match value.as_ref() {
+            ValueInner::Table(_) => self.config.tables == TableStyle::Standard,
+            ValueInner::Array(arr) => self.is_array_of_tables(arr),
+            _ => false,
+        }
+    }
+
+    fn is_array_of_tables(&self, arr: &[Value<'_>]) -> bool {
+        self.config.arrays_of_tables
+            && !arr.is_empty()
+            && arr
+                .iter()
+                .all(|v| matches!(v.as_ref(), ValueInner::Table(_)))
+    }
+
+    fn sorted<'t, 'de>(&self, table: &'t Table<'de>) -> Vec<(&'t Key<'de>, &'t Value<'de>)> {
+        // The table's own iteration order depends on the `preserve_order`
+        // feature, so always sort explicitly
+        let mut entries: Vec<_> = table.iter().collect();
+        entries.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+        if self.config.key_order == KeyOrder::Source {
+            entries.sort_by_key(|(k, _)| k.span.start);
+        }
+        entries
+    }
+
+    fn table<'t>(
+        &mut self,
+        path: &mut Vec<&'t str>,
+        pointer: &mut String,
+        table: &'t Table<'_>,
+        header: Header,
+    ) {
+        let entries = self.sorted(table);
+        let (plain, sections): (Vec<_>, Vec<_>) =
+            entries.into_iter().partition(|(_, v)| !self.is_section(v));
+
+        // The start of the section, not including the blank line separating it
+        // from the previous section
+        let start = self.out.len() + usize::from(!self.out.is_empty());
+        let mut end = start;
+
+        // Tables that only contain other tables don't need a header of their
+        // own, they are implicitly defined by their subtables
+        let has_header = match header {
+            Header::Root => false,
+            Header::Standard => !plain.is_empty() || sections.is_empty(),
+            Header::Array => true,
+        };
+
+        if has_header {
+            if !self.out.is_empty() {
+                self.out.push('\n');
+            }
+
+            let (open, close) = if matches!(header, Header::Array) {
+                ("[[", "]]")
+            } else {
+                ("[", "]")
+            };
+
+            self.out.push_str(open);
+            for (i, key) in path.iter().enumerate() {
+                if i > 0 {
+                    self.out.push('.');
+                }
+                write_key(&mut self.out, key, self.config.strings);
+            }
+            self.out.push_str(close);
+            end = self.out.len();
+            self.out.push('\n');
+        }
+
+        for (key, value) in plain {
+            let len = pointer.len();
+            push_key(pointer, &key.name);
+
+            write_key(&mut self.out, &key.name, self.config.strings);
+            self.out.push_str(" = ");
+            self.inline_value(pointer, value);
+            end = self.out.len();
+            self.out.push('\n');
+
+            pointer.truncate(len);
+        }
+
+        for (key, value) in sections {
+            let len = pointer.len();
+            push_key(pointer, &key.name);
+            path.push(&key.name);
+
+            match value.as_ref() {
+                ValueInner::Table(tab) => {
+                    self.table(path, pointer, tab, Header::Standard);
+                }
+                ValueInner::Array(arr) => {
+                    let arr_start = self.out.len() + usize::from(!self.out.is_empty());
+                    for (i, ele) in arr.iter().enumerate() {
+                        let len = pointer.len();
+                        let _ = write!(pointer, "/{i}");
+                        if let ValueInner::Table(tab) = ele.as_ref() {
+                            self.table(path, pointer, tab, Header::Array);
+                        }
+                        pointer.truncate(len);
+                    }
+                    let arr_end = self.out.len() - 1;
+                    self.span(pointer, Span::new(arr_start, arr_end));
+                }
+                _ => unreachable!("only tables and arrays of tables are sections"),
+            }
+
+            path.pop();
+            pointer.truncate(len);
+        }
+
+        match header {
+            Header::Root => {}
+            // Like the parser, the span of a table with a header covers the
+            // header and its own keys, but not its subtables
+            _ if has_header => self.span(pointer, Span::new(start, end)),
+            // Implicit tables span all of their subtables
+            _ => self.span(pointer, Span::new(start, self.out.len() - 1)),
+        }
+    }
+
+    fn inline_value(&mut self, pointer: &mut String, value: &Value<'_>) {
+        let start = self.out.len();
+        let span = match value.as_ref() {
+            ValueInner::String(s) => write_string(&mut self.out, s, self.config.strings),
+            ValueInner::Integer(i) => {
+                let _ = write!(self.out, "{i}");
+                Span::new(start, self.out.len())
+            }
+            ValueInner::Float(f) => {
+                write_float(&mut self.out, *f);
+                Span::new(start, self.out.len())
+            }
+            ValueInner::Boolean(b) => {
+                let _ = write!(self.out, "{b}");
+                Span::new(start, self.out.len())
+            }
+            ValueInner::Datetime(dt) => {
+                let _ = write!(self.out, "{dt}");
+                Span::new(start, self.out.len())
+            }
+            ValueInner::Array(arr) => {
+                self.out.push('[');
+                for (i, ele) in arr.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    let len = pointer.len();
+                    let _ = write!(pointer, "/{i}");
+                    self.inline_value(pointer, ele);
+                    pointer.truncate(len);
+                }
+                self.out.push(']');
+                Span::new(start, self.out.len())
+            }
+            ValueInner::Table(tab) => {
+                if tab.is_empty() {
+                    self.out.push_str("{}");
+                } else {
+                    self.out.push_str("{ ");
+                    for (i, (key, value)) in self.sorted(tab).into_iter().enumerate() {
+                        if i > 0 {
+                            self.out.push_str(", ");
+                        }
+                        let len = pointer.len();
+                        push_key(pointer, &key.name);
+
+                        write_key(&mut self.out, &key.name, self.config.strings);
+                        self.out.push_str(" = ");
+                        self.inline_value(pointer, value);
+
+                        pointer.truncate(len);
+                    }
+                    self.out.push_str(" }");
+                }
+                Span::new(start, self.out.len())
+            }
+        };
+
+        self.span(pointer, span);
+    }
+}
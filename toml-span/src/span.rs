@@ -47,6 +47,154 @@ impl From<Span> for std::ops::Range<usize> {
     }
 }
 
+/// The unit that the column of a [`Position`] is measured in
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ColumnUnit {
+    /// The column is the byte offset from the start of the line
+    Utf8,
+    /// The column is the number of UTF-16 code units from the start of the
+    /// line, as used by the Language Server Protocol by default
+    Utf16,
+    /// The column is the number of `char`s from the start of the line
+    Char,
+}
+
+/// A 0-based line and column within a toml document
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+pub struct Position {
+    /// The 0-based line
+    pub line: usize,
+    /// The 0-based column, in the [`ColumnUnit`] the position was created with
+    pub column: usize,
+}
+
+/// Converts byte offsets in a toml document to line and column [`Position`]s
+/// and back
+///
+/// The index is built once from the source, after which lookups are
+/// logarithmic in the number of lines, plus the number of multi-byte
+/// characters on the line for [`ColumnUnit::Utf16`] and [`ColumnUnit::Char`]
+/// columns.
+///
+/// Lines are terminated by `\n`, a preceding `\r` is considered part of the
+/// line, the same as the line information in parser [`Error`](crate::Error)s.
+#[derive(Clone, Debug)]
+pub struct LineIndex {
+    /// The byte offset of the start of each line
+    line_starts: Vec<usize>,
+    /// The byte offset and UTF-8 length of every multi-byte character
+    multibyte: Vec<(usize, u8)>,
+    /// The length of the source
+    len: usize,
+}
+
+impl LineIndex {
+    /// Builds the index for the specified source
+    pub fn new(src: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut multibyte = Vec::new();
+
+        for (i, c) in src.char_indices() {
+            if c == '\n' {
+                line_starts.push(i + 1);
+            } else if !c.is_ascii() {
+                multibyte.push((i, c.len_utf8() as u8));
+            }
+        }
+
+        Self {
+            line_starts,
+            multibyte,
+            len: src.len(),
+        }
+    }
+
+    /// The number of lines in the source
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Gets the span of the specified line, including its line terminator
+    pub fn line_span(&self, line: usize) -> Option<Span> {
+        let start = *self.line_starts.get(line)?;
+        let end = self.line_starts.get(line + 1).copied().unwrap_or(self.len);
+        Some(Span::new(start, end))
+    }
+
+    /// Converts a byte offset into a [`Position`]
+    ///
+    /// Offsets past the end of the source are clamped to the end.
+    pub fn position(&self, offset: usize, unit: ColumnUnit) -> Position {
+        let offset = offset.min(self.len);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+
+        let column = offset
+            - line_start
+            - self
+                .multibyte_in(line_start..offset)
+                .iter()
+                .map(|&(_, len)| usize::from(len) - units(len, unit))
+                .sum::<usize>();
+
+        Position { line, column }
+    }
+
+    /// Converts a [`Span`] into the [`Position`]s of its start and end
+    #[inline]
+    pub fn span(&self, span: Span, unit: ColumnUnit) -> (Position, Position) {
+        (
+            self.position(span.start, unit),
+            self.position(span.end, unit),
+        )
+    }
+
+    /// Converts a [`Position`] back into a byte offset, returning `None` if
+    /// the position is not within the source, or is in the middle of a
+    /// character, eg. between the two UTF-16 units of a surrogate pair
+    pub fn offset(&self, position: Position, unit: ColumnUnit) -> Option<usize> {
+        let line = self.line_span(position.line)?;
+
+        let mut offset = line.start + position.column;
+        for &(at, len) in self.multibyte_in(line.start..line.end) {
+            if at >= offset {
+                break;
+            }
+            let units = units(len, unit);
+            if offset < at + units {
+                return None;
+            }
+            offset += usize::from(len) - units;
+        }
+
+        (offset <= line.end).then_some(offset)
+    }
+
+    /// Gets the multi-byte characters that start within the range
+    fn multibyte_in(&self, range: std::ops::Range<usize>) -> &[(usize, u8)] {
+        let start = self.multibyte.partition_point(|&(at, _)| at < range.start);
+        let end = self.multibyte.partition_point(|&(at, _)| at < range.end);
+        &self.multibyte[start..end]
+    }
+}
+
+/// The number of units a character with the specified UTF-8 length occupies
+#[inline]
+fn units(len: u8, unit: ColumnUnit) -> usize {
+    match unit {
+        ColumnUnit::Utf8 => len.into(),
+        ColumnUnit::Utf16 => {
+            if len == 4 {
+                2
+            } else {
+                1
+            }
+        }
+        ColumnUnit::Char => 1,
+    }
+}
+
 /// An arbitrary `T` with additional span information
 pub struct Spanned<T> {
     /// The value
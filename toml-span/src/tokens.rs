@@ -1,7 +1,7 @@
 #![allow(missing_docs)]
 //! The tokenizer is publicly exposed if you wish to use it instead
 
-use crate::{Span, value::Key};
+use crate::{Span, TomlVersion, value::Key};
 use std::{borrow::Cow, char, str};
 
 #[derive(Eq, PartialEq, Debug)]
@@ -38,6 +38,7 @@ pub enum Error {
     Unexpected(usize, char),
     UnterminatedString(usize),
     MultilineStringKey(usize, usize),
+    Unsupported(usize, usize, &'static str),
     Wanted {
         at: usize,
         expected: &'static str,
@@ -49,11 +50,14 @@ pub enum Error {
 pub struct Tokenizer<'a> {
     input: &'a str,
     chars: CrlfFold<'a>,
+    version: TomlVersion,
 }
 
 #[derive(Clone)]
 struct CrlfFold<'a> {
     chars: str::CharIndices<'a>,
+    /// The byte offset of `chars` in the input
+    offset: usize,
 }
 
 #[derive(Debug)]
@@ -64,11 +68,18 @@ enum MaybeString {
 
 impl<'a> Tokenizer<'a> {
     pub fn new(input: &'a str) -> Tokenizer<'a> {
+        Self::with_version(input, TomlVersion::default())
+    }
+
+    /// Creates a tokenizer that fails on escapes not supported by `version`
+    pub fn with_version(input: &'a str, version: TomlVersion) -> Tokenizer<'a> {
         let mut t = Tokenizer {
             input,
             chars: CrlfFold {
                 chars: input.char_indices(),
+                offset: 0,
             },
+            version,
         };
         // Eat utf-8 BOM
         t.eatc('\u{feff}');
@@ -232,6 +243,15 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    /// Moves the tokenizer to the byte offset, which must be at a char
+    /// boundary, without scanning the input before it
+    pub fn reset_to(&mut self, offset: usize) {
+        self.chars = CrlfFold {
+            chars: self.input[offset..].char_indices(),
+            offset,
+        };
+    }
+
     pub fn current(&mut self) -> usize {
         match self.chars.clone().next() {
             Some(i) => i.0,
@@ -248,7 +268,7 @@ impl<'a> Tokenizer<'a> {
 
     fn comment_token(&mut self, start: usize) -> Token<'a> {
         while let Some((_, ch)) = self.chars.clone().next() {
-            if ch != '\t' && !('\u{20}'..='\u{10ffff}').contains(&ch) {
+            if !(ch == '\u{09}' || (ch != '\u{7f}' && ('\u{20}'..='\u{10ffff}').contains(&ch))) {
                 break;
             }
             self.one();
@@ -384,7 +404,11 @@ impl<'a> Tokenizer<'a> {
                     Some((_, 'n')) => val.push('\n'),
                     Some((_, 'r')) => val.push('\r'),
                     Some((_, 't')) => val.push('\t'),
-                    Some((_, 'e')) => val.push('\u{1b}'), // TOML 1.1: escape character
+                    Some((e, 'e')) => {
+                        // TOML 1.1: escape character
+                        me.unsupported(i, e + 1, "`\\e` escapes")?;
+                        val.push('\u{1b}');
+                    }
                     Some((i, c @ ('u' | 'U'))) => {
                         let c = if c == 'u' {
                             me.hex::<4>(start, i)
@@ -393,9 +417,10 @@ impl<'a> Tokenizer<'a> {
                         };
                         val.push(c?);
                     }
-                    Some((i, 'x')) => {
+                    Some((x, 'x')) => {
                         // TOML 1.1: \xHH for codepoints < 255
-                        val.push(me.hex::<2>(start, i)?);
+                        me.unsupported(i, (x + 3).min(me.input.len()), "`\\x` escapes")?;
+                        val.push(me.hex::<2>(start, x)?);
                     }
                     Some((i, c @ (' ' | '\t' | '\n'))) if multi => {
                         if c != '\n' {
@@ -434,6 +459,16 @@ impl<'a> Tokenizer<'a> {
         })
     }
 
+    /// Fails if the tokenizer only supports TOML 1.0, as the feature was
+    /// added in TOML 1.1
+    fn unsupported(&self, start: usize, end: usize, feature: &'static str) -> Result<(), Error> {
+        if self.version == TomlVersion::V1_0 {
+            Err(Error::Unsupported(start, end, feature))
+        } else {
+            Ok(())
+        }
+    }
+
     fn hex<const N: usize>(&mut self, start: usize, i: usize) -> Result<char, Error> {
         let mut buf = [0; N];
         for b in buf.iter_mut() {
@@ -460,6 +495,45 @@ impl<'a> Tokenizer<'a> {
         Token::Keylike(&self.input[start..self.current()])
     }
 
+    /// Attempts to eat a datetime at the current position
+    ///
+    /// Anything that starts like a date (`DDDD-`) or a time (`DD:`) is
+    /// consumed, along with the rest of the characters that could be part of a
+    /// datetime, so that malformed datetimes can be reported as a whole.
+    /// Validation is left to [`crate::Datetime`]'s `FromStr` impl.
+    pub fn datetime(&mut self) -> Option<(Span, &'a str)> {
+        let start = self.current();
+        let rest = &self.input.as_bytes()[start..];
+
+        let is_digits = |r: &[u8], n: usize| r.len() >= n && r[..n].iter().all(u8::is_ascii_digit);
+
+        let is_date = is_digits(rest, 4) && rest.get(4) == Some(&b'-');
+        let is_time = is_digits(rest, 2) && rest.get(2) == Some(&b':');
+        if !is_date && !is_time {
+            return None;
+        }
+
+        let is_datetime_char =
+            |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b':' | b'.' | b'+');
+
+        let mut len = rest.iter().take_while(|b| is_datetime_char(**b)).count();
+
+        // A date may be separated from the time with a space instead of a `T`
+        if is_date && len == 10 && rest.get(10) == Some(&b' ') {
+            let time = &rest[11..];
+            if is_digits(time, 2) && time.get(2) == Some(&b':') {
+                len += 1 + time.iter().take_while(|b| is_datetime_char(**b)).count();
+            }
+        }
+
+        let end = start + len;
+        while self.current() < end {
+            self.one();
+        }
+
+        Some((Span::new(start, end), &self.input[start..end]))
+    }
+
     pub fn substr_offset(&self, s: &'a str) -> usize {
         assert!(s.len() <= self.input.len());
         let a = self.input.as_ptr() as usize;
@@ -497,10 +571,10 @@ impl Iterator for CrlfFold<'_> {
                 let mut attempt = self.chars.clone();
                 if let Some((_, '\n')) = attempt.next() {
                     self.chars = attempt;
-                    return (i, '\n');
+                    return (i + self.offset, '\n');
                 }
             }
-            (i, c)
+            (i + self.offset, c)
         })
     }
 }
@@ -3,13 +3,23 @@
 //! [`crate::Deserialize`] or your own constructs to deserialize into your own
 //! types
 
-use crate::{Error, ErrorKind, Span};
+use crate::{Error, ErrorKind, Span, datetime::Datetime};
 use std::{borrow::Cow, fmt};
 
+pub mod table;
+pub use table::Table;
+
 /// A deserialized [`ValueInner`] with accompanying [`Span`] information for where
 /// it was located in the toml document
 pub struct Value<'de> {
     value: Option<ValueInner<'de>>,
+    /// The warnings reported while deserializing the value, see
+    /// [`Self::take_warnings`]
+    ///
+    /// Boxed as most values never have any, so that they only take up a
+    /// pointer's worth of space
+    #[allow(clippy::box_collection)]
+    warnings: Option<Box<Vec<Error>>>,
     /// The location of the value in the toml document
     pub span: Span,
 }
@@ -26,10 +36,87 @@ impl<'de> Value<'de> {
     pub fn with_span(value: ValueInner<'de>, span: Span) -> Self {
         Self {
             value: Some(value),
+            warnings: None,
             span,
         }
     }
 
+    /// Converts the value into one that doesn't borrow from the toml document,
+    /// so that it can be passed to [`Native`](crate::impl_serde::Native)
+    #[cfg(feature = "serde")]
+    pub(crate) fn into_owned(self) -> Value<'static> {
+        let value = self.value.map(|value| match value {
+            ValueInner::String(s) => ValueInner::String(Cow::Owned(s.into_owned())),
+            ValueInner::Integer(i) => ValueInner::Integer(i),
+            ValueInner::Float(f) => ValueInner::Float(f),
+            ValueInner::Boolean(b) => ValueInner::Boolean(b),
+            ValueInner::Datetime(dt) => ValueInner::Datetime(dt),
+            ValueInner::Array(arr) => {
+                ValueInner::Array(arr.into_iter().map(Value::into_owned).collect())
+            }
+            ValueInner::Table(table) => ValueInner::Table(
+                table
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let key = Key {
+                            name: Cow::Owned(key.name.into_owned()),
+                            span: key.span,
+                        };
+                        (key, value.into_owned())
+                    })
+                    .collect(),
+            ),
+        });
+
+        Value {
+            value,
+            warnings: self.warnings,
+            span: self.span,
+        }
+    }
+
+    /// Takes the warnings reported while deserializing the value, eg. with
+    /// [`TableHelper::warn`](crate::de_helpers::TableHelper::warn)
+    ///
+    /// Warnings don't cause deserialization to fail, so they are returned
+    /// whether it succeeded or not. Warnings from the values nested in this
+    /// one are included, with the key or index of the nested value prepended
+    /// to their [`Error::path`].
+    ///
+    /// ```
+    /// use toml_span::{Deserialize, de_helpers::TableHelper};
+    ///
+    /// struct Config;
+    ///
+    /// impl<'de> Deserialize<'de> for Config {
+    ///     fn deserialize(value: &mut toml_span::Value<'de>) -> Result<Self, toml_span::DeserError> {
+    ///         let th = TableHelper::new(value)?;
+    ///         th.finalize_warn_unknown(value)?;
+    ///         Ok(Self)
+    ///     }
+    /// }
+    ///
+    /// let mut value = toml_span::parse("unknown = 1").unwrap();
+    /// assert!(Config::deserialize(&mut value).is_ok());
+    /// assert_eq!(value.take_warnings().len(), 1);
+    /// ```
+    #[inline]
+    pub fn take_warnings(&mut self) -> Vec<Error> {
+        self.warnings
+            .take()
+            .map_or_else(Vec::new, |warnings| *warnings)
+    }
+
+    /// Adds warnings reported while deserializing the value
+    #[inline]
+    pub(crate) fn add_warnings(&mut self, warnings: Vec<Error>) {
+        if !warnings.is_empty() {
+            self.warnings
+                .get_or_insert_with(Default::default)
+                .extend(warnings);
+        }
+    }
+
     /// Takes the inner [`ValueInner`]
     ///
     /// This panics if the inner value has already been taken.
@@ -49,6 +136,32 @@ impl<'de> Value<'de> {
         self.value = Some(value);
     }
 
+    /// Maps a byte range of a parsed string to its [`Span`] in the document,
+    /// accounting for escapes and line endings that make the string differ
+    /// from its source
+    ///
+    /// `src` is the toml document the value was parsed from. This still works
+    /// after the string has been taken with [`Self::take`] or
+    /// [`Self::take_string`], so that errors found while parsing the string
+    /// can point at the exact characters responsible. Returns `None` if the
+    /// value was not parsed from a string, or the range is out of bounds.
+    ///
+    /// ```
+    /// let toml = r#"s = "tab\there""#;
+    /// let mut value = toml_span::parse(toml).unwrap();
+    /// let s = value.pointer_mut("/s").unwrap();
+    /// let string = s.take_string(None).unwrap();
+    ///
+    /// let range = string.find("here").unwrap()..string.len();
+    /// let span = s.string_span(toml, range).unwrap();
+    /// assert_eq!(&toml[span.start..span.end], "here");
+    /// ```
+    pub fn string_span(&self, src: &str, range: std::ops::Range<usize>) -> Option<Span> {
+        let (content, basic) = string_contents(src, self.span)?;
+        let (start, end) = map_string_range(content, basic, range)?;
+        Some(Span::new(self.span.start + start, self.span.start + end))
+    }
+
     /// Returns true if the value is a table and is non-empty
     #[inline]
     pub fn has_keys(&self) -> bool {
@@ -79,14 +192,13 @@ impl<'de> Value<'de> {
     pub fn take_string(&mut self, msg: Option<&'static str>) -> Result<Cow<'de, str>, Error> {
         match self.take() {
             ValueInner::String(s) => Ok(s),
-            other => Err(Error {
-                kind: ErrorKind::Wanted {
+            other => Err(Error::new(
+                ErrorKind::Wanted {
                     expected: msg.unwrap_or("a string"),
                     found: other.type_str(),
                 },
-                span: self.span,
-                line_info: None,
-            }),
+                self.span,
+            )),
         }
     }
 
@@ -126,6 +238,12 @@ impl<'de> Value<'de> {
         self.value.as_ref().and_then(|v| v.as_bool())
     }
 
+    /// Returns a [`Datetime`] if this is a [`ValueInner::Datetime`]
+    #[inline]
+    pub fn as_datetime(&self) -> Option<&Datetime> {
+        self.value.as_ref().and_then(|v| v.as_datetime())
+    }
+
     /// Uses JSON pointer-like syntax to lookup a specific [`Value`]
     ///
     /// The basic format is:
@@ -194,13 +312,106 @@ impl<'de> Value<'de> {
     }
 }
 
-fn parse_index(s: &str) -> Option<usize> {
+pub(crate) fn parse_index(s: &str) -> Option<usize> {
     if s.starts_with('+') || (s.starts_with('0') && s.len() != 1) {
         return None;
     }
     s.parse().ok()
 }
 
+/// Gets the source of the contents of the string with the span in the
+/// document, and whether it is a basic string, or `None` if the span isn't
+/// that of a string
+///
+/// String spans exclude the delimiters, and the newline that is trimmed from
+/// the start of multiline strings, so the delimiter is found before the span
+fn string_contents(src: &str, span: Span) -> Option<(&str, bool)> {
+    let content = src.get(span.start..span.end)?;
+    let before = src.get(..span.start)?;
+    let before = before
+        .strip_suffix('\n')
+        .map_or(before, |b| b.strip_suffix('\r').unwrap_or(b));
+
+    if before.len() == span.start {
+        match before.as_bytes().last() {
+            Some(b'"') => return Some((content, true)),
+            Some(b'\'') => return Some((content, false)),
+            _ => {}
+        }
+    } else if before.ends_with("\"\"\"") || before.ends_with("'''") {
+        return Some((content, before.ends_with('"')));
+    }
+
+    // Empty strings have the span of their opening quote
+    (matches!(content, "\"" | "'") && src[span.end..].starts_with(content)).then_some(("", true))
+}
+
+/// Maps a range of the decoded string to the range of its source, relative
+/// to the start of the string's contents
+fn map_string_range(
+    content: &str,
+    basic: bool,
+    range: std::ops::Range<usize>,
+) -> Option<(usize, usize)> {
+    if range.start > range.end {
+        return None;
+    }
+
+    let bytes = content.as_bytes();
+    let (mut raw, mut decoded) = (0, 0);
+    let mut start = None;
+    loop {
+        // The number of bytes of source, and of the decoded string, that the
+        // next character or escape is made up of
+        let (raw_len, decoded_len) = match bytes.get(raw) {
+            None => (0, 0),
+            Some(b'\\') if basic => match bytes.get(raw + 1)? {
+                b'u' => (6, char_len(content.get(raw + 2..raw + 6)?)),
+                b'U' => (10, char_len(content.get(raw + 2..raw + 10)?)),
+                b'x' => (4, char_len(content.get(raw + 2..raw + 4)?)),
+                b' ' | b'\t' | b'\r' | b'\n' => {
+                    // A line ending backslash, which trims all whitespace up
+                    // to the next non-whitespace character
+                    let rest = &content[raw + 1..];
+                    let trimmed = rest.trim_start_matches([' ', '\t', '\r', '\n']);
+                    (1 + rest.len() - trimmed.len(), 0)
+                }
+                _ => (2, 1),
+            },
+            Some(b'\r') if bytes.get(raw + 1) == Some(&b'\n') => (2, 1),
+            Some(_) => {
+                let len = content[raw..].chars().next()?.len_utf8();
+                (len, len)
+            }
+        };
+
+        // Line continuations are skipped so the range doesn't start with one
+        let at_end = raw_len == 0;
+        if start.is_none() && decoded >= range.start && (decoded_len > 0 || at_end) {
+            start = Some(raw);
+        }
+        if let Some(start) = start {
+            if decoded >= range.end {
+                return Some((start, raw));
+            }
+        }
+        if at_end {
+            return None;
+        }
+
+        raw += raw_len;
+        decoded += decoded_len;
+    }
+}
+
+/// The length of the character with the specified hex codepoint
+fn char_len(hex: &str) -> usize {
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .map_or(1, char::len_utf8)
+}
+
 impl<'de> AsRef<ValueInner<'de>> for Value<'de> {
     fn as_ref(&self) -> &ValueInner<'de> {
         self.value
@@ -263,17 +474,16 @@ impl PartialEq for Key<'_> {
 
 impl Eq for Key<'_> {}
 
-/// A toml table, always represented as a sorted map.
-///
-/// The original key ordering can be obtained by ordering the keys by their span
-pub type Table<'de> = std::collections::BTreeMap<Key<'de>, Value<'de>>;
+impl std::hash::Hash for Key<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
 /// A toml array
 pub type Array<'de> = Vec<Value<'de>>;
 
 /// The core value types that toml can deserialize to
-///
-/// Note that this library does not support datetime values that are part of the
-/// toml spec since I have no need of them, but could be added
 #[derive(Debug)]
 pub enum ValueInner<'de> {
     /// A string.
@@ -287,6 +497,9 @@ pub enum ValueInner<'de> {
     Float(f64),
     /// A boolean
     Boolean(bool),
+    /// A datetime, which can be an offset date-time, local date-time, local
+    /// date, or local time
+    Datetime(Datetime),
     /// An array
     Array(Array<'de>),
     /// A table
@@ -301,6 +514,7 @@ impl<'de> ValueInner<'de> {
             Self::Integer(..) => "integer",
             Self::Float(..) => "float",
             Self::Boolean(..) => "boolean",
+            Self::Datetime(..) => "datetime",
             Self::Array(..) => "array",
             Self::Table(..) => "table",
         }
@@ -365,4 +579,14 @@ impl<'de> ValueInner<'de> {
             None
         }
     }
+
+    /// Returns a [`Datetime`] if this is a [`Self::Datetime`]
+    #[inline]
+    pub fn as_datetime(&self) -> Option<&Datetime> {
+        if let ValueInner::Datetime(dt) = self {
+            Some(dt)
+        } else {
+            None
+        }
+    }
 }
@@ -0,0 +1,268 @@
+//! Contains [`Table`], the map of keys to values of a toml table, and its
+//! iterators
+
+use super::{Key, Value};
+use std::fmt;
+
+#[cfg(not(feature = "preserve_order"))]
+pub(crate) type Map<'de> = std::collections::BTreeMap<Key<'de>, Value<'de>>;
+#[cfg(feature = "preserve_order")]
+pub(crate) type Map<'de> = indexmap::IndexMap<Key<'de>, Value<'de>>;
+
+#[cfg(feature = "preserve_order")]
+use indexmap::map;
+#[cfg(not(feature = "preserve_order"))]
+use std::collections::btree_map as map;
+
+/// A toml table
+///
+/// By default the keys are sorted, if the `preserve_order` feature is enabled
+/// the keys are instead kept in the order they first appear in the document.
+/// Either way the API is the same, and removing a key keeps the order of the
+/// remaining keys.
+///
+/// The original key ordering can also be obtained by ordering the keys by
+/// their span.
+#[derive(Default)]
+pub struct Table<'de> {
+    pub(crate) map: Map<'de>,
+}
+
+impl<'de> Table<'de> {
+    /// Creates an empty table
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of keys in the table
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the table has no keys
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns true if the table contains the key
+    #[inline]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Gets the value for the key
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&Value<'de>> {
+        self.map.get(key)
+    }
+
+    /// Gets a mutable reference to the value for the key
+    #[inline]
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value<'de>> {
+        self.map.get_mut(key)
+    }
+
+    /// Gets the stored key and its value
+    #[inline]
+    pub fn get_key_value(&self, key: &str) -> Option<(&Key<'de>, &Value<'de>)> {
+        self.map.get_key_value(key)
+    }
+
+    /// Inserts a value, returning the previous value for the key if there was
+    /// one, in which case the stored key, and its position, is not updated
+    #[inline]
+    pub fn insert(&mut self, key: Key<'de>, value: Value<'de>) -> Option<Value<'de>> {
+        self.map.insert(key, value)
+    }
+
+    /// Removes a key from the table, returning its value if it existed
+    #[inline]
+    pub fn remove(&mut self, key: &str) -> Option<Value<'de>> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Removes a key from the table, returning the stored key and its value
+    /// if it existed
+    #[inline]
+    pub fn remove_entry(&mut self, key: &str) -> Option<(Key<'de>, Value<'de>)> {
+        #[cfg(not(feature = "preserve_order"))]
+        {
+            self.map.remove_entry(key)
+        }
+        #[cfg(feature = "preserve_order")]
+        {
+            self.map.shift_remove_entry(key)
+        }
+    }
+
+    /// Iterates over the keys and values of the table
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, 'de> {
+        Iter(self.map.iter())
+    }
+
+    /// Iterates over the keys and mutable values of the table
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, 'de> {
+        IterMut(self.map.iter_mut())
+    }
+
+    /// Iterates over the keys of the table
+    #[inline]
+    pub fn keys(&self) -> Keys<'_, 'de> {
+        Keys(self.map.keys())
+    }
+
+    /// Iterates over the values of the table
+    #[inline]
+    pub fn values(&self) -> Values<'_, 'de> {
+        Values(self.map.values())
+    }
+
+    /// Iterates over mutable references to the values of the table
+    #[inline]
+    pub fn values_mut(&mut self) -> ValuesMut<'_, 'de> {
+        ValuesMut(self.map.values_mut())
+    }
+
+    /// Consumes the table, returning its keys
+    #[inline]
+    pub fn into_keys(self) -> IntoKeys<'de> {
+        IntoKeys(self.map.into_keys())
+    }
+
+    /// Consumes the table, returning its values
+    #[inline]
+    pub fn into_values(self) -> IntoValues<'de> {
+        IntoValues(self.map.into_values())
+    }
+}
+
+impl fmt::Debug for Table<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl<'de> std::ops::Index<&str> for Table<'de> {
+    type Output = Value<'de>;
+
+    /// Gets the value for the key, panicking if it is not in the table
+    #[inline]
+    fn index(&self, key: &str) -> &Self::Output {
+        &self.map[key]
+    }
+}
+
+impl<'de> FromIterator<(Key<'de>, Value<'de>)> for Table<'de> {
+    fn from_iter<I: IntoIterator<Item = (Key<'de>, Value<'de>)>>(iter: I) -> Self {
+        Self {
+            map: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<'de> Extend<(Key<'de>, Value<'de>)> for Table<'de> {
+    fn extend<I: IntoIterator<Item = (Key<'de>, Value<'de>)>>(&mut self, iter: I) {
+        self.map.extend(iter);
+    }
+}
+
+impl<'de> IntoIterator for Table<'de> {
+    type Item = (Key<'de>, Value<'de>);
+    type IntoIter = IntoIter<'de>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self.map.into_iter())
+    }
+}
+
+impl<'a, 'de> IntoIterator for &'a Table<'de> {
+    type Item = (&'a Key<'de>, &'a Value<'de>);
+    type IntoIter = Iter<'a, 'de>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, 'de> IntoIterator for &'a mut Table<'de> {
+    type Item = (&'a Key<'de>, &'a mut Value<'de>);
+    type IntoIter = IterMut<'a, 'de>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Declares a wrapper around an iterator of the underlying map, so that the
+/// iterator types are the same regardless of the `preserve_order` feature
+macro_rules! iter {
+    ($(#[$attr:meta])* $name:ident<$($lt:lifetime),+> => $inner:ident<$($ilt:lifetime),*>, $item:ty) => {
+        $(#[$attr])*
+        pub struct $name<$($lt),+>(map::$inner<$($ilt,)* Key<'de>, Value<'de>>);
+
+        impl<$($lt),+> Iterator for $name<$($lt),+> {
+            type Item = $item;
+
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                self.0.next()
+            }
+
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.0.size_hint()
+            }
+        }
+
+        impl<$($lt),+> DoubleEndedIterator for $name<$($lt),+> {
+            #[inline]
+            fn next_back(&mut self) -> Option<Self::Item> {
+                self.0.next_back()
+            }
+        }
+
+        impl<$($lt),+> ExactSizeIterator for $name<$($lt),+> {}
+        impl<$($lt),+> std::iter::FusedIterator for $name<$($lt),+> {}
+    };
+}
+
+iter!(
+    /// An iterator over the keys and values of a [`Table`]
+    Iter<'a, 'de> => Iter<'a>, (&'a Key<'de>, &'a Value<'de>)
+);
+iter!(
+    /// An iterator over the keys and mutable values of a [`Table`]
+    IterMut<'a, 'de> => IterMut<'a>, (&'a Key<'de>, &'a mut Value<'de>)
+);
+iter!(
+    /// An owning iterator over the keys and values of a [`Table`]
+    IntoIter<'de> => IntoIter<>, (Key<'de>, Value<'de>)
+);
+iter!(
+    /// An iterator over the keys of a [`Table`]
+    Keys<'a, 'de> => Keys<'a>, &'a Key<'de>
+);
+iter!(
+    /// An iterator over the values of a [`Table`]
+    Values<'a, 'de> => Values<'a>, &'a Value<'de>
+);
+iter!(
+    /// An iterator over mutable references to the values of a [`Table`]
+    ValuesMut<'a, 'de> => ValuesMut<'a>, &'a mut Value<'de>
+);
+iter!(
+    /// An owning iterator over the keys of a [`Table`]
+    IntoKeys<'de> => IntoKeys<>, Key<'de>
+);
+iter!(
+    /// An owning iterator over the values of a [`Table`]
+    IntoValues<'de> => IntoValues<>, Value<'de>
+);